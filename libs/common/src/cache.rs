@@ -77,6 +77,13 @@ impl RedisPool {
         Ok(value)
     }
 
+    /// Get a value from Redis by key and delete the key atomically
+    pub async fn get_and_delete(&self, key: &str) -> Result<Option<String>> {
+        let mut conn = self.get_connection().await?;
+        let value: Option<String> = redis::cmd("GETDEL").arg(key).query_async(&mut conn).await?;
+        Ok(value)
+    }
+
     /// Delete a key from Redis
     pub async fn delete(&self, key: &str) -> Result<()> {
         let mut conn = self.get_connection().await?;
@@ -84,6 +91,26 @@ impl RedisPool {
         Ok(())
     }
 
+    /// Run a Lua script atomically on the given keys and arguments
+    pub async fn eval<T: redis::FromRedisValue>(
+        &self,
+        script: &str,
+        keys: &[&str],
+        args: &[&str],
+    ) -> Result<T> {
+        let mut conn = self.get_connection().await?;
+        let script = redis::Script::new(script);
+        let mut invocation = script.prepare_invoke();
+        for key in keys {
+            invocation.key(*key);
+        }
+        for arg in args {
+            invocation.arg(*arg);
+        }
+        let value = invocation.invoke_async(&mut conn).await?;
+        Ok(value)
+    }
+
     /// Check if Redis is reachable
     pub async fn health_check(&self) -> Result<bool> {
         let mut conn = self.get_connection().await?;
@@ -0,0 +1,15 @@
+-- Create KnownLoginDevice table to detect logins from unfamiliar devices, IPs and countries
+CREATE TABLE known_login_devices (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    device_hash VARCHAR(64) NOT NULL,
+    ip_address VARCHAR(45) NOT NULL,
+    country VARCHAR(2),
+    user_agent TEXT,
+    first_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, device_hash, ip_address)
+);
+
+-- Create indexes for better performance
+CREATE INDEX idx_known_login_devices_user_id ON known_login_devices(user_id);
@@ -0,0 +1,38 @@
+//! Read-through caching of JSON values in Redis
+
+use anyhow::Result;
+use common::cache::RedisPool;
+use serde::{Serialize, de::DeserializeOwned};
+use tracing::warn;
+
+/// Read a value from the cache, loading and caching it on a miss
+///
+/// Cache failures are logged and fall back to the database.
+pub async fn cached<T: Serialize + DeserializeOwned>(
+    cache: &RedisPool,
+    key: &str,
+    ttl: u64,
+    load: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    match cache.get(key).await {
+        Ok(Some(value)) => match serde_json::from_str(&value) {
+            Ok(value) => return Ok(value),
+            Err(e) => warn!("Ignoring invalid cached value for {}: {}", key, e),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("Failed to read {} from cache: {}", key, e),
+    }
+
+    let value = load.await?;
+
+    match serde_json::to_string(&value) {
+        Ok(json) => {
+            if let Err(e) = cache.set(key, &json, Some(ttl)).await {
+                warn!("Failed to cache {}: {}", key, e);
+            }
+        }
+        Err(e) => warn!("Failed to serialize {} for caching: {}", key, e),
+    }
+
+    Ok(value)
+}
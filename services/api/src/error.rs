@@ -1,9 +1,9 @@
 //! Custom error types for the API service
 
 use axum::{
+    Json,
     http::StatusCode,
     response::{IntoResponse, Response},
-    Json,
 };
 use serde_json::json;
 use thiserror::Error;
@@ -15,10 +15,22 @@ pub enum ApiError {
     #[error("Unauthorized")]
     Unauthorized,
 
+    /// Authenticated but not allowed to perform the operation
+    #[error("Forbidden")]
+    Forbidden,
+
+    /// Resource does not exist or is not visible to the caller
+    #[error("Not found: {0}")]
+    NotFound(String),
+
     /// Bad request with message
     #[error("Bad request: {0}")]
     BadRequest(String),
 
+    /// Too many attempts, retry later
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
+
     /// Internal server error
     #[error("Internal server error")]
     InternalServerError,
@@ -32,7 +44,10 @@ impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let (status, error_message) = match self {
             ApiError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized".to_string()),
+            ApiError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden".to_string()),
+            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            ApiError::TooManyRequests(msg) => (StatusCode::TOO_MANY_REQUESTS, msg),
             ApiError::InternalServerError => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal server error".to_string(),
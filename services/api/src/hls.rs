@@ -0,0 +1,281 @@
+//! HLS playlists built from the renditions recorded for media items
+//!
+//! Playlists carry URLs signed for the caller, so they are generated per
+//! request; the rendition and segment lists they are built from are cached
+//! in Redis.
+
+use anyhow::Result;
+use common::cache::RedisPool;
+use std::fmt::Write;
+use uuid::Uuid;
+
+use crate::{
+    cache::cached,
+    models::hls::{Rendition, RenditionSegment},
+    repositories::rendition::RenditionRepository,
+};
+
+/// Rendition name used to sign master playlist URLs
+pub const HLS_RENDITION: &str = "hls";
+
+/// Content type of HLS playlists
+pub const PLAYLIST_CONTENT_TYPE: &str = "application/vnd.apple.mpegurl";
+
+/// HLS configuration
+#[derive(Debug, Clone)]
+pub struct HlsConfig {
+    /// Time in seconds rendition data is cached in Redis
+    pub cache_ttl: u64,
+}
+
+impl HlsConfig {
+    /// Create a new HlsConfig from environment variables
+    ///
+    /// # Environment Variables
+    /// - `HLS_CACHE_TTL`: Time rendition data is cached in seconds (default: 300)
+    pub fn from_env() -> Self {
+        let cache_ttl = std::env::var("HLS_CACHE_TTL")
+            .unwrap_or_else(|_| "300".to_string()) // 5 minutes
+            .parse()
+            .unwrap_or(300);
+
+        HlsConfig { cache_ttl }
+    }
+}
+
+/// Cached access to the renditions of media items
+#[derive(Clone)]
+pub struct HlsCatalog {
+    repository: RenditionRepository,
+    cache: RedisPool,
+    config: HlsConfig,
+}
+
+impl HlsCatalog {
+    /// Create a new catalog
+    pub fn new(repository: RenditionRepository, cache: RedisPool, config: HlsConfig) -> Self {
+        Self {
+            repository,
+            cache,
+            config,
+        }
+    }
+
+    /// Get the renditions of a media item
+    pub async fn renditions(&self, media_id: Uuid) -> Result<Vec<Rendition>> {
+        let key = format!("hls:{}:renditions", media_id);
+        cached(
+            &self.cache,
+            &key,
+            self.config.cache_ttl,
+            self.repository.get_renditions(media_id),
+        )
+        .await
+    }
+
+    /// Get the segments of a rendition
+    pub async fn segments(&self, media_id: Uuid, name: &str) -> Result<Vec<RenditionSegment>> {
+        let key = format!("hls:{}:{}:segments", media_id, name);
+        cached(
+            &self.cache,
+            &key,
+            self.config.cache_ttl,
+            self.repository.get_segments(media_id, name),
+        )
+        .await
+    }
+}
+
+/// Build the master playlist listing every rendition
+///
+/// Variant URIs are relative to the master playlist; `query` returns the
+/// signed query string for a rendition.
+pub fn master_playlist(renditions: &[Rendition], query: impl Fn(&Rendition) -> String) -> String {
+    let mut playlist = String::from("#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-INDEPENDENT-SEGMENTS\n");
+
+    for rendition in renditions {
+        let mut attributes = format!("BANDWIDTH={}", rendition.bandwidth);
+        if let Some(average_bandwidth) = rendition.average_bandwidth {
+            let _ = write!(attributes, ",AVERAGE-BANDWIDTH={}", average_bandwidth);
+        }
+        if let (Some(width), Some(height)) = (rendition.width, rendition.height) {
+            let _ = write!(attributes, ",RESOLUTION={}x{}", width, height);
+        }
+        if let Some(frame_rate) = rendition.frame_rate {
+            let _ = write!(attributes, ",FRAME-RATE={:.3}", frame_rate);
+        }
+        let _ = write!(attributes, ",CODECS=\"{}\"", rendition.codecs);
+
+        let _ = writeln!(playlist, "#EXT-X-STREAM-INF:{}", attributes);
+        let _ = writeln!(
+            playlist,
+            "{}/index.m3u8?{}",
+            rendition.name,
+            query(rendition)
+        );
+    }
+
+    playlist
+}
+
+/// Build the playlist of a rendition's segments
+///
+/// Segment URIs are relative to the variant playlist and share its signed
+/// `query`.
+pub fn variant_playlist(
+    rendition: &Rendition,
+    segments: &[RenditionSegment],
+    query: &str,
+) -> String {
+    let target_duration = segments
+        .iter()
+        .map(|segment| segment.duration.ceil() as i32)
+        .fold(rendition.target_duration, i32::max);
+    let media_sequence = segments.first().map_or(0, |segment| segment.sequence);
+
+    let mut playlist = String::from("#EXTM3U\n#EXT-X-VERSION:3\n");
+    let _ = writeln!(playlist, "#EXT-X-TARGETDURATION:{}", target_duration);
+    let _ = writeln!(playlist, "#EXT-X-MEDIA-SEQUENCE:{}", media_sequence);
+    playlist.push_str("#EXT-X-PLAYLIST-TYPE:VOD\n");
+
+    for segment in segments {
+        let _ = writeln!(playlist, "#EXTINF:{:.3},", segment.duration);
+        let _ = writeln!(
+            playlist,
+            "{}.{}?{}",
+            segment.sequence,
+            segment.extension(),
+            query
+        );
+    }
+
+    playlist.push_str("#EXT-X-ENDLIST\n");
+    playlist
+}
+
+/// Total playback duration of a list of segments in seconds
+pub fn total_duration(segments: &[RenditionSegment]) -> f64 {
+    segments.iter().map(|segment| segment.duration).sum()
+}
+
+/// Parse a segment file name such as `12.ts` into its sequence number
+pub fn parse_segment_name(name: &str) -> Option<i32> {
+    name.split_once('.')?.0.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MASTER_FIXTURE: &str = "\
+#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-INDEPENDENT-SEGMENTS
+#EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360,CODECS=\"avc1.4d401e,mp4a.40.2\"
+360p/index.m3u8?sig=360p
+#EXT-X-STREAM-INF:BANDWIDTH=5000000,AVERAGE-BANDWIDTH=4500000,RESOLUTION=1920x1080,FRAME-RATE=29.970,CODECS=\"avc1.640028,mp4a.40.2\"
+1080p/index.m3u8?sig=1080p
+";
+
+    const VARIANT_FIXTURE: &str = "\
+#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-TARGETDURATION:7
+#EXT-X-MEDIA-SEQUENCE:0
+#EXT-X-PLAYLIST-TYPE:VOD
+#EXTINF:6.000,
+0.ts?sig=360p
+#EXTINF:6.006,
+1.ts?sig=360p
+#EXTINF:2.500,
+2.ts?sig=360p
+#EXT-X-ENDLIST
+";
+
+    fn renditions() -> Vec<Rendition> {
+        vec![
+            Rendition {
+                name: "360p".to_string(),
+                bandwidth: 800_000,
+                average_bandwidth: None,
+                width: Some(640),
+                height: Some(360),
+                frame_rate: None,
+                codecs: "avc1.4d401e,mp4a.40.2".to_string(),
+                target_duration: 6,
+            },
+            Rendition {
+                name: "1080p".to_string(),
+                bandwidth: 5_000_000,
+                average_bandwidth: Some(4_500_000),
+                width: Some(1920),
+                height: Some(1080),
+                frame_rate: Some(29.97),
+                codecs: "avc1.640028,mp4a.40.2".to_string(),
+                target_duration: 6,
+            },
+        ]
+    }
+
+    fn segments() -> Vec<RenditionSegment> {
+        [6.0, 6.006, 2.5]
+            .into_iter()
+            .enumerate()
+            .map(|(sequence, duration)| RenditionSegment {
+                sequence: sequence as i32,
+                duration,
+                s3_key: format!("hls/movie/360p/segment{}.ts", sequence),
+            })
+            .collect()
+    }
+
+    /// Split a playlist into its tags and URIs, checking the basic structure
+    fn parse(playlist: &str) -> (Vec<&str>, Vec<&str>) {
+        let lines: Vec<&str> = playlist.lines().collect();
+        assert_eq!(lines.first(), Some(&"#EXTM3U"));
+        assert!(lines.iter().all(|line| !line.trim().is_empty()));
+
+        lines.into_iter().partition(|line| line.starts_with('#'))
+    }
+
+    #[test]
+    fn test_master_playlist() {
+        let playlist =
+            master_playlist(&renditions(), |rendition| format!("sig={}", rendition.name));
+        assert_eq!(playlist, MASTER_FIXTURE);
+
+        let (tags, uris) = parse(&playlist);
+        assert_eq!(
+            tags.iter()
+                .filter(|tag| tag.starts_with("#EXT-X-STREAM-INF:"))
+                .count(),
+            uris.len()
+        );
+        assert!(uris.iter().all(|uri| uri.contains("/index.m3u8?")));
+    }
+
+    #[test]
+    fn test_variant_playlist() {
+        let renditions = renditions();
+        let segments = segments();
+        let playlist = variant_playlist(&renditions[0], &segments, "sig=360p");
+        assert_eq!(playlist, VARIANT_FIXTURE);
+
+        let (tags, uris) = parse(&playlist);
+        assert_eq!(
+            tags.iter()
+                .filter(|tag| tag.starts_with("#EXTINF:"))
+                .count(),
+            uris.len()
+        );
+        assert_eq!(tags.last(), Some(&"#EXT-X-ENDLIST"));
+        assert!((total_duration(&segments) - 14.506).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_segment_name() {
+        assert_eq!(parse_segment_name("12.ts"), Some(12));
+        assert_eq!(parse_segment_name("index.m3u8"), None);
+        assert_eq!(parse_segment_name("12"), None);
+    }
+}
@@ -0,0 +1,118 @@
+//! Live feed of comment changes for server-sent events
+//!
+//! Comment writes announce the changed comment on a Postgres channel when
+//! they commit. Every API instance listens on that channel, loads each
+//! changed comment once and fans it out to its own subscribers, so clients
+//! see changes made through any instance.
+
+use sqlx::{PgPool, postgres::PgListener};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{error, warn};
+
+use crate::{
+    models::comment::{
+        COMMENT_CHANNEL, Comment, CommentEventKind, CommentNotification, CommentStatus,
+    },
+    repositories::comment::CommentRepository,
+};
+
+/// Number of events buffered for slow subscribers before they skip ahead
+const FEED_CAPACITY: usize = 256;
+
+/// Delay before reconnecting after the listener connection failed
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Change to a comment as seen by live subscribers
+#[derive(Debug, Clone)]
+pub struct CommentEvent {
+    pub kind: CommentEventKind,
+    /// Changed comment, without its text once removed
+    pub comment: Comment,
+}
+
+/// Fan-out of comment changes to the subscribers of this instance
+#[derive(Clone)]
+pub struct CommentFeed {
+    sender: broadcast::Sender<CommentEvent>,
+}
+
+impl CommentFeed {
+    /// Create the feed and start listening for comment changes
+    pub fn start(pool: PgPool, repository: CommentRepository) -> Self {
+        let (sender, _) = broadcast::channel(FEED_CAPACITY);
+        tokio::spawn(listen(pool, repository, sender.clone()));
+
+        Self { sender }
+    }
+
+    /// Subscribe to the changes of all comments
+    pub fn subscribe(&self) -> broadcast::Receiver<CommentEvent> {
+        self.sender.subscribe()
+    }
+}
+
+/// Forward comment changes from Postgres to the subscribers, reconnecting on failure
+async fn listen(
+    pool: PgPool,
+    repository: CommentRepository,
+    sender: broadcast::Sender<CommentEvent>,
+) {
+    loop {
+        match PgListener::connect_with(&pool).await {
+            Ok(mut listener) => match listener.listen(COMMENT_CHANNEL).await {
+                Ok(()) => loop {
+                    let notification = match listener.recv().await {
+                        Ok(notification) => notification,
+                        Err(e) => {
+                            error!("Comment feed listener failed: {}", e);
+                            break;
+                        }
+                    };
+                    if sender.receiver_count() == 0 {
+                        continue;
+                    }
+
+                    match serde_json::from_str::<CommentNotification>(notification.payload()) {
+                        Ok(change) => publish(&repository, &sender, change).await,
+                        Err(e) => warn!("Ignoring malformed comment notification: {}", e),
+                    }
+                },
+                Err(e) => error!("Failed to listen for comment changes: {}", e),
+            },
+            Err(e) => error!("Failed to connect comment feed listener: {}", e),
+        }
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Load a changed comment and send it to the subscribers
+///
+/// Hidden and deleted comments are only ever sent as removals without their
+/// text, so subscribers never see content they could not list.
+async fn publish(
+    repository: &CommentRepository,
+    sender: &broadcast::Sender<CommentEvent>,
+    change: CommentNotification,
+) {
+    let mut comment = match repository.get(change.comment_id).await {
+        Ok(Some(comment)) => comment,
+        Ok(None) => return,
+        Err(e) => {
+            error!("Failed to load comment {}: {}", change.comment_id, e);
+            return;
+        }
+    };
+
+    let removed = comment.status == CommentStatus::Hidden || comment.deleted_at.is_some();
+    let kind = if removed {
+        comment.body = None;
+        CommentEventKind::Removed
+    } else {
+        change.kind
+    };
+
+    // Sending only fails when nobody is subscribed any more
+    let _ = sender.send(CommentEvent { kind, comment });
+}
@@ -2,18 +2,28 @@ use anyhow::Result;
 use tracing::{Level, info};
 use tracing_subscriber::FmtSubscriber;
 
+mod cache;
 mod error;
+mod hls;
+mod live;
 mod middleware;
 mod models;
+mod playback;
+mod recommender;
 mod repositories;
 mod routes;
 mod state;
+mod storage;
+mod streaming;
+mod tus;
 
 use crate::repositories::media;
 
 use axum::Router;
+use common::cache::{RedisConfig, RedisPool};
 use common::database::{DatabaseConfig, init_pool};
 use sqlx::PgPool;
+use std::net::SocketAddr;
 use tokio::net::TcpListener;
 
 use crate::{
@@ -48,13 +58,123 @@ async fn main() -> Result<()> {
     // Initialize repositories
     let user_repository = UserRepository::new(pool.clone());
     let session_repository = SessionRepository::new(pool.clone());
+    let profile_repository = repositories::profile::ProfileRepository::new(pool.clone());
+    let parental_repository = repositories::parental::ParentalControlRepository::new(pool.clone());
     let media_repository = media::MediaRepository::new(pool.clone());
+    let upload_repository = repositories::upload::UploadRepository::new(pool.clone());
+    let tus_repository = repositories::tus::TusRepository::new(pool.clone());
+    let history_repository = repositories::history::HistoryRepository::new(pool.clone());
+    let collection_repository = repositories::collection::CollectionRepository::new(pool.clone());
+    let series_repository = repositories::series::SeriesRepository::new(pool.clone());
+    let taxonomy_repository = repositories::taxonomy::TaxonomyRepository::new(pool.clone());
+    let review_repository = repositories::review::ReviewRepository::new(
+        pool.clone(),
+        models::review::ReviewConfig::from_env(),
+    );
+    let comment_repository = repositories::comment::CommentRepository::new(pool.clone());
+    let notification_repository =
+        repositories::notification::NotificationRepository::new(pool.clone());
+    let progress_repository = repositories::progress::ProgressRepository::new(
+        pool.clone(),
+        models::progress::ProgressConfig::from_env(),
+    );
+
+    // Initialize object storage
+    let storage = storage::Storage::new(storage::StorageConfig::from_env()).await;
+    let tus_store = tus::TusStore::new(storage.clone(), tus::TusConfig::from_env());
+    // Initialize Redis for caching playback data
+    let redis_pool = RedisPool::new(&RedisConfig::from_env()?).await?;
+    let hls = hls::HlsCatalog::new(
+        repositories::rendition::RenditionRepository::new(pool.clone()),
+        redis_pool.clone(),
+        hls::HlsConfig::from_env(),
+    );
+
+    let playback = playback::PlaybackSigner::new(
+        playback::PlaybackConfig::from_env().map_err(|e| anyhow::anyhow!(e))?,
+    );
+
+    // Rebuild media similarities for recommendations periodically
+    let recommender = recommender::Recommender::new(
+        repositories::recommendation::RecommendationRepository::new(
+            pool.clone(),
+            models::recommendation::RecommendationConfig::from_env(),
+        ),
+        redis_pool,
+    );
+    tokio::spawn(recommender.clone().run_refresh_loop());
+
+    // Push comment changes to live subscribers
+    let comment_feed = live::CommentFeed::start(pool.clone(), comment_repository.clone());
+
+    // Remove expired resumable uploads every hour
+    {
+        let tus_repository = tus_repository.clone();
+        let tus_store = tus_store.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                match tus_repository.delete_expired().await {
+                    Ok(uploads) => {
+                        for upload in uploads {
+                            tus_store.remove(&upload).await;
+                        }
+                    }
+                    Err(e) => tracing::error!("Failed to delete expired uploads: {}", e),
+                }
+            }
+        });
+    }
+
+    // Discard expired direct-to-storage uploads every hour
+    {
+        let upload_repository = upload_repository.clone();
+        let storage = storage.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                match upload_repository.delete_expired().await {
+                    Ok(uploads) => {
+                        for upload in uploads {
+                            storage
+                                .discard_upload(
+                                    &upload.s3_key,
+                                    upload.multipart_upload_id.as_deref(),
+                                )
+                                .await;
+                        }
+                    }
+                    Err(e) => tracing::error!("Failed to delete expired direct uploads: {}", e),
+                }
+            }
+        });
+    }
 
     let app_state = AppState {
         db_pool: pool,
         user_repository,
+        profile_repository,
+        parental_repository,
         session_repository,
         media_repository,
+        progress_repository,
+        history_repository,
+        collection_repository,
+        series_repository,
+        taxonomy_repository,
+        review_repository,
+        comment_repository,
+        notification_repository,
+        upload_repository,
+        storage,
+        playback,
+        hls,
+        comment_feed,
+        recommender,
+        tus_repository,
+        tus_store,
     };
 
     // Start the web server
@@ -63,7 +183,11 @@ async fn main() -> Result<()> {
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3001").await?;
     info!("API service listening on 0.0.0.0:3001");
 
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
@@ -2,7 +2,7 @@
 
 use axum::{
     extract::State,
-    http::{Request, StatusCode},
+    http::{HeaderMap, Method, Request, StatusCode},
     middleware::Next,
     response::Response,
 };
@@ -12,7 +12,7 @@ use std::env;
 use tracing::error;
 use uuid::Uuid;
 
-use crate::{error::ApiError, state::AppState};
+use crate::{error::ApiError, models::profile::MaturityLevel, state::AppState};
 
 /// JWT claims structure
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,6 +29,22 @@ pub struct Claims {
     pub exp: u64,
     /// Token type (access or refresh)
     pub token_type: TokenType,
+    /// Admin acting on behalf of the subject, set for impersonation tokens
+    #[serde(default)]
+    pub act: Option<Actor>,
+    /// Whether the token is limited to read operations
+    #[serde(default)]
+    pub read_only: bool,
+    /// Viewer profile the token was issued for
+    #[serde(default)]
+    pub profile_id: Option<Uuid>,
+}
+
+/// Actor claim identifying who is acting on behalf of the subject
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Actor {
+    /// ID of the acting user
+    pub sub: Uuid,
 }
 
 /// Token type enum
@@ -46,6 +62,58 @@ pub struct AuthUser {
     pub id: Uuid,
     pub roles: Vec<String>,
     pub permissions: Vec<String>,
+    /// ID of the admin impersonating this user, if any
+    pub actor_id: Option<Uuid>,
+    /// Whether the user may only perform read operations
+    pub read_only: bool,
+    /// Selected viewer profile; viewing data of the account itself if none
+    pub profile_id: Option<Uuid>,
+    /// Maximum maturity rating of media the user may see; unrestricted if
+    /// none
+    pub max_rating: Option<MaturityLevel>,
+}
+
+impl AuthUser {
+    /// Check whether the user has the admin role
+    pub fn is_admin(&self) -> bool {
+        self.roles.iter().any(|role| role == "admin")
+    }
+
+    /// Refuse operations reserved for admins
+    pub fn ensure_admin(&self) -> Result<(), ApiError> {
+        if !self.is_admin() {
+            return Err(ApiError::Forbidden);
+        }
+        Ok(())
+    }
+
+    /// Media ratings the user may see, or `None` if unrestricted
+    pub fn allowed_ratings(&self) -> Option<Vec<String>> {
+        self.max_rating.and_then(MaturityLevel::allowed_ratings)
+    }
+
+    /// Refuse anything but GET and HEAD requests for read-only tokens
+    pub fn ensure_method_allowed(&self, method: &Method) -> Result<(), ApiError> {
+        if self.read_only && !matches!(*method, Method::GET | Method::HEAD) {
+            return Err(ApiError::Forbidden);
+        }
+        Ok(())
+    }
+
+    /// Refuse sensitive operations while an admin impersonates the user
+    ///
+    /// Impersonated sessions may browse, play media and record progress and
+    /// watch history to reproduce issues. Every other write is refused:
+    /// profiles, parental controls, history settings and deletions,
+    /// collections and their items and sharing, notifications, reviews and
+    /// comments, genres and moderation, and media edits, visibility,
+    /// maturity ratings, deletion, restores, refreshes and uploads.
+    pub fn ensure_not_impersonated(&self) -> Result<(), ApiError> {
+        if self.actor_id.is_some() {
+            return Err(ApiError::Forbidden);
+        }
+        Ok(())
+    }
 }
 
 /// JWT configuration
@@ -105,9 +173,45 @@ pub async fn auth_middleware(
     mut req: Request<axum::body::Body>,
     next: Next,
 ) -> Result<Response, ApiError> {
+    let user = authenticate_with_limits(&state, req.headers()).await?;
+    user.ensure_method_allowed(req.method())?;
+
+    // Insert the user into the request extensions
+    req.extensions_mut().insert(user);
+
+    // Call the next service
+    let response = next.run(req).await;
+
+    Ok(response)
+}
+
+/// Authenticate a request and resolve the user's maturity limit
+///
+/// The limit is looked up on every request, so parental control changes
+/// apply to tokens that were already issued.
+pub async fn authenticate_with_limits(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<AuthUser, ApiError> {
+    let mut user = authenticate(headers)?;
+    user.max_rating = state
+        .parental_repository
+        .max_rating(user.id, user.profile_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get maturity limit: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(user)
+}
+
+/// Authenticate a request from its bearer access token
+///
+/// The maturity limit is left unresolved; see `authenticate_with_limits`.
+pub fn authenticate(headers: &HeaderMap) -> Result<AuthUser, ApiError> {
     // Extract the Authorization header
-    let auth_header = req
-        .headers()
+    let auth_header = headers
         .get(axum::http::header::AUTHORIZATION)
         .and_then(|header| header.to_str().ok())
         .ok_or(ApiError::Unauthorized)?;
@@ -140,23 +244,66 @@ pub async fn auth_middleware(
             ApiError::Unauthorized
         })?;
 
+    // Only access tokens may be used to call the API
+    if token_data.claims.token_type != TokenType::Access {
+        return Err(ApiError::Unauthorized);
+    }
+
     // Create authenticated user from claims
-    let user = AuthUser {
+    Ok(AuthUser {
         id: token_data.claims.sub,
         roles: token_data.claims.roles,
         permissions: token_data.claims.permissions,
-    };
-
-    // Insert the user into the request extensions
-    req.extensions_mut().insert(user);
-
-    // Call the next service
-    let response = next.run(req).await;
-
-    Ok(response)
+        actor_id: token_data.claims.act.map(|actor| actor.sub),
+        read_only: token_data.claims.read_only,
+        profile_id: token_data.claims.profile_id,
+        max_rating: None,
+    })
 }
 
 /// Extract the authenticated user from the request extensions
 pub fn get_current_user<B>(req: &Request<B>) -> Option<AuthUser> {
     req.extensions().get::<AuthUser>().cloned()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(actor_id: Option<Uuid>) -> AuthUser {
+        AuthUser {
+            id: Uuid::new_v4(),
+            roles: vec!["user".to_string()],
+            permissions: Vec::new(),
+            actor_id,
+            read_only: false,
+            profile_id: None,
+            max_rating: None,
+        }
+    }
+
+    #[test]
+    fn test_ensure_not_impersonated() {
+        assert!(user(None).ensure_not_impersonated().is_ok());
+        assert!(matches!(
+            user(Some(Uuid::new_v4())).ensure_not_impersonated(),
+            Err(ApiError::Forbidden)
+        ));
+    }
+
+    #[test]
+    fn test_ensure_method_allowed() {
+        let mut user = user(None);
+        assert!(user.ensure_method_allowed(&Method::PUT).is_ok());
+
+        user.read_only = true;
+        assert!(user.ensure_method_allowed(&Method::GET).is_ok());
+        assert!(user.ensure_method_allowed(&Method::HEAD).is_ok());
+        for method in [Method::POST, Method::PUT, Method::PATCH, Method::DELETE] {
+            assert!(matches!(
+                user.ensure_method_allowed(&method),
+                Err(ApiError::Forbidden)
+            ));
+        }
+    }
+}
@@ -4,7 +4,20 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+pub mod collection;
+pub mod comment;
+pub mod history;
+pub mod hls;
 pub mod media;
+pub mod notification;
+pub mod parental;
+pub mod profile;
+pub mod progress;
+pub mod recommendation;
+pub mod review;
+pub mod series;
+pub mod taxonomy;
+pub mod upload;
 
 /// Request for user registration
 #[derive(Deserialize)]
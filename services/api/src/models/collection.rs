@@ -0,0 +1,156 @@
+//! Collection models for the API service
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::media::MediaItem;
+
+/// Maximum length of a collection name
+const MAX_NAME_LENGTH: usize = 100;
+
+/// Kind of collection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionKind {
+    /// Built-in "Watch later" list
+    WatchLater,
+    /// Built-in "Favorites" list
+    Favorites,
+    /// Named collection created by the user
+    Custom,
+}
+
+impl CollectionKind {
+    /// Built-in collections every user has
+    pub const BUILT_IN: [CollectionKind; 2] =
+        [CollectionKind::WatchLater, CollectionKind::Favorites];
+
+    /// Database representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CollectionKind::WatchLater => "watch_later",
+            CollectionKind::Favorites => "favorites",
+            CollectionKind::Custom => "custom",
+        }
+    }
+
+    /// Parse the database representation
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "watch_later" => Some(CollectionKind::WatchLater),
+            "favorites" => Some(CollectionKind::Favorites),
+            "custom" => Some(CollectionKind::Custom),
+            _ => None,
+        }
+    }
+
+    /// Name of a built-in collection
+    pub fn default_name(&self) -> &'static str {
+        match self {
+            CollectionKind::WatchLater => "Watch later",
+            CollectionKind::Favorites => "Favorites",
+            CollectionKind::Custom => "Collection",
+        }
+    }
+
+    /// Whether the collection can be renamed and deleted
+    pub fn is_custom(&self) -> bool {
+        *self == CollectionKind::Custom
+    }
+}
+
+/// Collection model
+#[derive(Debug, Clone, Serialize)]
+pub struct Collection {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Viewer profile the collection belongs to
+    pub profile_id: Option<Uuid>,
+    pub name: String,
+    pub kind: CollectionKind,
+    pub item_count: i64,
+    /// Token of the share link, only shown to the owner
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub share_token: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Collection with its items in order
+#[derive(Debug, Clone, Serialize)]
+pub struct CollectionDetail {
+    #[serde(flatten)]
+    pub collection: Collection,
+    pub items: Vec<MediaItem>,
+}
+
+/// Request for creating or renaming a collection
+#[derive(Debug, Clone, Deserialize)]
+pub struct CollectionNameRequest {
+    pub name: String,
+}
+
+impl CollectionNameRequest {
+    /// Validated, trimmed collection name
+    pub fn name(&self) -> Result<String, String> {
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+            return Err(format!(
+                "Collection name must be between 1 and {} characters",
+                MAX_NAME_LENGTH
+            ));
+        }
+        Ok(name.to_string())
+    }
+}
+
+/// Request for adding a media item to a collection
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddCollectionItemRequest {
+    pub media_id: Uuid,
+    /// 1-based position to insert at (default: at the end)
+    pub position: Option<i32>,
+}
+
+/// Request for reordering a collection
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReorderCollectionRequest {
+    /// Every media item of the collection in the new order
+    pub media_ids: Vec<Uuid>,
+}
+
+/// Share link of a collection
+#[derive(Debug, Clone, Serialize)]
+pub struct ShareCollectionResponse {
+    pub share_token: String,
+    pub url: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collection_kind_round_trip() {
+        for kind in [
+            CollectionKind::WatchLater,
+            CollectionKind::Favorites,
+            CollectionKind::Custom,
+        ] {
+            assert_eq!(CollectionKind::parse(kind.as_str()), Some(kind));
+        }
+        assert!(!CollectionKind::Favorites.is_custom());
+    }
+
+    #[test]
+    fn test_collection_name() {
+        let request = |name: &str| CollectionNameRequest {
+            name: name.to_string(),
+        };
+
+        assert_eq!(request("  Sci-fi  ").name().unwrap(), "Sci-fi");
+        assert!(request("   ").name().is_err());
+        assert!(request(&"x".repeat(101)).name().is_err());
+    }
+}
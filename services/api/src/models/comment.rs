@@ -0,0 +1,281 @@
+//! Comment models for the API service
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Maximum length of a comment
+pub const MAX_COMMENT_LENGTH: usize = 2000;
+
+/// Maximum number of users notified for mentions in one comment
+pub const MAX_MENTIONS: usize = 10;
+
+/// Postgres channel comment changes are announced on
+pub const COMMENT_CHANNEL: &str = "media_comments";
+
+/// Moderation state of a comment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentStatus {
+    /// Shown to everyone who can see the item
+    Published,
+    /// Removed by a moderator; only the author still sees it
+    Hidden,
+}
+
+impl CommentStatus {
+    /// Database representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommentStatus::Published => "published",
+            CommentStatus::Hidden => "hidden",
+        }
+    }
+
+    /// Parse the database representation
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "published" => Some(CommentStatus::Published),
+            "hidden" => Some(CommentStatus::Hidden),
+            _ => None,
+        }
+    }
+}
+
+/// Comment on a media item
+#[derive(Debug, Clone, Serialize)]
+pub struct Comment {
+    pub id: Uuid,
+    pub media_id: Uuid,
+    pub user_id: Uuid,
+    /// Comment this one replies to
+    pub parent_id: Option<Uuid>,
+    /// Playback position in seconds the comment refers to
+    pub timestamp: Option<f64>,
+    /// Comment text, removed once the comment is deleted
+    pub body: Option<String>,
+    pub status: CommentStatus,
+    /// Number of published replies
+    pub reply_count: i64,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request for posting a comment
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateCommentRequest {
+    pub body: String,
+    /// Playback position in seconds the comment refers to
+    pub timestamp: Option<f64>,
+    /// Comment to reply to
+    pub parent_id: Option<Uuid>,
+}
+
+impl CreateCommentRequest {
+    /// Validate the comment against the item's duration, returning the trimmed text
+    pub fn validate(&self, duration: Option<f64>) -> Result<String, String> {
+        if let Some(timestamp) = self.timestamp {
+            if !timestamp.is_finite() || timestamp < 0.0 {
+                return Err("timestamp must be a non-negative number of seconds".to_string());
+            }
+            if duration.is_some_and(|duration| timestamp > duration) {
+                return Err("timestamp must not be past the end of the media".to_string());
+            }
+        }
+
+        validate_body(&self.body)
+    }
+}
+
+/// Request for editing a comment
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateCommentRequest {
+    pub body: String,
+}
+
+/// Validate a comment text, returning it trimmed
+pub fn validate_body(body: &str) -> Result<String, String> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err("Comment must not be empty".to_string());
+    }
+    if body.chars().count() > MAX_COMMENT_LENGTH {
+        return Err(format!(
+            "Comment must be at most {} characters",
+            MAX_COMMENT_LENGTH
+        ));
+    }
+    Ok(body.to_string())
+}
+
+/// Extract the users mentioned in a comment
+///
+/// Users are mentioned by email address, e.g. `@jane@example.com`. The
+/// addresses are returned lowercased and without duplicates.
+pub fn parse_mentions(body: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+
+    for word in body.split_whitespace() {
+        let Some(address) = word.strip_prefix('@') else {
+            continue;
+        };
+        let address = address
+            .trim_end_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase();
+        let valid = address.split_once('@').is_some_and(|(local, domain)| {
+            !local.is_empty() && domain.contains('.') && !domain.contains('@')
+        });
+
+        if valid && !mentions.contains(&address) {
+            mentions.push(address);
+            if mentions.len() == MAX_MENTIONS {
+                break;
+            }
+        }
+    }
+
+    mentions
+}
+
+/// Position of the last comment of a page
+///
+/// Comment listings are ordered by creation time and id, so the cursor only
+/// has to carry those of the boundary comment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommentCursor {
+    #[serde(rename = "c")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "i")]
+    pub id: Uuid,
+}
+
+impl CommentCursor {
+    /// Encode the cursor as an opaque string
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    /// Decode a cursor previously returned by [`CommentCursor::encode`]
+    pub fn decode(value: &str) -> Option<Self> {
+        let json = URL_SAFE_NO_PAD.decode(value).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+/// Query parameters for listing comments
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommentQuery {
+    /// Opaque cursor from a previous response's `next_cursor`
+    pub cursor: Option<String>,
+    /// Number of comments per page
+    pub limit: Option<u32>,
+    /// Comments in this state, only for the admin listing (default: hidden)
+    pub status: Option<CommentStatus>,
+}
+
+impl CommentQuery {
+    /// Decode the cursor, if any
+    pub fn cursor(&self) -> Result<Option<CommentCursor>, String> {
+        self.cursor
+            .as_deref()
+            .map(|value| CommentCursor::decode(value).ok_or_else(|| "Invalid cursor".to_string()))
+            .transpose()
+    }
+}
+
+/// Page of comments
+#[derive(Debug, Clone, Serialize)]
+pub struct CommentListResponse {
+    pub items: Vec<Comment>,
+    pub limit: u32,
+    /// Cursor for the following page, if there is one
+    pub next_cursor: Option<String>,
+}
+
+/// Moderator decision on a comment
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModerateCommentRequest {
+    pub status: CommentStatus,
+}
+
+/// Change to a comment pushed to live subscribers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentEventKind {
+    Created,
+    Updated,
+    /// Deleted by its author or hidden by a moderator
+    Removed,
+}
+
+impl CommentEventKind {
+    /// Name of the server-sent event
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommentEventKind::Created => "created",
+            CommentEventKind::Updated => "updated",
+            CommentEventKind::Removed => "removed",
+        }
+    }
+}
+
+/// Payload of the Postgres notification sent when a comment changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentNotification {
+    pub kind: CommentEventKind,
+    pub comment_id: Uuid,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_comment_request() {
+        let request = |body: &str, timestamp: Option<f64>| CreateCommentRequest {
+            body: body.to_string(),
+            timestamp,
+            parent_id: None,
+        };
+
+        assert_eq!(
+            request("  Great scene  ", Some(42.0)).validate(Some(100.0)),
+            Ok("Great scene".to_string())
+        );
+        assert!(request("   ", None).validate(None).is_err());
+        assert!(request("Late", Some(101.0)).validate(Some(100.0)).is_err());
+        assert!(request("Early", Some(-1.0)).validate(None).is_err());
+        assert!(
+            request("Unknown length", Some(5000.0))
+                .validate(None)
+                .is_ok()
+        );
+        assert!(
+            request(&"x".repeat(MAX_COMMENT_LENGTH + 1), None)
+                .validate(None)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_mentions() {
+        assert_eq!(
+            parse_mentions("Thanks @Jane@Example.com, see @bob@example.org! and @jane@example.com"),
+            vec!["jane@example.com", "bob@example.org"]
+        );
+        assert!(parse_mentions("email me at jane@example.com or @here @x@y").is_empty());
+    }
+
+    #[test]
+    fn test_comment_cursor_round_trip() {
+        let cursor = CommentCursor {
+            created_at: Utc::now(),
+            id: Uuid::new_v4(),
+        };
+        assert_eq!(CommentCursor::decode(&cursor.encode()), Some(cursor));
+        assert_eq!(CommentCursor::decode("not a cursor"), None);
+    }
+}
@@ -0,0 +1,100 @@
+//! Watch history models for the API service
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Key of the `users.settings` flag that pauses history recording
+pub const HISTORY_PAUSED_SETTING: &str = "history_paused";
+
+/// Request for recording a viewing session
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewHistoryEntry {
+    pub media_id: Uuid,
+    /// Position in seconds the session started at
+    pub start_position: f64,
+    /// Position in seconds the session ended at
+    pub end_position: f64,
+    /// Optional device description, e.g. "Living room TV"
+    pub device: Option<String>,
+}
+
+impl NewHistoryEntry {
+    /// Validate the reported positions and device
+    pub fn validate(&self) -> Result<(), String> {
+        let valid_position = |position: f64| position.is_finite() && position >= 0.0;
+        if !valid_position(self.start_position) || !valid_position(self.end_position) {
+            return Err("Positions must be non-negative numbers of seconds".to_string());
+        }
+        if self.end_position < self.start_position {
+            return Err("end_position must not be before start_position".to_string());
+        }
+        if self
+            .device
+            .as_ref()
+            .is_some_and(|device| device.len() > 255)
+        {
+            return Err("device must be at most 255 characters".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Viewing session in the watch history
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    pub id: Uuid,
+    pub media_id: Uuid,
+    /// Title of the media item at the time of listing
+    pub title: Option<String>,
+    pub start_position: f64,
+    pub end_position: f64,
+    pub device: Option<String>,
+    pub watched_at: DateTime<Utc>,
+}
+
+/// Query parameters for listing the watch history
+#[derive(Debug, Clone, Deserialize)]
+pub struct HistoryQuery {
+    /// Page number (1-based)
+    pub page: Option<u32>,
+    /// Number of entries per page
+    pub limit: Option<u32>,
+}
+
+/// Page of the watch history
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryListResponse {
+    pub items: Vec<HistoryEntry>,
+    pub page: u32,
+    pub limit: u32,
+    pub total: i64,
+}
+
+/// Watch history settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistorySettings {
+    /// Whether new viewing sessions are not recorded
+    pub paused: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_entry() {
+        let entry = |start_position, end_position| NewHistoryEntry {
+            media_id: Uuid::new_v4(),
+            start_position,
+            end_position,
+            device: None,
+        };
+
+        assert!(entry(0.0, 120.5).validate().is_ok());
+        assert!(entry(30.0, 30.0).validate().is_ok());
+        assert!(entry(60.0, 30.0).validate().is_err());
+        assert!(entry(-1.0, 30.0).validate().is_err());
+        assert!(entry(0.0, f64::NAN).validate().is_err());
+    }
+}
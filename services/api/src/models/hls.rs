@@ -0,0 +1,40 @@
+//! HLS rendition models for the API service
+
+use serde::{Deserialize, Serialize};
+
+/// Transcoded rendition of a media item
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rendition {
+    /// URL-safe name, e.g. `720p`
+    pub name: String,
+    /// Peak bit rate in bits per second
+    pub bandwidth: i32,
+    pub average_bandwidth: Option<i32>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub frame_rate: Option<f64>,
+    /// RFC 6381 codec strings, e.g. `avc1.64001f,mp4a.40.2`
+    pub codecs: String,
+    /// Maximum segment duration in seconds
+    pub target_duration: i32,
+}
+
+/// Media segment of a rendition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenditionSegment {
+    pub sequence: i32,
+    /// Duration in seconds
+    pub duration: f64,
+    pub s3_key: String,
+}
+
+impl RenditionSegment {
+    /// File extension of the segment, used in its playlist URI
+    pub fn extension(&self) -> &str {
+        self.s3_key
+            .rsplit_once('.')
+            .map(|(_, extension)| extension)
+            .filter(|extension| !extension.contains('/'))
+            .unwrap_or("ts")
+    }
+}
@@ -1,9 +1,16 @@
 //! Media models for the API service
 
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::models::profile::MaturityLevel;
+use crate::models::taxonomy::{MAX_TAXONOMY_NAME_LENGTH, MediaFacets, parse_slugs};
+
+/// Kinds a video can be classified as
+pub const MEDIA_KINDS: [&str; 2] = ["movie", "episode"];
+
 /// Media item model
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MediaItem {
@@ -26,13 +33,43 @@ pub struct MediaItem {
     pub sample_rate: Option<i32>,
     pub channels: Option<i32>,
     pub thumbnail_url: Option<String>,
+    /// Who may see the item (private, unlisted, shared or public)
+    pub visibility: String,
+    /// When the item was moved to the trash
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Whether the item is a movie or an episode, if classified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// Season an episode belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub season_id: Option<Uuid>,
+    /// Number of an episode within its season
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub episode_number: Option<i32>,
+    /// Maturity rating, if the item has been rated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maturity_rating: Option<MaturityLevel>,
+    /// Average user rating, if the item has been rated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating_average: Option<f64>,
+    /// Number of user ratings, if the item has been rated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating_count: Option<i32>,
+    /// Highlighted search snippet, only set for search results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<String>,
 }
 
 /// Query parameters for media listing
 #[derive(Debug, Clone, Deserialize)]
 pub struct MediaQuery {
-    /// Page number (1-based)
+    /// Page number (1-based), selects legacy offset pagination
     pub page: Option<u32>,
+    /// Opaque cursor from a previous response's `next_cursor` or `prev_cursor`
+    pub cursor: Option<String>,
+    /// Whether to compute the exact total in cursor mode
+    pub include_total: Option<bool>,
     /// Number of items per page
     pub limit: Option<u32>,
     /// Sort field
@@ -44,19 +81,397 @@ pub struct MediaQuery {
     pub media_type: Option<String>,
     /// Filter by status
     pub status: Option<String>,
+    /// Filter by kind (movie or episode)
+    pub kind: Option<String>,
     /// Filter by user ID
     pub user_id: Option<Uuid>,
     /// Search term for metadata
     pub search: Option<String>,
+    /// Minimum duration in seconds
+    pub min_duration: Option<f64>,
+    /// Maximum duration in seconds
+    pub max_duration: Option<f64>,
+    /// Minimum width in pixels
+    pub min_width: Option<i32>,
+    /// Maximum width in pixels
+    pub max_width: Option<i32>,
+    /// Minimum height in pixels
+    pub min_height: Option<i32>,
+    /// Maximum height in pixels
+    pub max_height: Option<i32>,
+    /// Only items created at or after this time
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only items created at or before this time
+    pub created_before: Option<DateTime<Utc>>,
+    /// Only items in this collection of the requesting user
+    pub collection_id: Option<Uuid>,
+    /// Comma separated tags the items must all carry
+    pub tag: Option<String>,
+    /// Comma separated genres the items must all belong to
+    pub genre: Option<String>,
+    /// Whether to count the matching items by genre, type and resolution
+    pub include_facets: Option<bool>,
+}
+
+impl MediaQuery {
+    /// Parse the requested sort field and order
+    pub fn sort(&self) -> Result<(MediaSortField, SortOrder), String> {
+        let has_search = self.search.as_deref().is_some_and(|s| !s.trim().is_empty());
+
+        let field = match self.sort_by.as_deref() {
+            Some(value) => MediaSortField::parse(value)
+                .ok_or_else(|| format!("Unsupported sort field: {}", value))?,
+            // Search results are ranked by relevance unless asked otherwise
+            None if has_search => MediaSortField::Relevance,
+            None => MediaSortField::CreatedAt,
+        };
+
+        if field == MediaSortField::Relevance && !has_search {
+            return Err("Sorting by relevance requires a search term".to_string());
+        }
+
+        let order = match self.order.as_deref() {
+            Some(value) => SortOrder::parse(value)
+                .ok_or_else(|| format!("Unsupported sort order: {}", value))?,
+            None => SortOrder::Desc,
+        };
+
+        Ok((field, order))
+    }
+
+    /// Slugs of the tags to filter by
+    pub fn tags(&self) -> Vec<String> {
+        self.tag.as_deref().map(parse_slugs).unwrap_or_default()
+    }
+
+    /// Slugs of the genres to filter by
+    pub fn genres(&self) -> Vec<String> {
+        self.genre.as_deref().map(parse_slugs).unwrap_or_default()
+    }
+
+    /// Decode the requested cursor, checking it matches the requested sort
+    pub fn cursor(&self) -> Result<Option<MediaCursor>, String> {
+        let Some(cursor) = &self.cursor else {
+            return Ok(None);
+        };
+
+        let cursor = MediaCursor::decode(cursor).ok_or_else(|| "Invalid cursor".to_string())?;
+        if (cursor.sort_by, cursor.order) != self.sort()? {
+            return Err("Cursor does not match the requested sort order".to_string());
+        }
+
+        Ok(Some(cursor))
+    }
+
+    /// Validate sorting, cursor, kind and range parameters
+    pub fn validate(&self) -> Result<(), String> {
+        self.cursor()?;
+
+        if self.cursor.is_some() && self.page.is_some() {
+            return Err("page and cursor cannot be combined".to_string());
+        }
+
+        fn check_range<T: PartialOrd>(
+            name: &str,
+            min: Option<T>,
+            max: Option<T>,
+        ) -> Result<(), String> {
+            match (min, max) {
+                (Some(min), Some(max)) if min > max => Err(format!(
+                    "min_{} must not be greater than max_{}",
+                    name, name
+                )),
+                _ => Ok(()),
+            }
+        }
+
+        if let Some(kind) = self.kind.as_deref()
+            && !MEDIA_KINDS.contains(&kind)
+        {
+            return Err(format!("Unsupported kind: {}", kind));
+        }
+
+        check_range("duration", self.min_duration, self.max_duration)?;
+        check_range("width", self.min_width, self.max_width)?;
+        check_range("height", self.min_height, self.max_height)?;
+
+        if let (Some(after), Some(before)) = (self.created_after, self.created_before)
+            && after > before
+        {
+            return Err("created_after must not be later than created_before".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// Fields media listings can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaSortField {
+    CreatedAt,
+    UpdatedAt,
+    Title,
+    Duration,
+    Width,
+    Height,
+    Bitrate,
+    /// Average user rating
+    Rating,
+    /// Full-text search rank, only valid together with a search term
+    Relevance,
+}
+
+impl MediaSortField {
+    /// Parse a sort field from its query parameter value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "created_at" => Some(Self::CreatedAt),
+            "updated_at" => Some(Self::UpdatedAt),
+            "title" => Some(Self::Title),
+            "duration" => Some(Self::Duration),
+            "width" => Some(Self::Width),
+            "height" => Some(Self::Height),
+            "bitrate" => Some(Self::Bitrate),
+            "rating" => Some(Self::Rating),
+            "relevance" => Some(Self::Relevance),
+            _ => None,
+        }
+    }
+
+    /// Column to sort by, `None` for the computed search rank
+    pub fn column(&self) -> Option<&'static str> {
+        match self {
+            Self::CreatedAt => Some("created_at"),
+            Self::UpdatedAt => Some("updated_at"),
+            Self::Title => Some("title"),
+            Self::Duration => Some("duration"),
+            Self::Width => Some("width"),
+            Self::Height => Some("height"),
+            Self::Bitrate => Some("bitrate"),
+            Self::Rating => Some(RATING_AVERAGE),
+            Self::Relevance => None,
+        }
+    }
+
+    /// SQL type of the sort value, used to restore cursor values
+    pub fn sql_type(&self) -> &'static str {
+        match self {
+            Self::CreatedAt | Self::UpdatedAt => "TIMESTAMPTZ",
+            Self::Title => "TEXT",
+            Self::Duration | Self::Rating => "DOUBLE PRECISION",
+            Self::Width | Self::Height => "INTEGER",
+            Self::Bitrate => "BIGINT",
+            Self::Relevance => "REAL",
+        }
+    }
+}
+
+/// Average rating of the media item in the current row
+pub const RATING_AVERAGE: &str =
+    "(SELECT rating_average FROM media_rating_stats WHERE media_item_id = media_items.id)";
+
+/// Number of ratings of the media item in the current row
+pub const RATING_COUNT: &str =
+    "(SELECT rating_count FROM media_rating_stats WHERE media_item_id = media_items.id)";
+
+/// Sort direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    /// Parse a sort order from its query parameter value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "asc" => Some(Self::Asc),
+            "desc" => Some(Self::Desc),
+            _ => None,
+        }
+    }
+}
+
+/// Position in a keyset paginated media listing
+///
+/// Cursors are handed to clients as opaque URL-safe strings. They remember
+/// the sort they were issued for together with the sort value and ID of the
+/// item at the page boundary.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MediaCursor {
+    #[serde(rename = "s")]
+    pub sort_by: MediaSortField,
+    #[serde(rename = "o")]
+    pub order: SortOrder,
+    /// Sort value of the boundary item in its text representation
+    #[serde(rename = "v")]
+    pub value: Option<String>,
+    /// ID of the boundary item
+    #[serde(rename = "i")]
+    pub id: Uuid,
+    /// Whether the page lies before the boundary item
+    #[serde(rename = "b", default)]
+    pub backward: bool,
+}
+
+impl MediaCursor {
+    /// Encode the cursor as an opaque string
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    /// Decode a cursor previously returned by [`MediaCursor::encode`]
+    pub fn decode(value: &str) -> Option<Self> {
+        let json = URL_SAFE_NO_PAD.decode(value).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
 }
 
 /// Response for media listing with pagination
 #[derive(Debug, Clone, Serialize)]
 pub struct MediaListResponse {
     pub items: Vec<MediaItem>,
-    pub page: u32,
+    /// Current page, only set in legacy offset mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
     pub limit: u32,
-    pub total: i64,
+    /// Exact number of matching items, if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+    /// Cursor for the following page, if there is one
+    pub next_cursor: Option<String>,
+    /// Cursor for the preceding page, if there is one
+    pub prev_cursor: Option<String>,
+    /// Counts of the matching items by facet, if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<MediaFacets>,
+}
+
+/// Visibility of a media item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    /// Only the owner can see the item
+    Private,
+    /// Anyone who knows the ID can see the item, but it is not listed
+    Unlisted,
+    /// The owner and the users it is shared with can see the item
+    Shared,
+    /// Everyone can see and list the item
+    Public,
+}
+
+impl Visibility {
+    /// Database representation of the visibility
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Private => "private",
+            Self::Unlisted => "unlisted",
+            Self::Shared => "shared",
+            Self::Public => "public",
+        }
+    }
+}
+
+/// Request for changing the visibility of a media item
+#[derive(Debug, Clone, Deserialize)]
+pub struct MediaVisibilityRequest {
+    pub visibility: Visibility,
+    /// Users the item is shared with, replacing any previous shares
+    #[serde(default)]
+    pub shared_with: Vec<Uuid>,
+}
+
+/// Request for changing the maturity rating of a media item
+#[derive(Debug, Clone, Deserialize)]
+pub struct MaturityRatingRequest {
+    /// New rating; `null` marks the item as unrated
+    pub maturity_rating: Option<MaturityLevel>,
+}
+
+/// Metadata fields that can be edited through `PATCH /media/:id`
+pub const EDITABLE_METADATA_FIELDS: [&str; 4] = ["title", "description", "tags", "custom"];
+
+/// Validate a JSON Merge Patch for media metadata
+///
+/// Only the editable fields may be patched; `null` removes a field.
+pub fn validate_metadata_patch(patch: &serde_json::Value) -> Result<(), String> {
+    use serde_json::Value;
+
+    let fields = patch
+        .as_object()
+        .ok_or_else(|| "Metadata patch must be a JSON object".to_string())?;
+
+    for (field, value) in fields {
+        let valid = match (field.as_str(), value) {
+            (_, Value::Null) => EDITABLE_METADATA_FIELDS.contains(&field.as_str()),
+            ("title", Value::String(title)) => !title.trim().is_empty(),
+            ("description", Value::String(_)) => true,
+            ("tags", Value::Array(tags)) => tags.iter().all(|tag| {
+                tag.as_str()
+                    .is_some_and(|tag| tag.chars().count() <= MAX_TAXONOMY_NAME_LENGTH)
+            }),
+            ("custom", Value::Object(_)) => true,
+            _ => false,
+        };
+
+        if !valid {
+            return Err(format!("Invalid or read-only metadata field: {}", field));
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply a JSON Merge Patch (RFC 7396) to a JSON value
+pub fn merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    use serde_json::Value;
+
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// Query parameters for the trash view
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrashQuery {
+    /// Page number (1-based)
+    pub page: Option<u32>,
+    /// Number of items per page
+    pub limit: Option<u32>,
+}
+
+/// Query parameters for search suggestions
+#[derive(Debug, Clone, Deserialize)]
+pub struct SuggestQuery {
+    /// Partial search input
+    pub q: String,
+    /// Maximum number of suggestions
+    pub limit: Option<u32>,
+}
+
+/// Response for search suggestions
+#[derive(Debug, Clone, Serialize)]
+pub struct SuggestResponse {
+    pub suggestions: Vec<String>,
 }
 
 /// Request for media refresh
@@ -67,3 +482,140 @@ pub struct MediaRefreshRequest {
     /// Optional S3 key to refresh
     pub s3_key: Option<String>,
 }
+
+/// Request for a signed playback URL
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PlaybackUrlRequest {
+    /// Rendition to play (default: "original")
+    pub rendition: Option<String>,
+    /// Only accept the URL from the requesting IP address
+    #[serde(default)]
+    pub bind_ip: bool,
+}
+
+/// Signed playback URL
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaybackUrlResponse {
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query() -> MediaQuery {
+        serde_json::from_value(serde_json::json!({})).unwrap()
+    }
+
+    #[test]
+    fn test_sort_defaults_and_whitelist() {
+        let mut query = query();
+        assert_eq!(
+            query.sort().unwrap(),
+            (MediaSortField::CreatedAt, SortOrder::Desc)
+        );
+
+        query.sort_by = Some("duration".to_string());
+        query.order = Some("ASC".to_string());
+        assert_eq!(
+            query.sort().unwrap(),
+            (MediaSortField::Duration, SortOrder::Asc)
+        );
+
+        query.sort_by = Some("rating".to_string());
+        query.order = None;
+        assert_eq!(
+            query.sort().unwrap(),
+            (MediaSortField::Rating, SortOrder::Desc)
+        );
+
+        query.sort_by = Some("id; DROP TABLE media_items".to_string());
+        assert!(query.sort().is_err());
+
+        query.sort_by = Some("relevance".to_string());
+        assert!(query.sort().is_err());
+
+        query.sort_by = None;
+        query.order = None;
+        query.search = Some("matrix".to_string());
+        assert_eq!(
+            query.sort().unwrap(),
+            (MediaSortField::Relevance, SortOrder::Desc)
+        );
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = MediaCursor {
+            sort_by: MediaSortField::Duration,
+            order: SortOrder::Asc,
+            value: Some("42.5".to_string()),
+            id: Uuid::new_v4(),
+            backward: true,
+        };
+        assert_eq!(MediaCursor::decode(&cursor.encode()), Some(cursor.clone()));
+        assert_eq!(MediaCursor::decode("not a cursor"), None);
+
+        let mut query = query();
+        query.cursor = Some(cursor.encode());
+        assert!(query.cursor().is_err());
+
+        query.sort_by = Some("duration".to_string());
+        query.order = Some("asc".to_string());
+        assert_eq!(query.cursor().unwrap(), Some(cursor));
+    }
+
+    #[test]
+    fn test_merge_patch() {
+        let mut metadata = serde_json::json!({
+            "title": "Old",
+            "tags": ["a"],
+            "custom": {"season": 1, "episode": 2},
+            "codec_private": "keep"
+        });
+        let patch = serde_json::json!({
+            "title": "New",
+            "tags": null,
+            "custom": {"episode": 3, "rating": "PG"}
+        });
+
+        assert!(validate_metadata_patch(&patch).is_ok());
+        merge_patch(&mut metadata, &patch);
+        assert_eq!(
+            metadata,
+            serde_json::json!({
+                "title": "New",
+                "custom": {"season": 1, "episode": 3, "rating": "PG"},
+                "codec_private": "keep"
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_metadata_patch() {
+        assert!(validate_metadata_patch(&serde_json::json!({"title": ""})).is_err());
+        assert!(validate_metadata_patch(&serde_json::json!({"title": null})).is_ok());
+        assert!(validate_metadata_patch(&serde_json::json!({"tags": [1]})).is_err());
+        assert!(validate_metadata_patch(&serde_json::json!({"s3_key": "x"})).is_err());
+        assert!(validate_metadata_patch(&serde_json::json!(["title"])).is_err());
+    }
+
+    #[test]
+    fn test_validate_ranges() {
+        let mut query = query();
+        query.min_duration = Some(60.0);
+        query.max_duration = Some(30.0);
+        assert!(query.validate().is_err());
+
+        query.max_duration = Some(120.0);
+        query.min_height = Some(720);
+        query.max_height = Some(1080);
+        assert!(query.validate().is_ok());
+
+        query.kind = Some("series".to_string());
+        assert!(query.validate().is_err());
+        query.kind = Some("episode".to_string());
+        assert!(query.validate().is_ok());
+    }
+}
@@ -0,0 +1,42 @@
+//! Notification models for the API service
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Notification of a user
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub id: Uuid,
+    /// What happened, currently only `mention`
+    pub kind: String,
+    pub media_id: Uuid,
+    pub comment_id: Uuid,
+    /// User who caused the notification
+    pub actor_id: Option<Uuid>,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Query parameters for listing notifications
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationQuery {
+    /// Only list unread notifications
+    #[serde(default)]
+    pub unread: bool,
+    /// Page number (1-based)
+    pub page: Option<u32>,
+    /// Number of notifications per page
+    pub limit: Option<u32>,
+}
+
+/// Page of notifications
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationListResponse {
+    pub items: Vec<Notification>,
+    pub page: u32,
+    pub limit: u32,
+    pub total: i64,
+    /// Number of unread notifications
+    pub unread: i64,
+}
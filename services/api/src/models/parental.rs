@@ -0,0 +1,92 @@
+//! Parental control models for the API service
+
+use anyhow::Result;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHash, PasswordHasher as _, PasswordVerifier as _, SaltString},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::models::profile::MaturityLevel;
+
+/// Failed PIN attempts allowed before the PIN is locked
+pub const MAX_PIN_FAILURES: i32 = 5;
+
+/// Minutes the PIN stays locked after too many failed attempts
+pub const PIN_LOCKOUT_MINUTES: i32 = 15;
+
+/// Parental controls of an account
+#[derive(Debug, Clone, Serialize)]
+pub struct ParentalControls {
+    /// Maximum maturity rating for tokens without a selected profile;
+    /// unrestricted if none
+    pub max_rating: Option<MaturityLevel>,
+    /// Whether a PIN is required to change maturity limits
+    pub pin_set: bool,
+}
+
+/// Request for changing the account's maximum maturity rating
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateParentalControlsRequest {
+    /// New maximum rating; `null` removes the limit
+    pub max_rating: Option<MaturityLevel>,
+    /// Current PIN, required if one is set
+    pub pin: Option<String>,
+}
+
+/// Request for setting, changing or removing the PIN
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdatePinRequest {
+    /// Current PIN, required if one is set
+    pub current_pin: Option<String>,
+    /// New PIN; `null` removes the PIN
+    pub new_pin: Option<String>,
+}
+
+/// Validate a PIN of 4 to 8 digits
+pub fn validate_pin(pin: &str) -> Result<(), String> {
+    if !(4..=8).contains(&pin.len()) || !pin.chars().all(|c| c.is_ascii_digit()) {
+        return Err("PIN must be 4 to 8 digits".to_string());
+    }
+    Ok(())
+}
+
+/// Hash a PIN for storage
+pub fn hash_pin(pin: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hash = Argon2::default()
+        .hash_password(pin.as_bytes(), &salt)
+        .map_err(|e| anyhow::anyhow!("Failed to hash PIN: {}", e))?
+        .to_string();
+
+    Ok(hash)
+}
+
+/// Verify a PIN against its stored hash
+pub fn verify_pin(pin_hash: &str, pin: &str) -> Result<bool> {
+    let parsed_hash = PasswordHash::new(pin_hash)
+        .map_err(|e| anyhow::anyhow!("Failed to parse PIN hash: {}", e))?;
+
+    Ok(Argon2::default()
+        .verify_password(pin.as_bytes(), &parsed_hash)
+        .is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin() {
+        assert!(validate_pin("1234").is_ok());
+        assert!(validate_pin("12345678").is_ok());
+        assert!(validate_pin("123").is_err());
+        assert!(validate_pin("123456789").is_err());
+        assert!(validate_pin("12a4").is_err());
+
+        let hash = hash_pin("2468").unwrap();
+        assert_ne!(hash, "2468");
+        assert!(verify_pin(&hash, "2468").unwrap());
+        assert!(!verify_pin(&hash, "1357").unwrap());
+    }
+}
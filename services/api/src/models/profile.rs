@@ -0,0 +1,268 @@
+//! Viewer profile models for the API service
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Maximum number of profiles per account
+pub const MAX_PROFILES: i64 = 5;
+
+/// Maximum length of a profile name
+const MAX_NAME_LENGTH: usize = 50;
+
+/// Maximum length of an avatar URL
+const MAX_AVATAR_URL_LENGTH: usize = 2048;
+
+/// Maximum length of a language tag
+const MAX_LANGUAGE_LENGTH: usize = 35;
+
+/// Maturity level of a profile, from the most to the least restricted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaturityLevel {
+    /// Suitable for all ages
+    All,
+    /// Ages 7 and up
+    Kids,
+    /// Ages 13 and up
+    Teen,
+    /// Ages 18 and up
+    Adult,
+}
+
+impl MaturityLevel {
+    /// All levels, from the most to the least restricted
+    pub const LEVELS: [MaturityLevel; 4] = [
+        MaturityLevel::All,
+        MaturityLevel::Kids,
+        MaturityLevel::Teen,
+        MaturityLevel::Adult,
+    ];
+
+    /// Database representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MaturityLevel::All => "all",
+            MaturityLevel::Kids => "kids",
+            MaturityLevel::Teen => "teen",
+            MaturityLevel::Adult => "adult",
+        }
+    }
+
+    /// Parse the database representation
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "all" => Some(MaturityLevel::All),
+            "kids" => Some(MaturityLevel::Kids),
+            "teen" => Some(MaturityLevel::Teen),
+            "adult" => Some(MaturityLevel::Adult),
+            _ => None,
+        }
+    }
+
+    /// Media ratings a viewer limited to this level may watch
+    ///
+    /// Returns `None` if the level restricts nothing. Unrated media is only
+    /// shown to unrestricted viewers.
+    pub fn allowed_ratings(self) -> Option<Vec<String>> {
+        (self < MaturityLevel::Adult).then(|| {
+            Self::LEVELS
+                .iter()
+                .filter(|level| **level <= self)
+                .map(|level| level.as_str().to_string())
+                .collect()
+        })
+    }
+}
+
+/// Viewer profile of an account
+#[derive(Debug, Clone, Serialize)]
+pub struct Profile {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub avatar_url: Option<String>,
+    pub maturity_level: MaturityLevel,
+    /// Preferred audio language as a BCP 47 tag
+    pub audio_language: Option<String>,
+    /// Preferred subtitle language as a BCP 47 tag
+    pub subtitle_language: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Validated profile fields, as stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileFields {
+    pub name: String,
+    pub avatar_url: Option<String>,
+    pub maturity_level: MaturityLevel,
+    pub audio_language: Option<String>,
+    pub subtitle_language: Option<String>,
+}
+
+/// Request for creating a profile
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateProfileRequest {
+    pub name: String,
+    pub avatar_url: Option<String>,
+    /// Maturity level (default: adult)
+    pub maturity_level: Option<MaturityLevel>,
+    pub audio_language: Option<String>,
+    pub subtitle_language: Option<String>,
+    /// Parental control PIN, required if one is set
+    pub pin: Option<String>,
+}
+
+impl CreateProfileRequest {
+    /// Validate the request, returning the fields to store
+    pub fn validate(&self) -> Result<ProfileFields, String> {
+        Ok(ProfileFields {
+            name: validate_name(&self.name)?,
+            avatar_url: validate_avatar_url(self.avatar_url.as_deref())?,
+            maturity_level: self.maturity_level.unwrap_or(MaturityLevel::Adult),
+            audio_language: validate_language(self.audio_language.as_deref())?,
+            subtitle_language: validate_language(self.subtitle_language.as_deref())?,
+        })
+    }
+}
+
+/// Request for editing a profile
+///
+/// Omitted fields are left unchanged; an empty avatar URL or language
+/// removes it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateProfileRequest {
+    pub name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub maturity_level: Option<MaturityLevel>,
+    pub audio_language: Option<String>,
+    pub subtitle_language: Option<String>,
+    /// Parental control PIN, required to change the maturity level if one
+    /// is set
+    pub pin: Option<String>,
+}
+
+impl UpdateProfileRequest {
+    /// Apply the changes to a profile, returning the fields to store
+    pub fn apply(&self, profile: &Profile) -> Result<ProfileFields, String> {
+        Ok(ProfileFields {
+            name: match &self.name {
+                Some(name) => validate_name(name)?,
+                None => profile.name.clone(),
+            },
+            avatar_url: match &self.avatar_url {
+                Some(url) => validate_avatar_url(Some(url))?,
+                None => profile.avatar_url.clone(),
+            },
+            maturity_level: self.maturity_level.unwrap_or(profile.maturity_level),
+            audio_language: match &self.audio_language {
+                Some(language) => validate_language(Some(language))?,
+                None => profile.audio_language.clone(),
+            },
+            subtitle_language: match &self.subtitle_language {
+                Some(language) => validate_language(Some(language))?,
+                None => profile.subtitle_language.clone(),
+            },
+        })
+    }
+}
+
+/// Validate a profile name, returning it trimmed
+fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!(
+            "Profile name must be between 1 and {} characters",
+            MAX_NAME_LENGTH
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// Validate an avatar URL; empty means none
+fn validate_avatar_url(url: Option<&str>) -> Result<Option<String>, String> {
+    let Some(url) = url.map(str::trim).filter(|url| !url.is_empty()) else {
+        return Ok(None);
+    };
+    if url.len() > MAX_AVATAR_URL_LENGTH
+        || !(url.starts_with("https://") || url.starts_with("http://"))
+    {
+        return Err(format!(
+            "avatar_url must be an http(s) URL of at most {} characters",
+            MAX_AVATAR_URL_LENGTH
+        ));
+    }
+    Ok(Some(url.to_string()))
+}
+
+/// Validate a BCP 47 language tag such as `en` or `pt-BR`; empty means none
+fn validate_language(language: Option<&str>) -> Result<Option<String>, String> {
+    let Some(language) = language.map(str::trim).filter(|tag| !tag.is_empty()) else {
+        return Ok(None);
+    };
+
+    let mut subtags = language.split('-');
+    let primary = subtags.next().unwrap_or_default();
+    let valid = language.len() <= MAX_LANGUAGE_LENGTH
+        && (2..=3).contains(&primary.len())
+        && primary.chars().all(|c| c.is_ascii_alphabetic())
+        && subtags.all(|subtag| {
+            (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+    if !valid {
+        return Err(format!("Invalid language tag: {}", language));
+    }
+    Ok(Some(language.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maturity_level_order() {
+        assert!(MaturityLevel::All < MaturityLevel::Kids);
+        assert!(MaturityLevel::Teen < MaturityLevel::Adult);
+        for level in MaturityLevel::LEVELS {
+            assert_eq!(MaturityLevel::parse(level.as_str()), Some(level));
+        }
+    }
+
+    #[test]
+    fn test_allowed_ratings() {
+        assert_eq!(
+            MaturityLevel::Kids.allowed_ratings(),
+            Some(vec!["all".to_string(), "kids".to_string()])
+        );
+        assert_eq!(MaturityLevel::Adult.allowed_ratings(), None);
+    }
+
+    #[test]
+    fn test_create_profile_request() {
+        let request = CreateProfileRequest {
+            name: "  Kids  ".to_string(),
+            avatar_url: Some("https://example.com/a.png".to_string()),
+            maturity_level: Some(MaturityLevel::Kids),
+            audio_language: Some("pt-BR".to_string()),
+            subtitle_language: Some("".to_string()),
+            pin: None,
+        };
+        let fields = request.validate().unwrap();
+        assert_eq!(fields.name, "Kids");
+        assert_eq!(fields.audio_language.as_deref(), Some("pt-BR"));
+        assert_eq!(fields.subtitle_language, None);
+
+        let invalid = |change: fn(&mut CreateProfileRequest)| {
+            let mut request = request.clone();
+            change(&mut request);
+            request.validate().is_err()
+        };
+        assert!(invalid(|r| r.name = " ".to_string()));
+        assert!(invalid(
+            |r| r.avatar_url = Some("javascript:alert(1)".to_string())
+        ));
+        assert!(invalid(|r| r.audio_language = Some("english!".to_string())));
+        assert!(invalid(|r| r.subtitle_language = Some("e".to_string())));
+    }
+}
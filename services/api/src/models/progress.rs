@@ -0,0 +1,146 @@
+//! Playback progress models for the API service
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::media::MediaItem;
+
+/// Maximum number of media IDs in a bulk progress request
+pub const MAX_PROGRESS_IDS: usize = 100;
+
+/// Playback progress configuration
+#[derive(Debug, Clone)]
+pub struct ProgressConfig {
+    /// Minimum time in seconds between stored position reports
+    pub throttle_seconds: u64,
+    /// Fraction of the duration after which an item counts as finished
+    pub finished_threshold: f64,
+}
+
+impl ProgressConfig {
+    /// Create a new ProgressConfig from environment variables
+    ///
+    /// # Environment Variables
+    /// - `PROGRESS_THROTTLE_SECONDS`: Minimum time between stored reports (default: 10)
+    /// - `PROGRESS_FINISHED_THRESHOLD`: Fraction of the duration that marks an item finished (default: 0.9)
+    pub fn from_env() -> Self {
+        let throttle_seconds = std::env::var("PROGRESS_THROTTLE_SECONDS")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse()
+            .unwrap_or(10);
+
+        let finished_threshold = std::env::var("PROGRESS_FINISHED_THRESHOLD")
+            .ok()
+            .and_then(|threshold| threshold.parse::<f64>().ok())
+            .filter(|threshold| *threshold > 0.0 && *threshold <= 1.0)
+            .unwrap_or(0.9);
+
+        ProgressConfig {
+            throttle_seconds,
+            finished_threshold,
+        }
+    }
+
+    /// Whether a position counts as having finished an item
+    ///
+    /// Items without a known duration are never finished automatically.
+    pub fn is_finished(&self, position: f64, duration: Option<f64>) -> bool {
+        duration.is_some_and(|duration| {
+            duration > 0.0 && position >= duration * self.finished_threshold
+        })
+    }
+}
+
+/// Request for reporting the playback position
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProgressUpdateRequest {
+    /// Position in seconds
+    pub position: f64,
+}
+
+/// Playback progress of a media item
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaybackProgress {
+    pub media_id: Uuid,
+    /// Position in seconds
+    pub position: f64,
+    pub duration: Option<f64>,
+    pub finished: bool,
+    pub last_watched_at: DateTime<Utc>,
+}
+
+/// Query parameters for bulk progress lookups
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProgressQuery {
+    /// Comma separated media IDs
+    pub ids: String,
+}
+
+impl ProgressQuery {
+    /// Parse the requested media IDs
+    pub fn ids(&self) -> Result<Vec<Uuid>, String> {
+        let ids = self
+            .ids
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(|id| Uuid::parse_str(id).map_err(|_| format!("Invalid media ID: {}", id)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if ids.len() > MAX_PROGRESS_IDS {
+            return Err(format!(
+                "At most {} media IDs are allowed",
+                MAX_PROGRESS_IDS
+            ));
+        }
+
+        Ok(ids)
+    }
+}
+
+/// Query parameters for the continue-watching feed
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContinueWatchingQuery {
+    /// Number of items (default: 20)
+    pub limit: Option<u32>,
+}
+
+/// Partially watched media item
+#[derive(Debug, Clone, Serialize)]
+pub struct ContinueWatchingItem {
+    pub media: MediaItem,
+    pub progress: PlaybackProgress,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_finished() {
+        let config = ProgressConfig {
+            throttle_seconds: 10,
+            finished_threshold: 0.9,
+        };
+
+        assert!(config.is_finished(90.0, Some(100.0)));
+        assert!(!config.is_finished(89.9, Some(100.0)));
+        assert!(!config.is_finished(5000.0, None));
+        assert!(!config.is_finished(0.0, Some(0.0)));
+    }
+
+    #[test]
+    fn test_progress_query_ids() {
+        let id = Uuid::new_v4();
+        let query = ProgressQuery {
+            ids: format!("{}, ,{}", id, id),
+        };
+        assert_eq!(query.ids().unwrap(), vec![id, id]);
+
+        let query = ProgressQuery {
+            ids: "not-a-uuid".to_string(),
+        };
+        assert!(query.ids().is_err());
+    }
+}
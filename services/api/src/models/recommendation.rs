@@ -0,0 +1,127 @@
+//! Recommendation models for the API service
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::media::MediaItem;
+
+/// Number of candidates kept per item or user, before visibility filtering
+pub const MAX_CANDIDATES: i64 = 100;
+
+/// Recommendation configuration
+#[derive(Debug, Clone)]
+pub struct RecommendationConfig {
+    /// Time in seconds between rebuilds of the similarity table
+    pub refresh_interval: u64,
+    /// Number of similar items stored per item
+    pub neighbors: i64,
+    /// Number of days of watch history used for co-watch similarity
+    pub history_days: i32,
+    /// Weight of shared genres and tags in the similarity score
+    pub taxonomy_weight: f64,
+    /// Weight of co-watching in the similarity score
+    pub cowatch_weight: f64,
+    /// Weight of matching type, kind and duration in the similarity score
+    pub metadata_weight: f64,
+    /// Time in seconds personalized recommendations are cached in Redis
+    pub cache_ttl: u64,
+}
+
+impl RecommendationConfig {
+    /// Create a new RecommendationConfig from environment variables
+    ///
+    /// The weights are normalized to sum to 1, so scores stay between 0 and 1.
+    ///
+    /// # Environment Variables
+    /// - `RECOMMENDATION_REFRESH_INTERVAL`: Time between similarity rebuilds in seconds (default: 3600)
+    /// - `RECOMMENDATION_NEIGHBORS`: Similar items stored per item (default: 50)
+    /// - `RECOMMENDATION_HISTORY_DAYS`: Days of watch history used for co-watch similarity (default: 180)
+    /// - `RECOMMENDATION_TAXONOMY_WEIGHT`: Weight of shared genres and tags (default: 0.5)
+    /// - `RECOMMENDATION_COWATCH_WEIGHT`: Weight of co-watching (default: 0.35)
+    /// - `RECOMMENDATION_METADATA_WEIGHT`: Weight of matching type, kind and duration (default: 0.15)
+    /// - `RECOMMENDATION_CACHE_TTL`: Time personalized recommendations are cached in seconds (default: 600)
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T, valid: impl Fn(&T) -> bool) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<T>().ok())
+                .filter(valid)
+                .unwrap_or(default)
+        }
+
+        let weight = |name: &str, default: f64| var(name, default, |w: &f64| *w >= 0.0);
+        let (taxonomy_weight, cowatch_weight, metadata_weight) = normalize_weights(
+            weight("RECOMMENDATION_TAXONOMY_WEIGHT", 0.5),
+            weight("RECOMMENDATION_COWATCH_WEIGHT", 0.35),
+            weight("RECOMMENDATION_METADATA_WEIGHT", 0.15),
+        );
+
+        RecommendationConfig {
+            refresh_interval: var("RECOMMENDATION_REFRESH_INTERVAL", 3600, |s: &u64| *s > 0),
+            neighbors: var("RECOMMENDATION_NEIGHBORS", 50, |n: &i64| *n > 0),
+            history_days: var("RECOMMENDATION_HISTORY_DAYS", 180, |d: &i32| *d > 0),
+            taxonomy_weight,
+            cowatch_weight,
+            metadata_weight,
+            cache_ttl: var("RECOMMENDATION_CACHE_TTL", 600, |_: &u64| true),
+        }
+    }
+}
+
+/// Scale the similarity weights to sum to 1, falling back to the defaults if all are 0
+pub fn normalize_weights(taxonomy: f64, cowatch: f64, metadata: f64) -> (f64, f64, f64) {
+    let total = taxonomy + cowatch + metadata;
+    if !total.is_finite() || total <= 0.0 {
+        return (0.5, 0.35, 0.15);
+    }
+    (taxonomy / total, cowatch / total, metadata / total)
+}
+
+/// Why an item was recommended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecommendationReason {
+    /// Similar to the item or to items the user watched, rated or favorited
+    Similar,
+    /// Watched by many users recently
+    Popular,
+}
+
+/// Recommendation candidate before visibility filtering, as cached in Redis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Candidate {
+    pub id: Uuid,
+    pub score: f64,
+    pub reason: RecommendationReason,
+}
+
+/// Recommended media item
+#[derive(Debug, Clone, Serialize)]
+pub struct Recommendation {
+    #[serde(flatten)]
+    pub item: MediaItem,
+    /// Relevance of the item; only comparable between items with the same reason
+    pub score: f64,
+    pub reason: RecommendationReason,
+}
+
+/// Query parameters for recommendations
+#[derive(Debug, Clone, Deserialize)]
+pub struct RecommendationQuery {
+    /// Number of items to return
+    pub limit: Option<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_weights() {
+        assert_eq!(normalize_weights(2.0, 1.0, 1.0), (0.5, 0.25, 0.25));
+        assert_eq!(normalize_weights(0.0, 0.0, 0.0), (0.5, 0.35, 0.15));
+
+        let (taxonomy, cowatch, metadata) = normalize_weights(0.5, 0.35, 0.15);
+        assert!((taxonomy + cowatch + metadata - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,259 @@
+//! Rating and review models for the API service
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Maximum length of a review text
+pub const MAX_REVIEW_LENGTH: usize = 5000;
+
+/// Maximum length of the details of an abuse report
+pub const MAX_REPORT_DETAILS_LENGTH: usize = 1000;
+
+/// Review configuration
+#[derive(Debug, Clone)]
+pub struct ReviewConfig {
+    /// Number of open reports after which a review is held for moderation
+    pub report_threshold: i64,
+}
+
+impl ReviewConfig {
+    /// Create a new ReviewConfig from environment variables
+    ///
+    /// # Environment Variables
+    /// - `REVIEW_REPORT_THRESHOLD`: Open reports that flag a review for moderation (default: 3)
+    pub fn from_env() -> Self {
+        let report_threshold = std::env::var("REVIEW_REPORT_THRESHOLD")
+            .ok()
+            .and_then(|threshold| threshold.parse::<i64>().ok())
+            .filter(|threshold| *threshold > 0)
+            .unwrap_or(3);
+
+        ReviewConfig { report_threshold }
+    }
+}
+
+/// Moderation state of a review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewStatus {
+    /// Shown to everyone who can see the item
+    Published,
+    /// Reported often enough to be held until a moderator decides
+    Flagged,
+    /// Removed by a moderator; only the author still sees it
+    Hidden,
+}
+
+impl ReviewStatus {
+    /// Database representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewStatus::Published => "published",
+            ReviewStatus::Flagged => "flagged",
+            ReviewStatus::Hidden => "hidden",
+        }
+    }
+
+    /// Parse the database representation
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "published" => Some(ReviewStatus::Published),
+            "flagged" => Some(ReviewStatus::Flagged),
+            "hidden" => Some(ReviewStatus::Hidden),
+            _ => None,
+        }
+    }
+}
+
+/// Reason given when reporting a review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    Spam,
+    Abuse,
+    Spoiler,
+    Other,
+}
+
+impl ReportReason {
+    /// Database representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportReason::Spam => "spam",
+            ReportReason::Abuse => "abuse",
+            ReportReason::Spoiler => "spoiler",
+            ReportReason::Other => "other",
+        }
+    }
+}
+
+/// Rating with optional review text
+#[derive(Debug, Clone, Serialize)]
+pub struct Review {
+    pub id: Uuid,
+    pub media_id: Uuid,
+    pub user_id: Uuid,
+    /// Stars from 1 to 5
+    pub rating: i16,
+    pub body: Option<String>,
+    pub status: ReviewStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request for rating and reviewing a media item
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReviewRequest {
+    pub rating: i16,
+    pub body: Option<String>,
+}
+
+impl ReviewRequest {
+    /// Validate the rating and text, returning the trimmed text
+    pub fn validate(&self) -> Result<Option<String>, String> {
+        if !(1..=5).contains(&self.rating) {
+            return Err("rating must be between 1 and 5".to_string());
+        }
+
+        let body = self
+            .body
+            .as_deref()
+            .map(str::trim)
+            .filter(|body| !body.is_empty());
+        if body.is_some_and(|body| body.chars().count() > MAX_REVIEW_LENGTH) {
+            return Err(format!(
+                "Review text must be at most {} characters",
+                MAX_REVIEW_LENGTH
+            ));
+        }
+
+        Ok(body.map(str::to_string))
+    }
+}
+
+/// Average rating of a media item
+#[derive(Debug, Clone, Serialize)]
+pub struct RatingSummary {
+    pub average: Option<f64>,
+    pub count: i64,
+}
+
+/// Query parameters for listing reviews
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReviewQuery {
+    /// Page number (1-based)
+    pub page: Option<u32>,
+    /// Number of reviews per page
+    pub limit: Option<u32>,
+}
+
+/// Page of reviews of a media item
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewListResponse {
+    pub items: Vec<Review>,
+    pub page: u32,
+    pub limit: u32,
+    pub total: i64,
+    pub rating: RatingSummary,
+}
+
+/// Request for reporting a review
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReportReviewRequest {
+    pub reason: ReportReason,
+    pub details: Option<String>,
+}
+
+impl ReportReviewRequest {
+    /// Validate the report details
+    pub fn validate(&self) -> Result<(), String> {
+        if self
+            .details
+            .as_ref()
+            .is_some_and(|details| details.chars().count() > MAX_REPORT_DETAILS_LENGTH)
+        {
+            return Err(format!(
+                "details must be at most {} characters",
+                MAX_REPORT_DETAILS_LENGTH
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Query parameters for the moderation queue
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModerationQuery {
+    /// Reviews in this state (default: flagged)
+    pub status: Option<ReviewStatus>,
+    /// Page number (1-based)
+    pub page: Option<u32>,
+    /// Number of reviews per page
+    pub limit: Option<u32>,
+}
+
+/// Review in the moderation queue with its open reports
+#[derive(Debug, Clone, Serialize)]
+pub struct ModerationItem {
+    #[serde(flatten)]
+    pub review: Review,
+    /// Reports not yet resolved by a moderator
+    pub open_reports: i64,
+    /// Distinct reasons of the open reports
+    pub reasons: Vec<String>,
+}
+
+/// Page of the moderation queue
+#[derive(Debug, Clone, Serialize)]
+pub struct ModerationListResponse {
+    pub items: Vec<ModerationItem>,
+    pub page: u32,
+    pub limit: u32,
+    pub total: i64,
+}
+
+/// Moderator decision on a review
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModerateReviewRequest {
+    /// `published` to keep the review or `hidden` to remove it
+    pub status: ReviewStatus,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_review_request() {
+        let request = |rating: i16, body: Option<&str>| ReviewRequest {
+            rating,
+            body: body.map(str::to_string),
+        };
+
+        assert_eq!(request(5, None).validate(), Ok(None));
+        assert_eq!(
+            request(3, Some("  Solid  ")).validate(),
+            Ok(Some("Solid".to_string()))
+        );
+        assert_eq!(request(4, Some("   ")).validate(), Ok(None));
+        assert!(request(0, None).validate().is_err());
+        assert!(request(6, None).validate().is_err());
+        assert!(
+            request(1, Some(&"x".repeat(MAX_REVIEW_LENGTH + 1)))
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_review_status_round_trip() {
+        for status in [
+            ReviewStatus::Published,
+            ReviewStatus::Flagged,
+            ReviewStatus::Hidden,
+        ] {
+            assert_eq!(ReviewStatus::parse(status.as_str()), Some(status));
+        }
+    }
+}
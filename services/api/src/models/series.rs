@@ -0,0 +1,106 @@
+//! Series, season and episode models for the API service
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::media::MediaItem;
+
+/// Series with counts of the episodes visible to the user
+#[derive(Debug, Clone, Serialize)]
+pub struct Series {
+    pub id: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub season_count: i64,
+    pub episode_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Season of a series with the user's watched count
+#[derive(Debug, Clone, Serialize)]
+pub struct SeasonSummary {
+    pub id: Uuid,
+    /// Season number, 0 for specials
+    pub number: i32,
+    pub title: Option<String>,
+    pub episode_count: i64,
+    /// Episodes the user has watched to the end
+    pub watched_count: i64,
+}
+
+/// Series with its seasons
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesDetail {
+    #[serde(flatten)]
+    pub series: Series,
+    /// Episodes of the series the user has watched to the end
+    pub watched_count: i64,
+    pub seasons: Vec<SeasonSummary>,
+}
+
+impl SeriesDetail {
+    /// Combine a series with its seasons, summing up the watched counts
+    pub fn new(series: Series, seasons: Vec<SeasonSummary>) -> Self {
+        Self {
+            series,
+            watched_count: seasons.iter().map(|season| season.watched_count).sum(),
+            seasons,
+        }
+    }
+}
+
+/// Season with its episodes in order
+#[derive(Debug, Clone, Serialize)]
+pub struct SeasonDetail {
+    pub series_id: Uuid,
+    pub number: i32,
+    pub episodes: Vec<MediaItem>,
+}
+
+/// Query parameters for listing series
+#[derive(Debug, Clone, Deserialize)]
+pub struct SeriesQuery {
+    /// Page number (1-based)
+    pub page: Option<u32>,
+    /// Number of series per page
+    pub limit: Option<u32>,
+}
+
+/// Page of series
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesListResponse {
+    pub items: Vec<Series>,
+    pub page: u32,
+    pub limit: u32,
+    pub total: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_series_detail_sums_watched_counts() {
+        let season = |number: i32, watched_count: i64| SeasonSummary {
+            id: Uuid::new_v4(),
+            number,
+            title: None,
+            episode_count: 10,
+            watched_count,
+        };
+        let series = Series {
+            id: Uuid::new_v4(),
+            title: "Firefly".to_string(),
+            description: None,
+            season_count: 2,
+            episode_count: 20,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        let detail = SeriesDetail::new(series, vec![season(1, 10), season(2, 3)]);
+        assert_eq!(detail.watched_count, 13);
+    }
+}
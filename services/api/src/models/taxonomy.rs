@@ -0,0 +1,144 @@
+//! Tag and genre models for the API service
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Maximum length of a tag or genre name
+pub const MAX_TAXONOMY_NAME_LENGTH: usize = 100;
+
+/// Admin-curated genre
+#[derive(Debug, Clone, Serialize)]
+pub struct Genre {
+    pub id: Uuid,
+    pub name: String,
+    pub slug: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request for creating or renaming a genre
+#[derive(Debug, Clone, Deserialize)]
+pub struct GenreRequest {
+    pub name: String,
+}
+
+impl GenreRequest {
+    /// Validated display name and slug of the genre
+    pub fn name_and_slug(&self) -> Result<(String, String), String> {
+        let name = normalize_name(&self.name);
+        if name.is_empty() || name.chars().count() > MAX_TAXONOMY_NAME_LENGTH {
+            return Err(format!(
+                "Genre name must be between 1 and {} characters",
+                MAX_TAXONOMY_NAME_LENGTH
+            ));
+        }
+        let slug = slugify(&name);
+        Ok((name, slug))
+    }
+}
+
+/// Request for setting the genres of a media item
+#[derive(Debug, Clone, Deserialize)]
+pub struct MediaGenresRequest {
+    pub genre_ids: Vec<Uuid>,
+}
+
+/// Query parameters for tag autocomplete
+#[derive(Debug, Clone, Deserialize)]
+pub struct TagSuggestQuery {
+    /// Partial tag input
+    pub q: String,
+    /// Maximum number of suggestions
+    pub limit: Option<u32>,
+}
+
+/// Tag suggested for autocomplete
+#[derive(Debug, Clone, Serialize)]
+pub struct TagSuggestion {
+    pub name: String,
+    pub slug: String,
+    /// Number of listed items carrying the tag
+    pub count: i64,
+}
+
+/// Number of matching items for one facet value
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+/// Counts of the items matching a media listing, by facet
+#[derive(Debug, Clone, Serialize)]
+pub struct MediaFacets {
+    /// Genre slugs
+    pub genres: Vec<FacetCount>,
+    /// Media types
+    pub types: Vec<FacetCount>,
+    /// Resolution buckets such as `1080p`, by height
+    pub resolutions: Vec<FacetCount>,
+}
+
+/// Collapse whitespace in a tag or genre name
+pub fn normalize_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Slug identifying a tag or genre: lowercase words joined by dashes
+///
+/// Must stay in line with the slugs computed by the migration that moved tags
+/// out of the metadata JSONB.
+pub fn slugify(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
+
+/// Normalize tag names, dropping empty ones and duplicates by slug
+pub fn normalize_tags<'a>(tags: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tags.into_iter()
+        .map(normalize_name)
+        .filter(|tag| !tag.is_empty() && seen.insert(slugify(tag)))
+        .collect()
+}
+
+/// Parse a comma separated filter of tag or genre names into slugs
+pub fn parse_slugs(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(slugify)
+        .filter(|slug| !slug.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugs() {
+        assert_eq!(slugify("  Science   Fiction "), "science-fiction");
+        assert_eq!(
+            normalize_tags(["Sci Fi", "sci  fi", " ", "Space"]),
+            vec!["Sci Fi".to_string(), "Space".to_string()]
+        );
+        assert_eq!(parse_slugs("Sci Fi, ,space"), vec!["sci-fi", "space"]);
+    }
+
+    #[test]
+    fn test_genre_request() {
+        let request = |name: &str| GenreRequest {
+            name: name.to_string(),
+        };
+
+        assert_eq!(
+            request(" Film  Noir ").name_and_slug().unwrap(),
+            ("Film Noir".to_string(), "film-noir".to_string())
+        );
+        assert!(request("  ").name_and_slug().is_err());
+    }
+}
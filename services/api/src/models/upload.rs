@@ -0,0 +1,171 @@
+//! Upload models for the API service
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Request for starting a direct-to-storage upload
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateUploadRequest {
+    /// Original file name
+    pub filename: String,
+    /// MIME type of the file (video/*, audio/* or image/*)
+    pub content_type: String,
+    /// File size in bytes
+    pub size: i64,
+    /// Optional title, defaults to the file name
+    pub title: Option<String>,
+}
+
+/// Presigned URL for one part of a multipart upload
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadPartUrl {
+    pub part_number: i32,
+    pub url: String,
+}
+
+/// Response for a started upload
+///
+/// Small files are uploaded with a single PUT to `url`. Large files are
+/// uploaded in `part_size` chunks to the URLs in `parts`, keeping the ETag
+/// returned for each part for the completion request.
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateUploadResponse {
+    pub upload_id: Uuid,
+    pub media_id: Uuid,
+    pub method: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<UploadPartUrl>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part_size: Option<i64>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Part of a multipart upload reported by the client on completion
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompletedUploadPart {
+    pub part_number: i32,
+    pub etag: String,
+}
+
+/// Request for completing an upload
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CompleteUploadRequest {
+    /// Uploaded parts, required for multipart uploads
+    #[serde(default)]
+    pub parts: Vec<CompletedUploadPart>,
+}
+
+/// Pending upload record
+#[derive(Debug, Clone)]
+pub struct MediaUpload {
+    pub id: Uuid,
+    pub media_item_id: Uuid,
+    pub user_id: Uuid,
+    pub s3_key: String,
+    pub content_type: String,
+    pub size: i64,
+    pub multipart_upload_id: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Resumable (tus) upload record
+#[derive(Debug, Clone)]
+pub struct TusUpload {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Total size of the upload in bytes
+    pub upload_length: i64,
+    /// Number of bytes received so far
+    pub upload_offset: i64,
+    /// Decoded `Upload-Metadata` of the creation request
+    pub metadata: serde_json::Value,
+    /// Object the upload is streamed to
+    pub s3_key: String,
+    /// S3 multipart upload receiving the chunks
+    pub multipart_upload_id: String,
+    /// Size of every part but the last
+    pub part_size: i64,
+    pub media_item_id: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl TusUpload {
+    /// Client supplied metadata value
+    pub fn metadata_value(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key).and_then(|value| value.as_str())
+    }
+}
+
+/// Media type stored for an uploaded content type
+pub fn media_type_for(content_type: &str) -> Option<&'static str> {
+    match content_type.split('/').next()? {
+        "video" => Some("video"),
+        "audio" => Some("audio"),
+        "image" => Some("image"),
+        _ => None,
+    }
+}
+
+/// Title of an uploaded item, defaulting to the file name without extension
+pub fn title_for(title: Option<&str>, filename: &str) -> String {
+    title
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            filename
+                .rsplit_once('.')
+                .map_or(filename, |(stem, _)| stem)
+                .to_string()
+        })
+}
+
+/// Reduce a client supplied file name to a safe object key segment
+pub fn sanitize_filename(filename: &str) -> String {
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or_default();
+    let sanitized: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let sanitized = sanitized.trim_start_matches('.');
+
+    if sanitized.is_empty() {
+        "upload".to_string()
+    } else {
+        sanitized.chars().take(200).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_media_type_for() {
+        assert_eq!(media_type_for("video/mp4"), Some("video"));
+        assert_eq!(media_type_for("audio/mpeg"), Some("audio"));
+        assert_eq!(media_type_for("application/pdf"), None);
+    }
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(
+            sanitize_filename("My Movie (2024).mkv"),
+            "My_Movie__2024_.mkv"
+        );
+        assert_eq!(sanitize_filename("../../etc/passwd"), "passwd");
+        assert_eq!(sanitize_filename("C:\\videos\\clip.mp4"), "clip.mp4");
+        assert_eq!(sanitize_filename(".."), "upload");
+    }
+}
@@ -0,0 +1,276 @@
+//! Signed, expiring playback URLs
+//!
+//! Media elements and CDNs cannot send our bearer token, so playback routes
+//! also accept URLs signed with HMAC-SHA256. The signature covers the media
+//! item, rendition, user, expiry and optionally the client IP, and is verified
+//! without touching the database.
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::env;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Rendition name of the original uploaded file
+pub const ORIGINAL_RENDITION: &str = "original";
+
+/// Playback URL configuration
+#[derive(Clone)]
+pub struct PlaybackConfig {
+    /// Key used to sign playback URLs
+    pub secret: Vec<u8>,
+    /// Lifetime of playback URLs in seconds
+    pub url_expiry: u64,
+}
+
+impl PlaybackConfig {
+    /// Create a new PlaybackConfig from environment variables
+    ///
+    /// # Environment Variables
+    /// - `PLAYBACK_URL_SECRET`: Key for signing playback URLs (required)
+    /// - `PLAYBACK_URL_EXPIRY`: Lifetime of playback URLs in seconds (default: 3600)
+    pub fn from_env() -> Result<Self, String> {
+        let secret = env::var("PLAYBACK_URL_SECRET")
+            .map_err(|_| "PLAYBACK_URL_SECRET environment variable not set".to_string())?;
+        if secret.len() < 32 {
+            return Err("PLAYBACK_URL_SECRET must be at least 32 characters".to_string());
+        }
+
+        let url_expiry = env::var("PLAYBACK_URL_EXPIRY")
+            .unwrap_or_else(|_| "3600".to_string()) // 1 hour
+            .parse()
+            .unwrap_or(3600);
+
+        Ok(PlaybackConfig {
+            secret: secret.into_bytes(),
+            url_expiry,
+        })
+    }
+}
+
+/// Query parameters of a signed playback URL
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PlaybackQuery {
+    pub expires: Option<i64>,
+    pub user: Option<Uuid>,
+    pub rendition: Option<String>,
+    /// Whether the signature is bound to the client IP
+    #[serde(default)]
+    pub bind_ip: bool,
+    pub signature: Option<String>,
+}
+
+impl PlaybackQuery {
+    /// Whether the request carries a playback signature
+    pub fn is_signed(&self) -> bool {
+        self.signature.is_some()
+    }
+}
+
+/// Access granted by a signed playback URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaybackGrant {
+    pub media_id: Uuid,
+    pub rendition: String,
+    pub user_id: Uuid,
+    /// Client IP the grant is bound to, if any
+    pub ip: Option<IpAddr>,
+    /// Expiry as a Unix timestamp
+    pub expires: i64,
+}
+
+impl PlaybackGrant {
+    /// Query string carrying this grant and its signature
+    fn query(&self, signature: &str) -> String {
+        let mut query = format!(
+            "expires={}&user={}&rendition={}&signature={}",
+            self.expires, self.user_id, self.rendition, signature
+        );
+        if self.ip.is_some() {
+            query.push_str("&bind_ip=true");
+        }
+        query
+    }
+
+    /// Canonical message covered by the signature
+    fn message(&self) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n{}",
+            self.media_id,
+            self.rendition,
+            self.user_id,
+            self.ip.map(|ip| ip.to_string()).unwrap_or_default(),
+            self.expires
+        )
+    }
+}
+
+/// Signs and verifies playback URLs
+#[derive(Clone)]
+pub struct PlaybackSigner {
+    config: PlaybackConfig,
+}
+
+impl PlaybackSigner {
+    /// Create a new signer
+    pub fn new(config: PlaybackConfig) -> Self {
+        Self { config }
+    }
+
+    /// Get the playback configuration
+    pub fn config(&self) -> &PlaybackConfig {
+        &self.config
+    }
+
+    fn mac(&self, grant: &PlaybackGrant) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.config.secret).expect("HMAC accepts any key length");
+        mac.update(grant.message().as_bytes());
+        mac
+    }
+
+    /// Sign a grant, returning the query string for the playback URL
+    pub fn sign(&self, grant: &PlaybackGrant) -> String {
+        let signature = URL_SAFE_NO_PAD.encode(self.mac(grant).finalize().into_bytes());
+        grant.query(&signature)
+    }
+
+    /// Verify a signed request for a rendition of a media item
+    ///
+    /// Returns the user the URL was issued to.
+    pub fn verify(
+        &self,
+        media_id: Uuid,
+        rendition: &str,
+        query: &PlaybackQuery,
+        client_ip: IpAddr,
+        now: i64,
+    ) -> Result<Uuid, ApiError> {
+        let (Some(expires), Some(user_id), Some(signature)) =
+            (query.expires, query.user, query.signature.as_deref())
+        else {
+            return Err(ApiError::Unauthorized);
+        };
+
+        if query.rendition.as_deref() != Some(rendition) || expires < now {
+            return Err(ApiError::Unauthorized);
+        }
+
+        let grant = PlaybackGrant {
+            media_id,
+            rendition: rendition.to_string(),
+            user_id,
+            ip: query.bind_ip.then_some(client_ip),
+            expires,
+        };
+
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| ApiError::Unauthorized)?;
+        self.mac(&grant)
+            .verify_slice(&signature)
+            .map_err(|_| ApiError::Unauthorized)?;
+
+        Ok(user_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer() -> PlaybackSigner {
+        PlaybackSigner::new(PlaybackConfig {
+            secret: b"0123456789abcdef0123456789abcdef".to_vec(),
+            url_expiry: 60,
+        })
+    }
+
+    fn query(signed: &str) -> PlaybackQuery {
+        let mut query = PlaybackQuery::default();
+        for (key, value) in signed.split('&').filter_map(|pair| pair.split_once('=')) {
+            match key {
+                "expires" => query.expires = value.parse().ok(),
+                "user" => query.user = value.parse().ok(),
+                "rendition" => query.rendition = Some(value.to_string()),
+                "bind_ip" => query.bind_ip = value == "true",
+                "signature" => query.signature = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        query
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signer = signer();
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let grant = PlaybackGrant {
+            media_id: Uuid::new_v4(),
+            rendition: ORIGINAL_RENDITION.to_string(),
+            user_id: Uuid::new_v4(),
+            ip: Some(ip),
+            expires: 1_000,
+        };
+        let signed = query(&signer.sign(&grant));
+
+        assert_eq!(
+            signer
+                .verify(grant.media_id, ORIGINAL_RENDITION, &signed, ip, 999)
+                .unwrap(),
+            grant.user_id
+        );
+
+        // Expired, other item, other rendition, other IP
+        assert!(
+            signer
+                .verify(grant.media_id, ORIGINAL_RENDITION, &signed, ip, 1_001)
+                .is_err()
+        );
+        assert!(
+            signer
+                .verify(Uuid::new_v4(), ORIGINAL_RENDITION, &signed, ip, 999)
+                .is_err()
+        );
+        assert!(
+            signer
+                .verify(grant.media_id, "720p", &signed, ip, 999)
+                .is_err()
+        );
+        assert!(
+            signer
+                .verify(
+                    grant.media_id,
+                    ORIGINAL_RENDITION,
+                    &signed,
+                    "203.0.113.8".parse().unwrap(),
+                    999
+                )
+                .is_err()
+        );
+
+        // Tampered user
+        let mut tampered = signed.clone();
+        tampered.user = Some(Uuid::new_v4());
+        assert!(
+            signer
+                .verify(grant.media_id, ORIGINAL_RENDITION, &tampered, ip, 999)
+                .is_err()
+        );
+
+        // Dropping the IP binding invalidates the signature
+        let mut unbound = signed;
+        unbound.bind_ip = false;
+        assert!(
+            signer
+                .verify(grant.media_id, ORIGINAL_RENDITION, &unbound, ip, 999)
+                .is_err()
+        );
+    }
+}
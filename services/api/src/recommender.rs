@@ -0,0 +1,80 @@
+//! "More like this" and personalized recommendations
+//!
+//! Item-item similarities are precomputed in Postgres by a periodic rebuild.
+//! Candidate lists are cached in Redis, while visibility is always checked
+//! against the database so a cached list never exposes items the caller may
+//! not see.
+
+use anyhow::Result;
+use common::cache::RedisPool;
+use uuid::Uuid;
+
+use crate::{
+    cache::cached, middleware::AuthUser, models::recommendation::Recommendation,
+    repositories::recommendation::RecommendationRepository,
+};
+
+/// Recommendations backed by the similarity table and a Redis cache
+#[derive(Clone)]
+pub struct Recommender {
+    repository: RecommendationRepository,
+    cache: RedisPool,
+}
+
+impl Recommender {
+    /// Create a new recommender
+    pub fn new(repository: RecommendationRepository, cache: RedisPool) -> Self {
+        Self { repository, cache }
+    }
+
+    /// Get the items most similar to a media item that the user may list
+    pub async fn similar(
+        &self,
+        media_id: Uuid,
+        user: &AuthUser,
+        limit: u32,
+    ) -> Result<Vec<Recommendation>> {
+        let key = format!("recommendations:similar:{}", media_id);
+        let candidates = cached(
+            &self.cache,
+            &key,
+            self.repository.config().cache_ttl,
+            self.repository.similar_candidates(media_id),
+        )
+        .await?;
+
+        self.repository.items(user, &candidates, limit).await
+    }
+
+    /// Get personalized recommendations for the user's profile
+    pub async fn for_user(&self, user: &AuthUser, limit: u32) -> Result<Vec<Recommendation>> {
+        let key = match user.profile_id {
+            Some(profile_id) => format!("recommendations:user:{}:{}", user.id, profile_id),
+            None => format!("recommendations:user:{}", user.id),
+        };
+        let candidates = cached(
+            &self.cache,
+            &key,
+            self.repository.config().cache_ttl,
+            self.repository.user_candidates(user),
+        )
+        .await?;
+
+        self.repository.items(user, &candidates, limit).await
+    }
+
+    /// Rebuild the similarity table every refresh interval
+    pub async fn run_refresh_loop(self) {
+        let period = std::time::Duration::from_secs(self.repository.config().refresh_interval);
+        let mut interval = tokio::time::interval(period);
+
+        loop {
+            interval.tick().await;
+            match self.repository.refresh().await {
+                Ok(Some(pairs)) => tracing::info!("Rebuilt media similarities ({} pairs)", pairs),
+                Ok(None) => tracing::debug!("Media similarities are rebuilt by another instance"),
+                Err(e) => tracing::error!("Failed to rebuild media similarities: {}", e),
+            }
+        }
+    }
+}
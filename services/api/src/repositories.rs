@@ -6,7 +6,22 @@ use uuid::Uuid;
 
 use crate::models::{CreateUserRequest, SessionResponse, UserResponse};
 
+pub mod access;
+pub mod collection;
+pub mod comment;
+pub mod history;
 pub mod media;
+pub mod notification;
+pub mod parental;
+pub mod profile;
+pub mod progress;
+pub mod recommendation;
+pub mod rendition;
+pub mod review;
+pub mod series;
+pub mod taxonomy;
+pub mod tus;
+pub mod upload;
 
 /// User repository for database operations
 #[derive(Clone)]
@@ -100,6 +115,33 @@ impl UserRepository {
             None => Ok(None),
         }
     }
+
+    /// Get one value of the user's settings
+    pub async fn get_setting(&self, id: Uuid, key: &str) -> Result<Option<serde_json::Value>> {
+        let value: Option<Option<serde_json::Value>> =
+            sqlx::query_scalar("SELECT settings -> $2 FROM users WHERE id = $1")
+                .bind(id)
+                .bind(key)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(value.flatten())
+    }
+
+    /// Set one value of the user's settings, keeping the others
+    pub async fn set_setting(&self, id: Uuid, key: &str, value: &serde_json::Value) -> Result<()> {
+        sqlx::query(
+            "UPDATE users SET settings = jsonb_set(settings, ARRAY[$2], $3, true), updated_at = NOW()
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(key)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
 }
 
 /// Session repository for database operations
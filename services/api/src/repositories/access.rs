@@ -0,0 +1,162 @@
+//! Access rules for media items shared by the repositories
+
+use sqlx::{Postgres, QueryBuilder};
+
+use crate::middleware::AuthUser;
+
+/// How a media item is reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reach {
+    /// Opened by ID, so unlisted items are included
+    Direct,
+    /// Listed in a catalog, feed or search, so unlisted items are left out
+    Listed,
+}
+
+/// Append the predicate for media items the user may see
+///
+/// `alias` is the name of the media_items table in the query. Users see
+/// their own items, public items and items shared with them, plus unlisted
+/// items when reached directly; admins see every item. Deleted items and
+/// items above the user's maturity limit, which applies to admins too, are
+/// always excluded.
+pub fn push_visible(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    alias: &str,
+    user: &AuthUser,
+    reach: Reach,
+) {
+    builder.push(format!("{alias}.deleted_at IS NULL"));
+
+    if !user.is_admin() {
+        let visible = match reach {
+            Reach::Direct => "IN ('public', 'unlisted')",
+            Reach::Listed => "= 'public'",
+        };
+        builder
+            .push(format!(" AND ({alias}.user_id = "))
+            .push_bind(user.id)
+            .push(format!(
+                " OR {alias}.visibility {visible} OR ({alias}.visibility = 'shared' AND EXISTS (\
+                 SELECT 1 FROM media_item_shares s \
+                 WHERE s.media_item_id = {alias}.id AND s.user_id = "
+            ))
+            .push_bind(user.id)
+            .push(")))");
+    }
+
+    if let Some(ratings) = user.allowed_ratings() {
+        builder
+            .push(format!(" AND {alias}.maturity_rating = ANY("))
+            .push_bind(ratings)
+            .push(")");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::profile::MaturityLevel;
+    use crate::repositories::media::MediaRepository;
+    use uuid::Uuid;
+
+    fn user(roles: &[&str]) -> AuthUser {
+        AuthUser {
+            id: Uuid::new_v4(),
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+            permissions: vec![],
+            actor_id: None,
+            read_only: false,
+            profile_id: None,
+            max_rating: None,
+        }
+    }
+
+    fn visible_sql(user: &AuthUser, reach: Reach) -> String {
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT m.id FROM media_items m WHERE ");
+        push_visible(&mut builder, "m", user, reach);
+        builder.sql().to_string()
+    }
+
+    #[test]
+    fn test_direct_reach_includes_unlisted() {
+        assert_eq!(
+            visible_sql(&user(&[]), Reach::Direct),
+            "SELECT m.id FROM media_items m WHERE m.deleted_at IS NULL \
+             AND (m.user_id = $1 OR m.visibility IN ('public', 'unlisted') \
+             OR (m.visibility = 'shared' AND EXISTS (SELECT 1 FROM media_item_shares s \
+             WHERE s.media_item_id = m.id AND s.user_id = $2)))"
+        );
+    }
+
+    #[test]
+    fn test_listed_reach_excludes_unlisted() {
+        assert_eq!(
+            visible_sql(&user(&[]), Reach::Listed),
+            "SELECT m.id FROM media_items m WHERE m.deleted_at IS NULL \
+             AND (m.user_id = $1 OR m.visibility = 'public' \
+             OR (m.visibility = 'shared' AND EXISTS (SELECT 1 FROM media_item_shares s \
+             WHERE s.media_item_id = m.id AND s.user_id = $2)))"
+        );
+    }
+
+    #[test]
+    fn test_admin_bypasses_visibility_but_not_maturity_limit() {
+        let mut admin = user(&["admin"]);
+        assert_eq!(
+            visible_sql(&admin, Reach::Direct),
+            "SELECT m.id FROM media_items m WHERE m.deleted_at IS NULL"
+        );
+
+        admin.max_rating = Some(MaturityLevel::Teen);
+        assert_eq!(
+            visible_sql(&admin, Reach::Listed),
+            "SELECT m.id FROM media_items m WHERE m.deleted_at IS NULL \
+             AND m.maturity_rating = ANY($1)"
+        );
+    }
+
+    /// Check who can open a shared item against a migrated database
+    ///
+    /// Run with `DATABASE_URL` set and `cargo test -- --ignored`.
+    #[tokio::test]
+    #[ignore = "requires a migrated PostgreSQL database"]
+    async fn test_get_by_id_visibility() -> anyhow::Result<()> {
+        let pool = sqlx::PgPool::connect(&std::env::var("DATABASE_URL")?).await?;
+        let (owner, shared, stranger, admin) = (user(&[]), user(&[]), user(&[]), user(&["admin"]));
+        for user in [&owner, &shared, &stranger, &admin] {
+            sqlx::query("INSERT INTO users (id, email, provider) VALUES ($1, $2, 'test')")
+                .bind(user.id)
+                .bind(format!("{}@visibility.test", user.id))
+                .execute(&pool)
+                .await?;
+        }
+        let item_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO media_items (type, s3_key, user_id, visibility)
+             VALUES ('video', 'visibility-test', $1, 'shared') RETURNING id",
+        )
+        .bind(owner.id)
+        .fetch_one(&pool)
+        .await?;
+        sqlx::query("INSERT INTO media_item_shares (media_item_id, user_id) VALUES ($1, $2)")
+            .bind(item_id)
+            .bind(shared.id)
+            .execute(&pool)
+            .await?;
+
+        let repo = MediaRepository::new(pool.clone());
+        let mut seen = Vec::new();
+        for user in [&owner, &shared, &stranger, &admin] {
+            seen.push(repo.get_by_id(item_id, user).await?.is_some());
+        }
+
+        sqlx::query("DELETE FROM users WHERE id = ANY($1)")
+            .bind(vec![owner.id, shared.id, stranger.id, admin.id])
+            .execute(&pool)
+            .await?;
+
+        // The stranger gets no item, which the handler reports as 404
+        assert_eq!(seen, vec![true, true, false, true]);
+        Ok(())
+    }
+}
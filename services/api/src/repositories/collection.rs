@@ -0,0 +1,402 @@
+//! Collection repository for database operations
+
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, QueryBuilder, Row, postgres::PgRow};
+use uuid::Uuid;
+
+use crate::{
+    middleware::AuthUser,
+    models::{
+        collection::{Collection, CollectionKind},
+        media::MediaItem,
+    },
+    repositories::{
+        access::{Reach, push_visible},
+        media::media_item_from_row,
+    },
+};
+
+/// Columns of a collection together with its item count
+const COLLECTION_COLUMNS: &str = r#"
+    c.id, c.user_id, c.profile_id, c.name, c.kind, c.share_token, c.created_at, c.updated_at,
+    (SELECT COUNT(*) FROM collection_items ci WHERE ci.collection_id = c.id) AS item_count
+"#;
+
+/// Columns of a media item joined as `m` to `collection_items ci`
+const ITEM_COLUMNS: &str = r#"
+    m.id, m.type, m.metadata, m.s3_key, m.status, m.user_id, m.created_at,
+    m.updated_at, m.duration, m.width, m.height, m.video_codec, m.audio_codec,
+    m.format, m.bitrate, m.sample_rate, m.channels, m.thumbnail_url,
+    m.visibility, m.deleted_at, m.kind, m.season_id, m.episode_number,
+    m.maturity_rating
+"#;
+
+/// Collection repository for database operations
+///
+/// Collections belong to the viewer profile they were created with.
+#[derive(Clone)]
+pub struct CollectionRepository {
+    pool: PgPool,
+}
+
+impl CollectionRepository {
+    /// Create a new collection repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create the built-in collections of the user's profile if they do not exist yet
+    pub async fn ensure_builtin(&self, user: &AuthUser) -> Result<()> {
+        for kind in CollectionKind::BUILT_IN {
+            sqlx::query(
+                "INSERT INTO collections (user_id, profile_id, name, kind) VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (user_id, profile_id, kind) WHERE kind <> 'custom' DO NOTHING",
+            )
+            .bind(user.id)
+            .bind(user.profile_id)
+            .bind(kind.default_name())
+            .bind(kind.as_str())
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// List the collections of the user's profile, built-in ones first
+    pub async fn list(&self, user: &AuthUser) -> Result<Vec<Collection>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {COLLECTION_COLUMNS} FROM collections c
+             WHERE c.user_id = $1 AND c.profile_id IS NOT DISTINCT FROM $2
+             ORDER BY c.kind = 'custom', c.kind, c.created_at"
+        ))
+        .bind(user.id)
+        .bind(user.profile_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(collection_from_row).collect())
+    }
+
+    /// Create a custom collection
+    pub async fn create(&self, user: &AuthUser, name: &str) -> Result<Collection> {
+        let id: Uuid = sqlx::query_scalar(
+            "INSERT INTO collections (user_id, profile_id, name, kind)
+             VALUES ($1, $2, $3, 'custom') RETURNING id",
+        )
+        .bind(user.id)
+        .bind(user.profile_id)
+        .bind(name)
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.get(id, user)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Created collection {} not found", id))
+    }
+
+    /// Get one of the collections of the user's profile
+    pub async fn get(&self, id: Uuid, user: &AuthUser) -> Result<Option<Collection>> {
+        let row = sqlx::query(&format!(
+            "SELECT {COLLECTION_COLUMNS} FROM collections c
+             WHERE c.id = $1 AND c.user_id = $2 AND c.profile_id IS NOT DISTINCT FROM $3"
+        ))
+        .bind(id)
+        .bind(user.id)
+        .bind(user.profile_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(collection_from_row))
+    }
+
+    /// Get a collection by its share token
+    pub async fn get_shared(&self, token: &str) -> Result<Option<Collection>> {
+        let row = sqlx::query(&format!(
+            "SELECT {COLLECTION_COLUMNS} FROM collections c WHERE c.share_token = $1"
+        ))
+        .bind(token)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(collection_from_row))
+    }
+
+    /// Get the items of a collection in order
+    ///
+    /// Items the user can no longer see or that are above the user's maturity
+    /// limit are left out.
+    pub async fn items(&self, collection_id: Uuid, user: &AuthUser) -> Result<Vec<MediaItem>> {
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {ITEM_COLUMNS} FROM collection_items ci \
+             JOIN media_items m ON m.id = ci.media_item_id \
+             WHERE ci.collection_id = "
+        ));
+        builder.push_bind(collection_id).push(" AND ");
+        push_visible(&mut builder, "m", user, Reach::Direct);
+        builder.push(" ORDER BY ci.position, ci.added_at");
+        let rows = builder.build().fetch_all(&self.pool).await?;
+
+        Ok(rows.iter().map(media_item_from_row).collect())
+    }
+
+    /// Get the items of a collection visible through its share link
+    ///
+    /// Only public and unlisted items are included.
+    pub async fn shared_items(&self, collection_id: Uuid) -> Result<Vec<MediaItem>> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT {ITEM_COLUMNS}
+            FROM collection_items ci
+            JOIN media_items m ON m.id = ci.media_item_id
+            WHERE ci.collection_id = $1 AND m.deleted_at IS NULL
+              AND m.visibility IN ('public', 'unlisted')
+            ORDER BY ci.position, ci.added_at
+            "#
+        ))
+        .bind(collection_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(media_item_from_row).collect())
+    }
+
+    /// Rename a custom collection
+    pub async fn rename(&self, id: Uuid, user: &AuthUser, name: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE collections SET name = $4
+             WHERE id = $1 AND user_id = $2 AND profile_id IS NOT DISTINCT FROM $3
+               AND kind = 'custom'",
+        )
+        .bind(id)
+        .bind(user.id)
+        .bind(user.profile_id)
+        .bind(name)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete a custom collection
+    pub async fn delete(&self, id: Uuid, user: &AuthUser) -> Result<bool> {
+        let result = sqlx::query(
+            "DELETE FROM collections
+             WHERE id = $1 AND user_id = $2 AND profile_id IS NOT DISTINCT FROM $3
+               AND kind = 'custom'",
+        )
+        .bind(id)
+        .bind(user.id)
+        .bind(user.profile_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Add a media item to a collection
+    ///
+    /// The item is appended unless a 1-based `position` is given, in which
+    /// case the items from that position on move down. Returns `false` if the
+    /// item is already in the collection.
+    pub async fn add_item(
+        &self,
+        collection_id: Uuid,
+        media_id: Uuid,
+        position: Option<i32>,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        // Serialize changes to the order of this collection
+        sqlx::query("SELECT id FROM collections WHERE id = $1 FOR UPDATE")
+            .bind(collection_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM collection_items
+                            WHERE collection_id = $1 AND media_item_id = $2)",
+        )
+        .bind(collection_id)
+        .bind(media_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if exists {
+            return Ok(false);
+        }
+
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM collection_items WHERE collection_id = $1")
+                .bind(collection_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        let position = match position {
+            Some(position) => position.clamp(1, count as i32 + 1),
+            None => count as i32 + 1,
+        };
+
+        sqlx::query(
+            "UPDATE collection_items SET position = position + 1
+             WHERE collection_id = $1 AND position >= $2",
+        )
+        .bind(collection_id)
+        .bind(position)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO collection_items (collection_id, media_item_id, position)
+             VALUES ($1, $2, $3)",
+        )
+        .bind(collection_id)
+        .bind(media_id)
+        .bind(position)
+        .execute(&mut *tx)
+        .await?;
+
+        touch(&mut tx, collection_id).await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Remove a media item from a collection, closing the gap it leaves
+    pub async fn remove_item(&self, collection_id: Uuid, media_id: Uuid) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        // Serialize changes to the order of this collection
+        sqlx::query("SELECT id FROM collections WHERE id = $1 FOR UPDATE")
+            .bind(collection_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let position: Option<i32> = sqlx::query_scalar(
+            "DELETE FROM collection_items WHERE collection_id = $1 AND media_item_id = $2
+             RETURNING position",
+        )
+        .bind(collection_id)
+        .bind(media_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(position) = position else {
+            return Ok(false);
+        };
+
+        sqlx::query(
+            "UPDATE collection_items SET position = position - 1
+             WHERE collection_id = $1 AND position > $2",
+        )
+        .bind(collection_id)
+        .bind(position)
+        .execute(&mut *tx)
+        .await?;
+
+        touch(&mut tx, collection_id).await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Put the items of a collection in the given order
+    ///
+    /// `media_ids` must list every item of the collection exactly once;
+    /// returns `false` otherwise.
+    pub async fn reorder(&self, collection_id: Uuid, media_ids: &[Uuid]) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("SELECT id FROM collections WHERE id = $1 FOR UPDATE")
+            .bind(collection_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut current: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT media_item_id FROM collection_items WHERE collection_id = $1",
+        )
+        .bind(collection_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let mut requested = media_ids.to_vec();
+        current.sort();
+        requested.sort();
+        requested.dedup();
+        if current != requested || requested.len() != media_ids.len() {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            "UPDATE collection_items ci SET position = o.position
+             FROM unnest($2::uuid[]) WITH ORDINALITY AS o(media_item_id, position)
+             WHERE ci.collection_id = $1 AND ci.media_item_id = o.media_item_id",
+        )
+        .bind(collection_id)
+        .bind(media_ids)
+        .execute(&mut *tx)
+        .await?;
+
+        touch(&mut tx, collection_id).await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Create a share link for a collection, keeping an existing one
+    pub async fn share(&self, id: Uuid, user: &AuthUser) -> Result<Option<String>> {
+        let token: Option<Option<String>> = sqlx::query_scalar(
+            "UPDATE collections SET share_token = COALESCE(share_token, $4)
+             WHERE id = $1 AND user_id = $2 AND profile_id IS NOT DISTINCT FROM $3
+             RETURNING share_token",
+        )
+        .bind(id)
+        .bind(user.id)
+        .bind(user.profile_id)
+        .bind(Uuid::new_v4().simple().to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(token.flatten())
+    }
+
+    /// Revoke the share link of a collection
+    pub async fn unshare(&self, id: Uuid, user: &AuthUser) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE collections SET share_token = NULL
+             WHERE id = $1 AND user_id = $2 AND profile_id IS NOT DISTINCT FROM $3
+               AND share_token IS NOT NULL",
+        )
+        .bind(id)
+        .bind(user.id)
+        .bind(user.profile_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// Mark a collection as updated
+async fn touch(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>, collection_id: Uuid) -> Result<()> {
+    sqlx::query("UPDATE collections SET updated_at = NOW() WHERE id = $1")
+        .bind(collection_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+/// Map a collections row selected with `COLLECTION_COLUMNS`
+fn collection_from_row(row: &PgRow) -> Collection {
+    let kind: String = row.get("kind");
+
+    Collection {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        profile_id: row.get("profile_id"),
+        name: row.get("name"),
+        kind: CollectionKind::parse(&kind).unwrap_or(CollectionKind::Custom),
+        item_count: row.get("item_count"),
+        share_token: row.get("share_token"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
@@ -0,0 +1,331 @@
+//! Comment repository for database operations
+
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, QueryBuilder, Row, Transaction, postgres::PgRow};
+use uuid::Uuid;
+
+use crate::{
+    middleware::AuthUser,
+    models::comment::{
+        COMMENT_CHANNEL, Comment, CommentCursor, CommentEventKind, CommentNotification,
+        CommentStatus, parse_mentions,
+    },
+};
+
+/// Columns of a comment, including its number of published replies
+const COMMENT_COLUMNS: &str = r#"
+    c.id, c.media_item_id, c.user_id, c.parent_id, c.timestamp_seconds, c.body, c.status,
+    c.edited_at, c.deleted_at, c.created_at,
+    (SELECT COUNT(*) FROM comments r
+     WHERE r.parent_id = c.id AND r.status = 'published' AND r.deleted_at IS NULL) AS reply_count
+"#;
+
+/// Comment repository for database operations
+///
+/// Every change to a comment sends a notification on [`COMMENT_CHANNEL`]
+/// when its transaction commits, which feeds the live comment subscriptions.
+#[derive(Clone)]
+pub struct CommentRepository {
+    pool: PgPool,
+}
+
+impl CommentRepository {
+    /// Create a new comment repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Post a comment, notifying the users it mentions
+    pub async fn create(
+        &self,
+        media_id: Uuid,
+        user_id: Uuid,
+        parent_id: Option<Uuid>,
+        timestamp: Option<f64>,
+        body: &str,
+    ) -> Result<Comment> {
+        let mut tx = self.pool.begin().await?;
+
+        let id: Uuid = sqlx::query_scalar(
+            "INSERT INTO comments (media_item_id, user_id, parent_id, timestamp_seconds, body)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING id",
+        )
+        .bind(media_id)
+        .bind(user_id)
+        .bind(parent_id)
+        .bind(timestamp)
+        .bind(body)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        notify_mentions(&mut tx, id, body).await?;
+        notify_change(&mut tx, CommentEventKind::Created, id).await?;
+        let comment = fetch(&mut tx, id).await?;
+        tx.commit().await?;
+
+        comment.ok_or_else(|| anyhow::anyhow!("Comment {} vanished after insert", id))
+    }
+
+    /// Get a comment by ID
+    pub async fn get(&self, id: Uuid) -> Result<Option<Comment>> {
+        let row = sqlx::query(&format!(
+            "SELECT {COMMENT_COLUMNS} FROM comments c WHERE c.id = $1"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(comment_from_row))
+    }
+
+    /// List the top-level comments of a media item, or the replies to a comment
+    ///
+    /// Top-level comments are listed newest first, replies oldest first. Only
+    /// published comments are listed, except that users always see their own
+    /// comments and admins see every comment. Deleted comments are kept as
+    /// placeholders while they have replies. Returns the cursor of the next
+    /// page, if there is one.
+    pub async fn list_thread(
+        &self,
+        media_id: Uuid,
+        parent_id: Option<Uuid>,
+        user: &AuthUser,
+        cursor: Option<&CommentCursor>,
+        limit: u32,
+    ) -> Result<(Vec<Comment>, Option<CommentCursor>)> {
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {COMMENT_COLUMNS} FROM comments c WHERE c.media_item_id = "
+        ));
+        builder.push_bind(media_id);
+        match parent_id {
+            Some(parent_id) => {
+                builder.push(" AND c.parent_id = ").push_bind(parent_id);
+            }
+            None => {
+                builder.push(" AND c.parent_id IS NULL");
+            }
+        }
+        builder
+            .push(" AND (c.status = 'published' OR c.user_id = ")
+            .push_bind(user.id)
+            .push(" OR ")
+            .push_bind(user.is_admin())
+            .push(
+                ") AND (c.deleted_at IS NULL OR EXISTS (
+                    SELECT 1 FROM comments r WHERE r.parent_id = c.id AND r.deleted_at IS NULL
+                ))",
+            );
+
+        let order = if parent_id.is_some() { "ASC" } else { "DESC" };
+        self.fetch_page(builder, cursor, order, limit).await
+    }
+
+    /// List comments in a moderation state across all media items, newest first
+    pub async fn list_by_status(
+        &self,
+        status: CommentStatus,
+        cursor: Option<&CommentCursor>,
+        limit: u32,
+    ) -> Result<(Vec<Comment>, Option<CommentCursor>)> {
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {COMMENT_COLUMNS} FROM comments c WHERE c.deleted_at IS NULL AND c.status = "
+        ));
+        builder.push_bind(status.as_str());
+
+        self.fetch_page(builder, cursor, "DESC", limit).await
+    }
+
+    /// Replace the text of a comment, notifying newly mentioned users
+    pub async fn update(&self, id: Uuid, body: &str) -> Result<Option<Comment>> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            "UPDATE comments SET body = $2, edited_at = NOW()
+             WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(body)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        notify_mentions(&mut tx, id, body).await?;
+        notify_change(&mut tx, CommentEventKind::Updated, id).await?;
+        let comment = fetch(&mut tx, id).await?;
+        tx.commit().await?;
+
+        Ok(comment)
+    }
+
+    /// Delete a comment, keeping its place in the thread for the replies
+    pub async fn delete(&self, id: Uuid) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            "UPDATE comments SET body = '', deleted_at = NOW()
+             WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            notify_change(&mut tx, CommentEventKind::Removed, id).await?;
+        }
+        tx.commit().await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Set the moderation state of a comment
+    pub async fn moderate(&self, id: Uuid, status: CommentStatus) -> Result<Option<Comment>> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query("UPDATE comments SET status = $2 WHERE id = $1")
+            .bind(id)
+            .bind(status.as_str())
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let kind = match status {
+            CommentStatus::Published => CommentEventKind::Updated,
+            CommentStatus::Hidden => CommentEventKind::Removed,
+        };
+        notify_change(&mut tx, kind, id).await?;
+        let comment = fetch(&mut tx, id).await?;
+        tx.commit().await?;
+
+        Ok(comment)
+    }
+
+    /// Order, limit and fetch a comment listing after an optional cursor
+    async fn fetch_page(
+        &self,
+        mut builder: QueryBuilder<'_, Postgres>,
+        cursor: Option<&CommentCursor>,
+        order: &str,
+        limit: u32,
+    ) -> Result<(Vec<Comment>, Option<CommentCursor>)> {
+        if let Some(cursor) = cursor {
+            let operator = if order == "ASC" { ">" } else { "<" };
+            builder
+                .push(format!(" AND (c.created_at, c.id) {} (", operator))
+                .push_bind(cursor.created_at)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
+        }
+
+        builder
+            .push(format!(
+                " ORDER BY c.created_at {order}, c.id {order} LIMIT "
+            ))
+            .push_bind(limit as i64 + 1);
+
+        let rows = builder.build().fetch_all(&self.pool).await?;
+        let mut comments: Vec<Comment> = rows.iter().map(comment_from_row).collect();
+
+        let next_cursor = if comments.len() > limit as usize {
+            comments.truncate(limit as usize);
+            comments.last().map(|comment| CommentCursor {
+                created_at: comment.created_at,
+                id: comment.id,
+            })
+        } else {
+            None
+        };
+
+        Ok((comments, next_cursor))
+    }
+}
+
+/// Load a comment inside a transaction
+async fn fetch(tx: &mut Transaction<'_, Postgres>, id: Uuid) -> Result<Option<Comment>> {
+    let row = sqlx::query(&format!(
+        "SELECT {COMMENT_COLUMNS} FROM comments c WHERE c.id = $1"
+    ))
+    .bind(id)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    Ok(row.as_ref().map(comment_from_row))
+}
+
+/// Notify the users mentioned in a comment
+///
+/// Only users who can see the media item are notified, never the author,
+/// and each user at most once per comment.
+async fn notify_mentions(tx: &mut Transaction<'_, Postgres>, id: Uuid, body: &str) -> Result<()> {
+    let mentions = parse_mentions(body);
+    if mentions.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO notifications (user_id, kind, media_item_id, comment_id, actor_id)
+        SELECT u.id, 'mention', c.media_item_id, c.id, c.user_id
+        FROM comments c
+        JOIN media_items m ON m.id = c.media_item_id
+        JOIN users u ON LOWER(u.email) = ANY($2)
+        WHERE c.id = $1 AND c.status = 'published' AND u.id <> c.user_id
+          AND (m.user_id = u.id OR m.visibility IN ('public', 'unlisted')
+               OR (m.visibility = 'shared' AND EXISTS (
+                   SELECT 1 FROM media_item_shares s
+                   WHERE s.media_item_id = m.id AND s.user_id = u.id
+               )))
+        ON CONFLICT (user_id, comment_id, kind) DO NOTHING
+        "#,
+    )
+    .bind(id)
+    .bind(&mentions)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Announce a change to a comment to live subscribers once the transaction commits
+async fn notify_change(
+    tx: &mut Transaction<'_, Postgres>,
+    kind: CommentEventKind,
+    comment_id: Uuid,
+) -> Result<()> {
+    let payload = serde_json::to_string(&CommentNotification { kind, comment_id })?;
+
+    sqlx::query("SELECT pg_notify($1, $2)")
+        .bind(COMMENT_CHANNEL)
+        .bind(payload)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+/// Map a comments row selected with `COMMENT_COLUMNS`
+fn comment_from_row(row: &PgRow) -> Comment {
+    let status: String = row.get("status");
+    let deleted_at: Option<chrono::DateTime<chrono::Utc>> = row.get("deleted_at");
+
+    Comment {
+        id: row.get("id"),
+        media_id: row.get("media_item_id"),
+        user_id: row.get("user_id"),
+        parent_id: row.get("parent_id"),
+        timestamp: row.get("timestamp_seconds"),
+        body: deleted_at.is_none().then(|| row.get("body")),
+        status: CommentStatus::parse(&status).unwrap_or(CommentStatus::Hidden),
+        reply_count: row.get("reply_count"),
+        edited_at: row.get("edited_at"),
+        deleted_at,
+        created_at: row.get("created_at"),
+    }
+}
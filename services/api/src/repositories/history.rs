@@ -0,0 +1,135 @@
+//! Watch history repository for database operations
+
+use anyhow::Result;
+use sqlx::{PgPool, Row, postgres::PgRow};
+use uuid::Uuid;
+
+use crate::{
+    middleware::AuthUser,
+    models::history::{HistoryEntry, NewHistoryEntry},
+};
+
+/// Watch history repository for database operations
+///
+/// The history is append-only: entries are never updated, only deleted by
+/// their owner. Each viewer profile has its own history.
+#[derive(Clone)]
+pub struct HistoryRepository {
+    pool: PgPool,
+}
+
+impl HistoryRepository {
+    /// Create a new watch history repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Append a viewing session to the history of the user's profile
+    pub async fn append(&self, user: &AuthUser, entry: &NewHistoryEntry) -> Result<HistoryEntry> {
+        let row = sqlx::query(
+            r#"
+            WITH inserted AS (
+                INSERT INTO watch_history
+                    (user_id, profile_id, media_item_id, start_position, end_position, device)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING id, media_item_id, start_position, end_position, device, watched_at
+            )
+            SELECT inserted.*, m.title
+            FROM inserted
+            JOIN media_items m ON m.id = inserted.media_item_id
+            "#,
+        )
+        .bind(user.id)
+        .bind(user.profile_id)
+        .bind(entry.media_id)
+        .bind(entry.start_position)
+        .bind(entry.end_position)
+        .bind(&entry.device)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(history_entry_from_row(&row))
+    }
+
+    /// List the history of the user's profile, most recent first, with the total count
+    pub async fn list(
+        &self,
+        user: &AuthUser,
+        page: u32,
+        limit: u32,
+    ) -> Result<(Vec<HistoryEntry>, i64)> {
+        let offset = (page - 1) * limit;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT h.id, h.media_item_id, h.start_position, h.end_position, h.device,
+                   h.watched_at, m.title
+            FROM watch_history h
+            JOIN media_items m ON m.id = h.media_item_id
+            WHERE h.user_id = $1 AND h.profile_id IS NOT DISTINCT FROM $2
+            ORDER BY h.watched_at DESC, h.id DESC
+            LIMIT $3 OFFSET $4
+            "#,
+        )
+        .bind(user.id)
+        .bind(user.profile_id)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM watch_history
+             WHERE user_id = $1 AND profile_id IS NOT DISTINCT FROM $2",
+        )
+        .bind(user.id)
+        .bind(user.profile_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((rows.iter().map(history_entry_from_row).collect(), total))
+    }
+
+    /// Delete one entry of the history of the user's profile
+    ///
+    /// Returns `false` if the entry does not exist or belongs to someone else.
+    pub async fn delete(&self, user: &AuthUser, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "DELETE FROM watch_history
+             WHERE id = $1 AND user_id = $2 AND profile_id IS NOT DISTINCT FROM $3",
+        )
+        .bind(id)
+        .bind(user.id)
+        .bind(user.profile_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete the entire history of the user's profile, returning the number of entries removed
+    pub async fn clear(&self, user: &AuthUser) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM watch_history WHERE user_id = $1 AND profile_id IS NOT DISTINCT FROM $2",
+        )
+        .bind(user.id)
+        .bind(user.profile_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Map a watch_history row joined with its media item's title
+fn history_entry_from_row(row: &PgRow) -> HistoryEntry {
+    HistoryEntry {
+        id: row.get("id"),
+        media_id: row.get("media_item_id"),
+        title: row.get("title"),
+        start_position: row.get("start_position"),
+        end_position: row.get("end_position"),
+        device: row.get("device"),
+        watched_at: row.get("watched_at"),
+    }
+}
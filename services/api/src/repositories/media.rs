@@ -1,10 +1,18 @@
 //! Media repository for database operations
 
 use anyhow::Result;
-use sqlx::{PgPool, Row};
+use sqlx::{PgPool, Postgres, QueryBuilder, Row, postgres::PgRow};
 use uuid::Uuid;
 
-use crate::models::media::{MediaItem, MediaQuery};
+use crate::middleware::AuthUser;
+use crate::models::media::{
+    MediaCursor, MediaItem, MediaListResponse, MediaQuery, MediaSortField, RATING_AVERAGE,
+    RATING_COUNT, SortOrder, Visibility, merge_patch,
+};
+use crate::models::profile::MaturityLevel;
+use crate::models::taxonomy::{FacetCount, MediaFacets, normalize_tags};
+use crate::repositories::access::{Reach, push_visible};
+use crate::repositories::taxonomy::sync_media_tags;
 
 /// Media repository for database operations
 #[derive(Clone)]
@@ -18,99 +26,866 @@ impl MediaRepository {
         Self { pool }
     }
 
-    /// Get a media item by ID
-    pub async fn get_by_id(&self, id: Uuid) -> Result<Option<MediaItem>> {
+    /// Get a media item by ID if it is visible to the user
+    ///
+    /// Items the user may not see, including items above the user's maturity
+    /// limit, are reported as missing so their existence is not leaked.
+    pub async fn get_by_id(&self, id: Uuid, user: &AuthUser) -> Result<Option<MediaItem>> {
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT id, type, metadata, s3_key, status, user_id, created_at, updated_at, \
+             duration, width, height, video_codec, audio_codec, format, bitrate, \
+             sample_rate, channels, thumbnail_url, visibility, deleted_at, kind, \
+             season_id, episode_number, maturity_rating, {RATING_AVERAGE} AS rating_average, \
+             {RATING_COUNT} AS rating_count \
+             FROM media_items WHERE id = "
+        ));
+        builder.push_bind(id).push(" AND ");
+        push_visible(&mut builder, "media_items", user, Reach::Direct);
+
+        let row = builder.build().fetch_optional(&self.pool).await?;
+
+        Ok(row.as_ref().map(media_item_from_row))
+    }
+
+    /// Get the storage key of a media item that has not been deleted
+    ///
+    /// Used for signed playback, where access was checked when signing.
+    pub async fn get_s3_key(&self, id: Uuid) -> Result<Option<String>> {
+        let row =
+            sqlx::query("SELECT s3_key FROM media_items WHERE id = $1 AND deleted_at IS NULL")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(row.map(|row| row.get("s3_key")))
+    }
+
+    /// Change the visibility of a media item owned by the user
+    ///
+    /// The shared users replace any previous shares. Returns `false` if the
+    /// item does not exist or the user may not change it.
+    pub async fn set_visibility(
+        &self,
+        id: Uuid,
+        user: &AuthUser,
+        visibility: Visibility,
+        shared_with: &[Uuid],
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            "UPDATE media_items SET visibility = $1, updated_at = NOW()
+             WHERE id = $2 AND deleted_at IS NULL AND ($3 OR user_id = $4)",
+        )
+        .bind(visibility.as_str())
+        .bind(id)
+        .bind(user.is_admin())
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM media_item_shares WHERE media_item_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO media_item_shares (media_item_id, user_id)
+             SELECT $1, UNNEST($2::uuid[])
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(id)
+        .bind(shared_with)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Set the maturity rating of a media item, overriding sidecar metadata
+    ///
+    /// Reserved for admins, who may rate items above their own limit.
+    /// Returns `None` if the item does not exist or is deleted.
+    pub async fn set_maturity_rating(
+        &self,
+        id: Uuid,
+        rating: Option<MaturityLevel>,
+    ) -> Result<Option<MediaItem>> {
         let row = sqlx::query(
             r#"
-            SELECT id, type, metadata, s3_key, status, user_id, created_at, updated_at,
-                   duration, width, height, video_codec, audio_codec, format, bitrate,
-                   sample_rate, channels, thumbnail_url
-            FROM media_items
-            WHERE id = $1
+            UPDATE media_items SET maturity_rating = $2, maturity_rating_source = 'admin',
+                updated_at = NOW()
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING id, type, metadata, s3_key, status, user_id, created_at, updated_at,
+                      duration, width, height, video_codec, audio_codec, format, bitrate,
+                      sample_rate, channels, thumbnail_url, visibility, deleted_at, kind,
+                      season_id, episode_number, maturity_rating
             "#,
         )
         .bind(id)
+        .bind(rating.map(|rating| rating.as_str()))
         .fetch_optional(&self.pool)
         .await?;
 
-        match row {
-            Some(row) => {
-                let media_item = MediaItem {
-                    id: row.get("id"),
-                    media_type: row.get("type"),
-                    metadata: row.get("metadata"),
-                    s3_key: row.get("s3_key"),
-                    status: row.get("status"),
-                    user_id: row.get("user_id"),
-                    created_at: row.get("created_at"),
-                    updated_at: row.get("updated_at"),
-                    duration: row.get("duration"),
-                    width: row.get("width"),
-                    height: row.get("height"),
-                    video_codec: row.get("video_codec"),
-                    audio_codec: row.get("audio_codec"),
-                    format: row.get("format"),
-                    bitrate: row.get("bitrate"),
-                    sample_rate: row.get("sample_rate"),
-                    channels: row.get("channels"),
-                    thumbnail_url: row.get("thumbnail_url"),
-                };
-                Ok(Some(media_item))
+        Ok(row.as_ref().map(media_item_from_row))
+    }
+
+    /// Apply a JSON Merge Patch to the metadata of a media item owned by the user
+    ///
+    /// The patch must already be validated. Returns `None` if the item does not
+    /// exist, is deleted, or the user may not change it.
+    pub async fn update_metadata(
+        &self,
+        id: Uuid,
+        user: &AuthUser,
+        patch: &serde_json::Value,
+    ) -> Result<Option<MediaItem>> {
+        let mut tx = self.pool.begin().await?;
+
+        let metadata: Option<serde_json::Value> = sqlx::query_scalar(
+            "SELECT metadata FROM media_items
+             WHERE id = $1 AND deleted_at IS NULL AND ($2 OR user_id = $3)
+             FOR UPDATE",
+        )
+        .bind(id)
+        .bind(user.is_admin())
+        .bind(user.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(mut metadata) = metadata else {
+            return Ok(None);
+        };
+        merge_patch(&mut metadata, patch);
+
+        // Tags are indexed in link tables next to their copy in the metadata;
+        // a null patch value removes them all
+        if let Some(tags) = patch.get("tags") {
+            let tags = match tags.as_array() {
+                Some(tags) => normalize_tags(tags.iter().filter_map(serde_json::Value::as_str)),
+                None => Vec::new(),
+            };
+            sync_media_tags(&mut tx, id, &tags, user.id).await?;
+            if patch["tags"].is_array() {
+                metadata["tags"] = serde_json::json!(tags);
             }
-            None => Ok(None),
         }
+
+        let row = sqlx::query(
+            r#"
+            UPDATE media_items SET metadata = $1, updated_at = NOW()
+            WHERE id = $2
+            RETURNING id, type, metadata, s3_key, status, user_id, created_at, updated_at,
+                      duration, width, height, video_codec, audio_codec, format, bitrate,
+                      sample_rate, channels, thumbnail_url, visibility, deleted_at, kind,
+                      season_id, episode_number, maturity_rating
+            "#,
+        )
+        .bind(&metadata)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(media_item_from_row(&row)))
     }
 
-    /// Get media items with pagination, sorting, and filtering
-    pub async fn get_media_items(&self, query: &MediaQuery) -> Result<(Vec<MediaItem>, i64)> {
-        let page = query.page.unwrap_or(1).max(1);
-        let limit = query.limit.unwrap_or(10).min(100).max(1);
+    /// Move a media item owned by the user to the trash
+    ///
+    /// Returns `false` if the item does not exist, is already deleted, or the
+    /// user may not delete it.
+    pub async fn soft_delete(&self, id: Uuid, user: &AuthUser) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE media_items SET deleted_at = NOW(), updated_at = NOW()
+             WHERE id = $1 AND deleted_at IS NULL AND ($2 OR user_id = $3)",
+        )
+        .bind(id)
+        .bind(user.is_admin())
+        .bind(user.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Restore a media item owned by the user from the trash
+    ///
+    /// Returns `false` if the item is not in the trash or the user may not
+    /// restore it.
+    pub async fn restore(&self, id: Uuid, user: &AuthUser) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE media_items SET deleted_at = NULL, updated_at = NOW()
+             WHERE id = $1 AND deleted_at IS NOT NULL AND ($2 OR user_id = $3)",
+        )
+        .bind(id)
+        .bind(user.is_admin())
+        .bind(user.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the deleted media items of the user, most recently deleted first
+    ///
+    /// Admins see the trash of all users.
+    pub async fn get_trash(
+        &self,
+        user: &AuthUser,
+        page: u32,
+        limit: u32,
+    ) -> Result<(Vec<MediaItem>, i64)> {
         let offset = (page - 1) as i64 * limit as i64;
 
-        // Build a simple query for now - in a real implementation we would build dynamic queries
         let rows = sqlx::query(
             r#"
             SELECT id, type, metadata, s3_key, status, user_id, created_at, updated_at,
                    duration, width, height, video_codec, audio_codec, format, bitrate,
-                   sample_rate, channels, thumbnail_url
+                   sample_rate, channels, thumbnail_url, visibility, deleted_at, kind,
+                   season_id, episode_number, maturity_rating
             FROM media_items
-            ORDER BY created_at DESC
-            LIMIT $1 OFFSET $2
+            WHERE deleted_at IS NOT NULL AND ($1 OR user_id = $2)
+              AND ($5::text[] IS NULL OR maturity_rating = ANY($5))
+            ORDER BY deleted_at DESC, id DESC
+            LIMIT $3 OFFSET $4
             "#,
         )
+        .bind(user.is_admin())
+        .bind(user.id)
         .bind(limit as i64)
         .bind(offset)
+        .bind(user.allowed_ratings())
         .fetch_all(&self.pool)
         .await?;
 
-        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM media_items")
-            .fetch_one(&self.pool)
-            .await?;
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM media_items WHERE deleted_at IS NOT NULL AND ($1 OR user_id = $2)
+               AND ($3::text[] IS NULL OR maturity_rating = ANY($3))",
+        )
+        .bind(user.is_admin())
+        .bind(user.id)
+        .bind(user.allowed_ratings())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((rows.iter().map(media_item_from_row).collect(), count))
+    }
+
+    /// Get media items with pagination, sorting, and filtering
+    ///
+    /// Pages are addressed by keyset cursors on the sort value plus id. When a
+    /// `page` is given instead, the legacy offset pagination is used. When a
+    /// search term is given, results are matched against the full-text search
+    /// vector and carry a highlighted snippet.
+    pub async fn get_media_items(
+        &self,
+        query: &MediaQuery,
+        user: &AuthUser,
+    ) -> Result<MediaListResponse> {
+        let limit = query.limit.unwrap_or(10).clamp(1, 100);
+        let (sort_field, sort_order) = query.sort().map_err(anyhow::Error::msg)?;
+        let cursor = query.cursor().map_err(anyhow::Error::msg)?;
+        let tsquery = query.search.as_deref().and_then(prefix_tsquery);
+        let backward = cursor.as_ref().is_some_and(|cursor| cursor.backward);
+
+        let mut builder = QueryBuilder::<Postgres>::new(
+            r#"
+            SELECT id, type, metadata, s3_key, status, user_id, created_at, updated_at,
+                   duration, width, height, video_codec, audio_codec, format, bitrate,
+                   sample_rate, channels, thumbnail_url, visibility, deleted_at, kind,
+                   season_id, episode_number, maturity_rating, "#,
+        );
+        builder.push(format!(
+            "{RATING_AVERAGE} AS rating_average, {RATING_COUNT} AS rating_count, "
+        ));
+        match &tsquery {
+            Some(tsquery) => {
+                builder
+                    .push(
+                        "ts_headline('simple', concat_ws(' ', metadata->>'title', \
+                         metadata->>'description'), to_tsquery('simple', ",
+                    )
+                    .push_bind(tsquery.clone())
+                    .push(format!("), '{}')", HEADLINE_OPTIONS));
+            }
+            None => {
+                builder.push("NULL::text");
+            }
+        }
+        builder.push(" AS highlight, (");
+        push_sort_expression(&mut builder, sort_field, tsquery.as_deref());
+        builder.push(")::text AS sort_key FROM media_items");
+        push_filters(&mut builder, query, user);
+
+        if let Some(cursor) = &cursor {
+            builder.push(" AND ");
+            push_keyset_predicate(&mut builder, cursor, tsquery.as_deref());
+        }
+
+        // Backward pages are fetched in reverse and flipped afterwards
+        let order = match (sort_order, backward) {
+            (SortOrder::Asc, false) | (SortOrder::Desc, true) => "ASC",
+            (SortOrder::Desc, false) | (SortOrder::Asc, true) => "DESC",
+        };
+        let nulls = if backward {
+            "NULLS FIRST"
+        } else {
+            "NULLS LAST"
+        };
 
-        let media_items = rows
-            .into_iter()
-            .map(|row| MediaItem {
+        // The sort column comes from a whitelist; id keeps the order stable
+        builder.push(" ORDER BY ");
+        push_sort_expression(&mut builder, sort_field, tsquery.as_deref());
+        builder
+            .push(format!(" {} {}, id {}", order, nulls, order))
+            .push(" LIMIT ")
+            .push_bind(limit as i64 + 1);
+
+        let offset = query
+            .page
+            .map(|page| (page.max(1) - 1) as i64 * limit as i64);
+        if let Some(offset) = offset {
+            builder.push(" OFFSET ").push_bind(offset);
+        }
+
+        let rows = builder.build().fetch_all(&self.pool).await?;
+
+        let has_more = rows.len() > limit as usize;
+        let mut rows: Vec<_> = rows.into_iter().take(limit as usize).collect();
+        if backward {
+            rows.reverse();
+        }
+
+        let cursor_at = |row: &PgRow, backward: bool| {
+            MediaCursor {
+                sort_by: sort_field,
+                order: sort_order,
+                value: row.get("sort_key"),
                 id: row.get("id"),
-                media_type: row.get("type"),
-                metadata: row.get("metadata"),
-                s3_key: row.get("s3_key"),
-                status: row.get("status"),
-                user_id: row.get("user_id"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-                duration: row.get("duration"),
-                width: row.get("width"),
-                height: row.get("height"),
-                video_codec: row.get("video_codec"),
-                audio_codec: row.get("audio_codec"),
-                format: row.get("format"),
-                bitrate: row.get("bitrate"),
-                sample_rate: row.get("sample_rate"),
-                channels: row.get("channels"),
-                thumbnail_url: row.get("thumbnail_url"),
+                backward,
+            }
+            .encode()
+        };
+
+        // A backward page always has the item it started from after it, and a
+        // forward page that started from a cursor always has items before it
+        let (next_cursor, prev_cursor) = match (rows.first(), rows.last()) {
+            (Some(first), Some(last)) => {
+                let has_next = if backward { true } else { has_more };
+                let has_prev = if backward {
+                    has_more
+                } else {
+                    cursor.is_some() || offset.is_some_and(|offset| offset > 0)
+                };
+                (
+                    has_next.then(|| cursor_at(last, false)),
+                    has_prev.then(|| cursor_at(first, true)),
+                )
+            }
+            _ => (None, None),
+        };
+
+        // Counting is expensive on large libraries, so it is opt-in for cursors
+        let total = if query.page.is_some() || query.include_total.unwrap_or(false) {
+            let mut count_builder =
+                QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM media_items");
+            push_filters(&mut count_builder, query, user);
+
+            Some(
+                count_builder
+                    .build_query_scalar()
+                    .fetch_one(&self.pool)
+                    .await?,
+            )
+        } else {
+            None
+        };
+
+        let facets = if query.include_facets.unwrap_or(false) {
+            Some(self.facets(query, user).await?)
+        } else {
+            None
+        };
+
+        Ok(MediaListResponse {
+            items: rows.iter().map(media_item_from_row).collect(),
+            page: query.page.map(|page| page.max(1)),
+            limit,
+            total,
+            next_cursor,
+            prev_cursor,
+            facets,
+        })
+    }
+
+    /// Count the items matching a media query by genre, type and resolution
+    async fn facets(&self, query: &MediaQuery, user: &AuthUser) -> Result<MediaFacets> {
+        let mut genres = QueryBuilder::<Postgres>::new(
+            "SELECT g.slug AS value, COUNT(*) AS count \
+             FROM media_item_genres mg JOIN genres g ON g.id = mg.genre_id \
+             WHERE mg.media_item_id IN (SELECT id FROM media_items",
+        );
+        push_filters(&mut genres, query, user);
+        genres.push(") GROUP BY g.slug");
+
+        let mut types = QueryBuilder::<Postgres>::new(
+            "SELECT type AS value, COUNT(*) AS count FROM media_items",
+        );
+        push_filters(&mut types, query, user);
+        types.push(" GROUP BY type");
+
+        let mut resolutions = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {} AS value, COUNT(*) AS count FROM media_items",
+            RESOLUTION_BUCKET
+        ));
+        push_filters(&mut resolutions, query, user);
+        resolutions.push(" AND height IS NOT NULL GROUP BY 1");
+
+        Ok(MediaFacets {
+            genres: self.facet_counts(genres).await?,
+            types: self.facet_counts(types).await?,
+            resolutions: self.facet_counts(resolutions).await?,
+        })
+    }
+
+    /// Run a facet query selecting `value` and `count`, largest counts first
+    async fn facet_counts(
+        &self,
+        mut builder: QueryBuilder<'_, Postgres>,
+    ) -> Result<Vec<FacetCount>> {
+        builder.push(" ORDER BY count DESC, value");
+        let rows = builder.build().fetch_all(&self.pool).await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| FacetCount {
+                value: row.get("value"),
+                count: row.get("count"),
             })
-            .collect();
+            .collect())
+    }
+
+    /// Suggest titles of listed items matching partial search input, best matches first
+    pub async fn suggest_titles(
+        &self,
+        input: &str,
+        limit: u32,
+        user: &AuthUser,
+    ) -> Result<Vec<String>> {
+        let Some(tsquery) = prefix_tsquery(input) else {
+            return Ok(Vec::new());
+        };
+
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "SELECT title FROM media_items WHERE search_vector @@ to_tsquery('simple', ",
+        );
+        builder
+            .push_bind(tsquery.clone())
+            .push(") AND title IS NOT NULL AND ");
+        push_visible(&mut builder, "media_items", user, Reach::Listed);
+        builder
+            .push(" GROUP BY title ORDER BY MAX(ts_rank(search_vector, to_tsquery('simple', ")
+            .push_bind(tsquery)
+            .push("))) DESC, title LIMIT ")
+            .push_bind(limit as i64);
+
+        let titles = builder.build_query_scalar().fetch_all(&self.pool).await?;
+
+        Ok(titles)
+    }
+}
+
+/// Resolution bucket of an item by its height, for facet counts
+const RESOLUTION_BUCKET: &str = "CASE WHEN height >= 2160 THEN '2160p' WHEN height >= 1440 THEN '1440p' \
+     WHEN height >= 1080 THEN '1080p' WHEN height >= 720 THEN '720p' \
+     WHEN height >= 480 THEN '480p' ELSE 'sd' END";
+
+/// Options for highlighted search snippets
+const HEADLINE_OPTIONS: &str =
+    "StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MaxWords=20, MinWords=5";
+
+/// Map a media_items row to a media item
+pub(crate) fn media_item_from_row(row: &PgRow) -> MediaItem {
+    MediaItem {
+        id: row.get("id"),
+        media_type: row.get("type"),
+        metadata: row.get("metadata"),
+        s3_key: row.get("s3_key"),
+        status: row.get("status"),
+        user_id: row.get("user_id"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        duration: row.get("duration"),
+        width: row.get("width"),
+        height: row.get("height"),
+        video_codec: row.get("video_codec"),
+        audio_codec: row.get("audio_codec"),
+        format: row.get("format"),
+        bitrate: row.get("bitrate"),
+        sample_rate: row.get("sample_rate"),
+        channels: row.get("channels"),
+        thumbnail_url: row.get("thumbnail_url"),
+        visibility: row.get("visibility"),
+        deleted_at: row.get("deleted_at"),
+        kind: row.get("kind"),
+        season_id: row.get("season_id"),
+        episode_number: row.get("episode_number"),
+        maturity_rating: row
+            .get::<Option<String>, _>("maturity_rating")
+            .as_deref()
+            .and_then(MaturityLevel::parse),
+        rating_average: row.try_get("rating_average").unwrap_or(None),
+        rating_count: row.try_get("rating_count").unwrap_or(None),
+        highlight: row.try_get("highlight").unwrap_or(None),
+    }
+}
+
+/// Build a prefix-matching tsquery from free-form search input
+///
+/// Only alphanumeric words are kept, so the input can never inject tsquery
+/// operators. Every word has to match, each as a prefix for type-ahead.
+fn prefix_tsquery(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("{}:*", word.to_lowercase()))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" & "))
+    }
+}
+
+/// Append the SQL expression a listing is sorted by
+fn push_sort_expression(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    field: MediaSortField,
+    tsquery: Option<&str>,
+) {
+    match (field.column(), tsquery) {
+        (Some(column), _) => {
+            builder.push(column);
+        }
+        (None, Some(tsquery)) => {
+            builder
+                .push("ts_rank(search_vector, to_tsquery('simple', ")
+                .push_bind(tsquery.to_string())
+                .push("))");
+        }
+        (None, None) => {
+            builder.push("created_at");
+        }
+    }
+}
+
+/// Append the predicate selecting the items after (or before) a cursor
+///
+/// Listings are ordered by the sort value with NULLs last and then by id, so
+/// the comparison has to treat items without a sort value explicitly.
+fn push_keyset_predicate(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    cursor: &MediaCursor,
+    tsquery: Option<&str>,
+) {
+    let operator = match (cursor.order, cursor.backward) {
+        (SortOrder::Asc, false) | (SortOrder::Desc, true) => ">",
+        (SortOrder::Desc, false) | (SortOrder::Asc, true) => "<",
+    };
+
+    let push_expression = |builder: &mut QueryBuilder<'_, Postgres>| {
+        push_sort_expression(builder, cursor.sort_by, tsquery);
+    };
+
+    builder.push("(");
+    match &cursor.value {
+        Some(value) => {
+            let push_value = |builder: &mut QueryBuilder<'_, Postgres>| {
+                builder
+                    .push("CAST(")
+                    .push_bind(value.clone())
+                    .push(format!(" AS {})", cursor.sort_by.sql_type()));
+            };
+
+            push_expression(builder);
+            builder.push(format!(" {} ", operator));
+            push_value(builder);
+            builder.push(" OR (");
+            push_expression(builder);
+            builder.push(" = ");
+            push_value(builder);
+            builder
+                .push(format!(" AND id {} ", operator))
+                .push_bind(cursor.id)
+                .push(")");
+
+            // Items without a sort value come after every other item
+            if !cursor.backward {
+                builder.push(" OR ");
+                push_expression(builder);
+                builder.push(" IS NULL");
+            }
+        }
+        None => {
+            if cursor.backward {
+                push_expression(builder);
+                builder.push(" IS NOT NULL OR ");
+            }
+            builder.push("(");
+            push_expression(builder);
+            builder
+                .push(format!(" IS NULL AND id {} ", operator))
+                .push_bind(cursor.id)
+                .push(")");
+        }
+    }
+    builder.push(")");
+}
+
+/// Append the WHERE clause for the filters of a media query
+///
+/// All values are bound as parameters; the listing and its count share this
+/// predicate so the total always matches the filtered results. Deleted
+/// items, items the user may not see and items above the user's maturity
+/// limit are always excluded; admins see every item within their limit.
+fn push_filters(builder: &mut QueryBuilder<'_, Postgres>, query: &MediaQuery, user: &AuthUser) {
+    builder.push(" WHERE ");
+    push_visible(builder, "media_items", user, Reach::Listed);
+
+    if let Some(media_type) = &query.media_type {
+        builder.push(" AND ");
+        builder.push("type = ").push_bind(media_type.clone());
+    }
+    if let Some(status) = &query.status {
+        builder.push(" AND ");
+        builder.push("status = ").push_bind(status.clone());
+    }
+    if let Some(kind) = &query.kind {
+        builder.push(" AND ");
+        builder.push("kind = ").push_bind(kind.clone());
+    }
+    for slug in query.tags() {
+        builder.push(" AND ");
+        builder
+            .push(
+                "EXISTS (SELECT 1 FROM media_item_tags mt JOIN tags t ON t.id = mt.tag_id \
+                 WHERE mt.media_item_id = media_items.id AND t.slug = ",
+            )
+            .push_bind(slug)
+            .push(")");
+    }
+    for slug in query.genres() {
+        builder.push(" AND ");
+        builder
+            .push(
+                "EXISTS (SELECT 1 FROM media_item_genres mg JOIN genres g ON g.id = mg.genre_id \
+                 WHERE mg.media_item_id = media_items.id AND g.slug = ",
+            )
+            .push_bind(slug)
+            .push(")");
+    }
+    if let Some(user_id) = query.user_id {
+        builder.push(" AND ");
+        builder.push("user_id = ").push_bind(user_id);
+    }
+    if let Some(tsquery) = query.search.as_deref().and_then(prefix_tsquery) {
+        builder.push(" AND ");
+        builder
+            .push("search_vector @@ to_tsquery('simple', ")
+            .push_bind(tsquery)
+            .push(")");
+    }
+
+    let ranges: [(&str, &str, Option<f64>); 2] = [
+        ("duration", ">=", query.min_duration),
+        ("duration", "<=", query.max_duration),
+    ];
+    for (column, operator, value) in ranges {
+        if let Some(value) = value {
+            builder.push(" AND ");
+            builder
+                .push(format!("{} {} ", column, operator))
+                .push_bind(value);
+        }
+    }
+
+    let ranges: [(&str, &str, Option<i32>); 4] = [
+        ("width", ">=", query.min_width),
+        ("width", "<=", query.max_width),
+        ("height", ">=", query.min_height),
+        ("height", "<=", query.max_height),
+    ];
+    for (column, operator, value) in ranges {
+        if let Some(value) = value {
+            builder.push(" AND ");
+            builder
+                .push(format!("{} {} ", column, operator))
+                .push_bind(value);
+        }
+    }
+
+    if let Some(created_after) = query.created_after {
+        builder.push(" AND ");
+        builder.push("created_at >= ").push_bind(created_after);
+    }
+    if let Some(created_before) = query.created_before {
+        builder.push(" AND ");
+        builder.push("created_at <= ").push_bind(created_before);
+    }
+    if let Some(collection_id) = query.collection_id {
+        builder.push(" AND ");
+        builder
+            .push(
+                "EXISTS (SELECT 1 FROM collection_items ci \
+                 JOIN collections c ON c.id = ci.collection_id \
+                 WHERE ci.media_item_id = media_items.id AND ci.collection_id = ",
+            )
+            .push_bind(collection_id)
+            .push(" AND c.user_id = ")
+            .push_bind(user.id)
+            .push(" AND c.profile_id IS NOT DISTINCT FROM ")
+            .push_bind(user.profile_id)
+            .push(")");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filters_are_bound() {
+        let query: MediaQuery = serde_json::from_value(serde_json::json!({
+            "type": "video",
+            "search": "star wars",
+            "min_duration": 60.0,
+            "max_height": 1080,
+            "collection_id": Uuid::nil(),
+        }))
+        .unwrap();
+
+        let mut user = AuthUser {
+            id: Uuid::new_v4(),
+            roles: vec!["admin".to_string()],
+            permissions: vec![],
+            actor_id: None,
+            read_only: false,
+            profile_id: None,
+            max_rating: None,
+        };
+
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM media_items");
+        push_filters(&mut builder, &query, &user);
+
+        assert_eq!(
+            builder.sql(),
+            "SELECT COUNT(*) FROM media_items WHERE media_items.deleted_at IS NULL AND type = $1 \
+             AND search_vector @@ to_tsquery('simple', $2) \
+             AND duration >= $3 AND height <= $4 \
+             AND EXISTS (SELECT 1 FROM collection_items ci \
+             JOIN collections c ON c.id = ci.collection_id \
+             WHERE ci.media_item_id = media_items.id AND ci.collection_id = $5 \
+             AND c.user_id = $6 AND c.profile_id IS NOT DISTINCT FROM $7)"
+        );
+
+        user.roles.clear();
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM media_items");
+        push_filters(&mut builder, &query, &user);
+
+        assert!(builder.sql().starts_with(
+            "SELECT COUNT(*) FROM media_items WHERE media_items.deleted_at IS NULL \
+             AND (media_items.user_id = $1 OR media_items.visibility = 'public' \
+             OR (media_items.visibility = 'shared' AND EXISTS (SELECT 1 FROM media_item_shares s \
+             WHERE s.media_item_id = media_items.id AND s.user_id = $2))) AND type = $3"
+        ));
+
+        user.max_rating = Some(MaturityLevel::Teen);
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM media_items");
+        push_filters(&mut builder, &query, &user);
+
+        assert!(builder.sql().contains(
+            "WHERE s.media_item_id = media_items.id AND s.user_id = $2))) \
+             AND media_items.maturity_rating = ANY($3) AND type = $4"
+        ));
+    }
+
+    #[test]
+    fn test_tag_and_genre_filters() {
+        let query: MediaQuery = serde_json::from_value(serde_json::json!({
+            "tag": "Sci Fi, space",
+            "genre": "drama",
+        }))
+        .unwrap();
+        let user = AuthUser {
+            id: Uuid::new_v4(),
+            roles: vec!["admin".to_string()],
+            permissions: vec![],
+            actor_id: None,
+            read_only: false,
+            profile_id: None,
+            max_rating: None,
+        };
+
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM media_items");
+        push_filters(&mut builder, &query, &user);
+
+        assert_eq!(
+            builder.sql(),
+            "SELECT COUNT(*) FROM media_items WHERE media_items.deleted_at IS NULL \
+             AND EXISTS (SELECT 1 FROM media_item_tags mt JOIN tags t ON t.id = mt.tag_id \
+             WHERE mt.media_item_id = media_items.id AND t.slug = $1) \
+             AND EXISTS (SELECT 1 FROM media_item_tags mt JOIN tags t ON t.id = mt.tag_id \
+             WHERE mt.media_item_id = media_items.id AND t.slug = $2) \
+             AND EXISTS (SELECT 1 FROM media_item_genres mg JOIN genres g ON g.id = mg.genre_id \
+             WHERE mg.media_item_id = media_items.id AND g.slug = $3)"
+        );
+        assert_eq!(query.tags(), vec!["sci-fi", "space"]);
+    }
+
+    #[test]
+    fn test_keyset_predicate() {
+        let mut cursor = MediaCursor {
+            sort_by: MediaSortField::Duration,
+            order: SortOrder::Asc,
+            value: Some("42.5".to_string()),
+            id: Uuid::new_v4(),
+            backward: false,
+        };
+
+        let mut builder = QueryBuilder::<Postgres>::new("");
+        push_keyset_predicate(&mut builder, &cursor, None);
+        assert_eq!(
+            builder.sql(),
+            "(duration > CAST($1 AS DOUBLE PRECISION) \
+             OR (duration = CAST($2 AS DOUBLE PRECISION) AND id > $3) \
+             OR duration IS NULL)"
+        );
+
+        cursor.backward = true;
+        cursor.value = None;
+        let mut builder = QueryBuilder::<Postgres>::new("");
+        push_keyset_predicate(&mut builder, &cursor, None);
+        assert_eq!(
+            builder.sql(),
+            "(duration IS NOT NULL OR (duration IS NULL AND id < $1))"
+        );
+    }
 
-        Ok((media_items, count))
+    #[test]
+    fn test_prefix_tsquery() {
+        assert_eq!(prefix_tsquery("Star Wa").as_deref(), Some("star:* & wa:*"));
+        assert_eq!(
+            prefix_tsquery("it's a 'trap' | !").as_deref(),
+            Some("it:* & s:* & a:* & trap:*")
+        );
+        assert_eq!(prefix_tsquery(" &!* "), None);
     }
 }
@@ -0,0 +1,110 @@
+//! Notification repository for database operations
+
+use anyhow::Result;
+use sqlx::{PgPool, Row, postgres::PgRow};
+use uuid::Uuid;
+
+use crate::models::notification::Notification;
+
+/// Notification repository for database operations
+///
+/// Notifications are created by the features that cause them, such as
+/// comment mentions; users can only list them and mark them as read.
+#[derive(Clone)]
+pub struct NotificationRepository {
+    pool: PgPool,
+}
+
+impl NotificationRepository {
+    /// Create a new notification repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// List the user's notifications, newest first, with the total and unread counts
+    pub async fn list(
+        &self,
+        user_id: Uuid,
+        unread_only: bool,
+        page: u32,
+        limit: u32,
+    ) -> Result<(Vec<Notification>, i64, i64)> {
+        let offset = (page - 1) * limit;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, kind, media_item_id, comment_id, actor_id, read_at, created_at
+            FROM notifications
+            WHERE user_id = $1 AND (NOT $2 OR read_at IS NULL)
+            ORDER BY created_at DESC, id DESC
+            LIMIT $3 OFFSET $4
+            "#,
+        )
+        .bind(user_id)
+        .bind(unread_only)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let counts = sqlx::query(
+            "SELECT COUNT(*) AS total, COUNT(*) FILTER (WHERE read_at IS NULL) AS unread
+             FROM notifications WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let unread: i64 = counts.get("unread");
+        let total = if unread_only {
+            unread
+        } else {
+            counts.get("total")
+        };
+
+        Ok((
+            rows.iter().map(notification_from_row).collect(),
+            total,
+            unread,
+        ))
+    }
+
+    /// Mark one of the user's notifications as read
+    pub async fn mark_read(&self, user_id: Uuid, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE notifications SET read_at = COALESCE(read_at, NOW())
+             WHERE id = $1 AND user_id = $2",
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Mark all of the user's notifications as read, returning how many were unread
+    pub async fn mark_all_read(&self, user_id: Uuid) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE notifications SET read_at = NOW() WHERE user_id = $1 AND read_at IS NULL",
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Map a notifications row
+fn notification_from_row(row: &PgRow) -> Notification {
+    Notification {
+        id: row.get("id"),
+        kind: row.get("kind"),
+        media_id: row.get("media_item_id"),
+        comment_id: row.get("comment_id"),
+        actor_id: row.get("actor_id"),
+        read_at: row.get("read_at"),
+        created_at: row.get("created_at"),
+    }
+}
@@ -0,0 +1,189 @@
+//! Parental control repository for database operations
+
+use anyhow::Result;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::models::{
+    parental::{MAX_PIN_FAILURES, PIN_LOCKOUT_MINUTES, ParentalControls, verify_pin},
+    profile::MaturityLevel,
+};
+
+/// Parental control repository for database operations
+#[derive(Clone)]
+pub struct ParentalControlRepository {
+    pool: PgPool,
+}
+
+impl ParentalControlRepository {
+    /// Create a new parental control repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Get the maximum maturity rating a token may watch
+    ///
+    /// Profile tokens are limited by the profile's maturity level, account
+    /// tokens by the account's maximum rating. A token for a deleted profile
+    /// is limited to media for all ages.
+    pub async fn max_rating(
+        &self,
+        user_id: Uuid,
+        profile_id: Option<Uuid>,
+    ) -> Result<Option<MaturityLevel>> {
+        let rating: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT CASE
+                WHEN $2::uuid IS NULL THEN u.max_maturity_rating
+                ELSE COALESCE(
+                    (SELECT maturity_level FROM profiles WHERE id = $2 AND user_id = u.id),
+                    'all'
+                )
+            END
+            FROM users u
+            WHERE u.id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(profile_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .flatten();
+
+        Ok(rating.as_deref().and_then(MaturityLevel::parse))
+    }
+
+    /// Get the parental controls of an account
+    pub async fn get(&self, user_id: Uuid) -> Result<ParentalControls> {
+        let row = sqlx::query(
+            "SELECT max_maturity_rating, parental_pin_hash IS NOT NULL AS pin_set
+             FROM users WHERE id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(match row {
+            Some(row) => ParentalControls {
+                max_rating: row
+                    .get::<Option<String>, _>("max_maturity_rating")
+                    .as_deref()
+                    .and_then(MaturityLevel::parse),
+                pin_set: row.get("pin_set"),
+            },
+            None => ParentalControls {
+                max_rating: None,
+                pin_set: false,
+            },
+        })
+    }
+
+    /// Check the PIN of an account before a maturity limit is changed
+    ///
+    /// Passes if the account has no PIN. After `MAX_PIN_FAILURES` wrong
+    /// PINs in a row, every attempt is refused for `PIN_LOCKOUT_MINUTES`.
+    pub async fn check_pin(&self, user_id: Uuid, pin: Option<&str>) -> Result<(), PinError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Serialize attempts so failures are counted exactly
+        let row = sqlx::query(
+            "SELECT parental_pin_hash, parental_pin_locked_until > NOW() AS locked
+             FROM users WHERE id = $1 FOR UPDATE",
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(());
+        };
+        let Some(pin_hash) = row.get::<Option<String>, _>("parental_pin_hash") else {
+            return Ok(());
+        };
+        if row.get::<Option<bool>, _>("locked").unwrap_or(false) {
+            return Err(PinError::Locked);
+        }
+        let Some(pin) = pin else {
+            return Err(PinError::Required);
+        };
+
+        if verify_pin(&pin_hash, pin)? {
+            sqlx::query(
+                "UPDATE users SET parental_pin_failures = 0, parental_pin_locked_until = NULL
+                 WHERE id = $1",
+            )
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE users SET
+                parental_pin_failures = CASE
+                    WHEN parental_pin_failures + 1 >= $2 THEN 0
+                    ELSE parental_pin_failures + 1
+                END,
+                parental_pin_locked_until = CASE
+                    WHEN parental_pin_failures + 1 >= $2 THEN NOW() + make_interval(mins => $3)
+                    ELSE parental_pin_locked_until
+                END
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(MAX_PIN_FAILURES)
+        .bind(PIN_LOCKOUT_MINUTES)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Err(PinError::Invalid)
+    }
+
+    /// Set the maximum maturity rating of an account's own tokens
+    pub async fn set_max_rating(&self, user_id: Uuid, rating: Option<MaturityLevel>) -> Result<()> {
+        sqlx::query("UPDATE users SET max_maturity_rating = $2 WHERE id = $1")
+            .bind(user_id)
+            .bind(rating.map(|rating| rating.as_str()))
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Replace or remove the hashed PIN of an account
+    pub async fn set_pin_hash(&self, user_id: Uuid, pin_hash: Option<&str>) -> Result<()> {
+        sqlx::query(
+            "UPDATE users SET parental_pin_hash = $2, parental_pin_failures = 0,
+                parental_pin_locked_until = NULL
+             WHERE id = $1",
+        )
+        .bind(user_id)
+        .bind(pin_hash)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Errors checking a parental control PIN
+#[derive(Debug, thiserror::Error)]
+pub enum PinError {
+    /// The account has a PIN but none was given
+    #[error("PIN required")]
+    Required,
+    /// The given PIN is wrong
+    #[error("Invalid PIN")]
+    Invalid,
+    /// Too many wrong PINs were given recently
+    #[error("PIN locked")]
+    Locked,
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Hash(#[from] anyhow::Error),
+}
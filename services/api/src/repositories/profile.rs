@@ -0,0 +1,197 @@
+//! Viewer profile repository for database operations
+
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, Row, Transaction, postgres::PgRow};
+use uuid::Uuid;
+
+use crate::models::profile::{MAX_PROFILES, MaturityLevel, Profile, ProfileFields};
+
+/// Columns of a profile
+const PROFILE_COLUMNS: &str = r#"
+    id, user_id, name, avatar_url, maturity_level, audio_language, subtitle_language,
+    created_at, updated_at
+"#;
+
+/// Viewer profile repository for database operations
+#[derive(Clone)]
+pub struct ProfileRepository {
+    pool: PgPool,
+}
+
+impl ProfileRepository {
+    /// Create a new profile repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// List the profiles of a user in creation order
+    pub async fn list(&self, user_id: Uuid) -> Result<Vec<Profile>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {PROFILE_COLUMNS} FROM profiles WHERE user_id = $1 ORDER BY created_at, id"
+        ))
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(profile_from_row).collect())
+    }
+
+    /// Get one of the user's profiles
+    pub async fn get(&self, id: Uuid, user_id: Uuid) -> Result<Option<Profile>> {
+        let row = sqlx::query(&format!(
+            "SELECT {PROFILE_COLUMNS} FROM profiles WHERE id = $1 AND user_id = $2"
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(profile_from_row))
+    }
+
+    /// Create a profile for a user
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        fields: &ProfileFields,
+    ) -> Result<Profile, ProfileError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Serialize profile changes of this user so the limit holds
+        sqlx::query("SELECT id FROM users WHERE id = $1 FOR UPDATE")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM profiles WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if count >= MAX_PROFILES {
+            return Err(ProfileError::LimitReached);
+        }
+        check_name(&mut tx, user_id, None, &fields.name).await?;
+
+        let row = sqlx::query(&format!(
+            "INSERT INTO profiles
+                (user_id, name, avatar_url, maturity_level, audio_language, subtitle_language)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING {PROFILE_COLUMNS}"
+        ))
+        .bind(user_id)
+        .bind(&fields.name)
+        .bind(&fields.avatar_url)
+        .bind(fields.maturity_level.as_str())
+        .bind(&fields.audio_language)
+        .bind(&fields.subtitle_language)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(profile_from_row(&row))
+    }
+
+    /// Replace the fields of one of the user's profiles
+    ///
+    /// Returns `Ok(None)` if the profile does not exist.
+    pub async fn update(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        fields: &ProfileFields,
+    ) -> Result<Option<Profile>, ProfileError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("SELECT id FROM users WHERE id = $1 FOR UPDATE")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        check_name(&mut tx, user_id, Some(id), &fields.name).await?;
+
+        let row = sqlx::query(&format!(
+            "UPDATE profiles SET name = $3, avatar_url = $4, maturity_level = $5,
+                audio_language = $6, subtitle_language = $7
+             WHERE id = $1 AND user_id = $2
+             RETURNING {PROFILE_COLUMNS}"
+        ))
+        .bind(id)
+        .bind(user_id)
+        .bind(&fields.name)
+        .bind(&fields.avatar_url)
+        .bind(fields.maturity_level.as_str())
+        .bind(&fields.audio_language)
+        .bind(&fields.subtitle_language)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(row.as_ref().map(profile_from_row))
+    }
+
+    /// Delete one of the user's profiles together with its viewing data
+    pub async fn delete(&self, id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM profiles WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// Errors creating or editing a profile
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    /// The user already has the maximum number of profiles
+    #[error("Profile limit reached")]
+    LimitReached,
+    /// Another profile of the user has the same name
+    #[error("Profile name is already taken")]
+    NameTaken,
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// Refuse a name used by another profile of the user, ignoring case
+async fn check_name(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    id: Option<Uuid>,
+    name: &str,
+) -> Result<(), ProfileError> {
+    let taken: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM profiles
+                        WHERE user_id = $1 AND LOWER(name) = LOWER($2)
+                          AND id IS DISTINCT FROM $3)",
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(id)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    if taken {
+        return Err(ProfileError::NameTaken);
+    }
+    Ok(())
+}
+
+/// Map a profiles row selected with `PROFILE_COLUMNS`
+fn profile_from_row(row: &PgRow) -> Profile {
+    let maturity_level: String = row.get("maturity_level");
+
+    Profile {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        name: row.get("name"),
+        avatar_url: row.get("avatar_url"),
+        maturity_level: MaturityLevel::parse(&maturity_level).unwrap_or(MaturityLevel::All),
+        audio_language: row.get("audio_language"),
+        subtitle_language: row.get("subtitle_language"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
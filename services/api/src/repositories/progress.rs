@@ -0,0 +1,145 @@
+//! Playback progress repository for database operations
+
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, QueryBuilder, Row, postgres::PgRow};
+use uuid::Uuid;
+
+use crate::{
+    middleware::AuthUser,
+    models::progress::{ContinueWatchingItem, PlaybackProgress, ProgressConfig},
+    repositories::{
+        access::{Reach, push_visible},
+        media::media_item_from_row,
+    },
+};
+
+/// Playback progress repository for database operations
+#[derive(Clone)]
+pub struct ProgressRepository {
+    pool: PgPool,
+    config: ProgressConfig,
+}
+
+impl ProgressRepository {
+    /// Create a new playback progress repository
+    pub fn new(pool: PgPool, config: ProgressConfig) -> Self {
+        Self { pool, config }
+    }
+
+    /// Get the playback progress configuration
+    pub fn config(&self) -> &ProgressConfig {
+        &self.config
+    }
+
+    /// Record a reported playback position for the user's profile
+    ///
+    /// Reports arriving within the throttle window of the last stored one
+    /// are dropped unless they change whether the item is finished. Returns
+    /// whether the report was stored.
+    pub async fn record(
+        &self,
+        user: &AuthUser,
+        media_id: Uuid,
+        position: f64,
+        finished: bool,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            "INSERT INTO playback_progress (user_id, profile_id, media_item_id, position, finished)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (user_id, profile_id, media_item_id) DO UPDATE SET
+                position = EXCLUDED.position,
+                finished = EXCLUDED.finished,
+                last_watched_at = NOW()
+             WHERE playback_progress.last_watched_at <= NOW() - make_interval(secs => $6)
+                OR playback_progress.finished <> EXCLUDED.finished",
+        )
+        .bind(user.id)
+        .bind(user.profile_id)
+        .bind(media_id)
+        .bind(position)
+        .bind(finished)
+        .bind(self.config.throttle_seconds as f64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the progress of the user's profile for a set of media items
+    ///
+    /// Items without progress are left out, as are items the user can no
+    /// longer see, deleted items and items above the user's maturity limit.
+    pub async fn get_for_media(
+        &self,
+        user: &AuthUser,
+        media_ids: &[Uuid],
+    ) -> Result<Vec<PlaybackProgress>> {
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "SELECT p.media_item_id, p.position, m.duration, p.finished, p.last_watched_at \
+             FROM playback_progress p \
+             JOIN media_items m ON m.id = p.media_item_id \
+             WHERE p.user_id = ",
+        );
+        builder
+            .push_bind(user.id)
+            .push(" AND p.profile_id IS NOT DISTINCT FROM ")
+            .push_bind(user.profile_id)
+            .push(" AND p.media_item_id = ANY(")
+            .push_bind(media_ids)
+            .push(") AND ");
+        push_visible(&mut builder, "m", user, Reach::Direct);
+
+        let rows = builder.build().fetch_all(&self.pool).await?;
+
+        Ok(rows.iter().map(progress_from_row).collect())
+    }
+
+    /// Get the partially watched items of the user's profile, most recently watched first
+    pub async fn continue_watching(
+        &self,
+        user: &AuthUser,
+        limit: u32,
+    ) -> Result<Vec<ContinueWatchingItem>> {
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "SELECT m.id, m.type, m.metadata, m.s3_key, m.status, m.user_id, m.created_at, \
+             m.updated_at, m.duration, m.width, m.height, m.video_codec, m.audio_codec, \
+             m.format, m.bitrate, m.sample_rate, m.channels, m.thumbnail_url, \
+             m.visibility, m.deleted_at, m.kind, m.season_id, m.episode_number, \
+             m.maturity_rating, \
+             p.media_item_id, p.position, p.finished, p.last_watched_at \
+             FROM playback_progress p \
+             JOIN media_items m ON m.id = p.media_item_id \
+             WHERE p.user_id = ",
+        );
+        builder
+            .push_bind(user.id)
+            .push(" AND p.profile_id IS NOT DISTINCT FROM ")
+            .push_bind(user.profile_id)
+            .push(" AND NOT p.finished AND p.position > 0 AND ");
+        push_visible(&mut builder, "m", user, Reach::Direct);
+        builder
+            .push(" ORDER BY p.last_watched_at DESC LIMIT ")
+            .push_bind(limit as i64);
+
+        let rows = builder.build().fetch_all(&self.pool).await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ContinueWatchingItem {
+                media: media_item_from_row(row),
+                progress: progress_from_row(row),
+            })
+            .collect())
+    }
+}
+
+/// Map a playback_progress row joined with its media item's duration
+fn progress_from_row(row: &PgRow) -> PlaybackProgress {
+    PlaybackProgress {
+        media_id: row.get("media_item_id"),
+        position: row.get("position"),
+        duration: row.get("duration"),
+        finished: row.get("finished"),
+        last_watched_at: row.get("last_watched_at"),
+    }
+}
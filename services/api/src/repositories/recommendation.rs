@@ -0,0 +1,313 @@
+//! Recommendation repository for database operations
+
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, QueryBuilder, Row};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    middleware::AuthUser,
+    models::recommendation::{
+        Candidate, MAX_CANDIDATES, Recommendation, RecommendationConfig, RecommendationReason,
+    },
+    repositories::{
+        access::{Reach, push_visible},
+        media::media_item_from_row,
+    },
+};
+
+/// Columns of a media item
+const ITEM_COLUMNS: &str = r#"
+    m.id, m.type, m.metadata, m.s3_key, m.status, m.user_id, m.created_at,
+    m.updated_at, m.duration, m.width, m.height, m.video_codec, m.audio_codec,
+    m.format, m.bitrate, m.sample_rate, m.channels, m.thumbnail_url,
+    m.visibility, m.deleted_at, m.kind, m.season_id, m.episode_number,
+    m.maturity_rating
+"#;
+
+/// Rebuild the similarity table from genres and tags, co-watching and metadata
+///
+/// Only pairs sharing a genre, tag or viewer are scored, which keeps the
+/// work proportional to the actual overlap instead of all pairs of items.
+/// Parameters: $1 history days, $2..$4 taxonomy, co-watch and metadata
+/// weights, $5 neighbors kept per item.
+const REFRESH_SIMILARITIES: &str = r#"
+    WITH eligible AS (
+        SELECT id, type, kind, duration FROM media_items WHERE deleted_at IS NULL
+    ),
+    features AS (
+        SELECT g.media_item_id, 'genre:' || g.genre_id AS feature
+        FROM media_item_genres g JOIN eligible e ON e.id = g.media_item_id
+        UNION
+        SELECT t.media_item_id, 'tag:' || t.tag_id
+        FROM media_item_tags t JOIN eligible e ON e.id = t.media_item_id
+    ),
+    feature_counts AS (
+        SELECT media_item_id, COUNT(*) AS n FROM features GROUP BY media_item_id
+    ),
+    taxonomy AS (
+        SELECT a.media_item_id AS item_a, b.media_item_id AS item_b,
+               COUNT(*)::double precision / (ca.n + cb.n - COUNT(*)) AS score
+        FROM features a
+        JOIN features b ON b.feature = a.feature AND b.media_item_id <> a.media_item_id
+        JOIN feature_counts ca ON ca.media_item_id = a.media_item_id
+        JOIN feature_counts cb ON cb.media_item_id = b.media_item_id
+        GROUP BY a.media_item_id, b.media_item_id, ca.n, cb.n
+    ),
+    watchers AS (
+        SELECT DISTINCT h.user_id, h.media_item_id
+        FROM watch_history h JOIN eligible e ON e.id = h.media_item_id
+        WHERE h.watched_at > NOW() - make_interval(days => $1)
+    ),
+    watcher_counts AS (
+        SELECT media_item_id, COUNT(*) AS n FROM watchers GROUP BY media_item_id
+    ),
+    cowatch AS (
+        SELECT a.media_item_id AS item_a, b.media_item_id AS item_b,
+               COUNT(*) / SQRT(ca.n * cb.n) AS score
+        FROM watchers a
+        JOIN watchers b ON b.user_id = a.user_id AND b.media_item_id <> a.media_item_id
+        JOIN watcher_counts ca ON ca.media_item_id = a.media_item_id
+        JOIN watcher_counts cb ON cb.media_item_id = b.media_item_id
+        GROUP BY a.media_item_id, b.media_item_id, ca.n, cb.n
+    ),
+    pairs AS (
+        SELECT item_a, item_b, MAX(taxonomy) AS taxonomy, MAX(cowatch) AS cowatch
+        FROM (
+            SELECT item_a, item_b, score AS taxonomy, 0::double precision AS cowatch
+            FROM taxonomy
+            UNION ALL
+            SELECT item_a, item_b, 0, score FROM cowatch
+        ) signals
+        GROUP BY item_a, item_b
+    ),
+    scored AS (
+        SELECT p.item_a, p.item_b, p.taxonomy, p.cowatch,
+               (CASE WHEN a.type = b.type THEN 0.5 ELSE 0 END
+                + CASE WHEN a.kind IS NOT DISTINCT FROM b.kind THEN 0.25 ELSE 0 END
+                + CASE WHEN a.duration > 0 AND b.duration > 0
+                       THEN 0.25 * LEAST(a.duration, b.duration) / GREATEST(a.duration, b.duration)
+                       ELSE 0 END)::double precision AS metadata
+        FROM pairs p
+        JOIN eligible a ON a.id = p.item_a
+        JOIN eligible b ON b.id = p.item_b
+    ),
+    ranked AS (
+        SELECT item_a, item_b, taxonomy, cowatch, metadata,
+               $2 * taxonomy + $3 * cowatch + $4 * metadata AS score
+        FROM scored
+    )
+    INSERT INTO media_similarities
+        (media_item_id, similar_item_id, score, taxonomy_score, cowatch_score, metadata_score)
+    SELECT item_a, item_b, score, taxonomy, cowatch, metadata
+    FROM (
+        SELECT ranked.*,
+               ROW_NUMBER() OVER (PARTITION BY item_a ORDER BY score DESC, item_b) AS rank
+        FROM ranked
+    ) neighbors
+    WHERE rank <= $5
+"#;
+
+/// Recommendation repository for database operations
+#[derive(Clone)]
+pub struct RecommendationRepository {
+    pool: PgPool,
+    config: RecommendationConfig,
+}
+
+impl RecommendationRepository {
+    /// Create a new recommendation repository
+    pub fn new(pool: PgPool, config: RecommendationConfig) -> Self {
+        Self { pool, config }
+    }
+
+    /// Get the recommendation configuration
+    pub fn config(&self) -> &RecommendationConfig {
+        &self.config
+    }
+
+    /// Rebuild the similarity table, returning the number of stored pairs
+    ///
+    /// Readers keep seeing the previous similarities until the rebuild
+    /// commits. Returns `None` if another instance is already rebuilding.
+    pub async fn refresh(&self) -> Result<Option<u64>> {
+        let mut tx = self.pool.begin().await?;
+
+        let locked: bool =
+            sqlx::query_scalar("SELECT pg_try_advisory_xact_lock(hashtext('media_similarities'))")
+                .fetch_one(&mut *tx)
+                .await?;
+        if !locked {
+            return Ok(None);
+        }
+
+        sqlx::query("DELETE FROM media_similarities")
+            .execute(&mut *tx)
+            .await?;
+
+        let result = sqlx::query(REFRESH_SIMILARITIES)
+            .bind(self.config.history_days)
+            .bind(self.config.taxonomy_weight)
+            .bind(self.config.cowatch_weight)
+            .bind(self.config.metadata_weight)
+            .bind(self.config.neighbors)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(result.rows_affected()))
+    }
+
+    /// Get the items most similar to a media item
+    pub async fn similar_candidates(&self, media_id: Uuid) -> Result<Vec<Candidate>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT similar_item_id AS id, score
+            FROM media_similarities
+            WHERE media_item_id = $1
+            ORDER BY score DESC, similar_item_id
+            LIMIT $2
+            "#,
+        )
+        .bind(media_id)
+        .bind(MAX_CANDIDATES)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| Candidate {
+                id: row.get("id"),
+                score: row.get("score"),
+                reason: RecommendationReason::Similar,
+            })
+            .collect())
+    }
+
+    /// Get personalized candidates for a user's profile, followed by popular items
+    ///
+    /// Items the profile watched or favorited and the user rated seed the
+    /// recommendations, weighted by how much they were liked. Items already
+    /// watched, finished or rated are never recommended. Ratings belong to
+    /// the account and apply to all of its profiles.
+    pub async fn user_candidates(&self, user: &AuthUser) -> Result<Vec<Candidate>> {
+        let rows = sqlx::query(
+            r#"
+            WITH seeds AS (
+                SELECT media_item_id, SUM(weight) AS weight
+                FROM (
+                    SELECT DISTINCT media_item_id, 1.0 AS weight
+                    FROM watch_history
+                    WHERE user_id = $1 AND profile_id IS NOT DISTINCT FROM $4
+                      AND watched_at > NOW() - make_interval(days => $2)
+                    UNION ALL
+                    SELECT media_item_id, (rating - 3) / 2.0 FROM reviews WHERE user_id = $1
+                    UNION ALL
+                    SELECT ci.media_item_id, 1.5
+                    FROM collection_items ci
+                    JOIN collections c ON c.id = ci.collection_id
+                    WHERE c.user_id = $1 AND c.profile_id IS NOT DISTINCT FROM $4
+                      AND c.kind = 'favorites'
+                ) signals
+                GROUP BY media_item_id
+            ),
+            seen AS (
+                SELECT media_item_id FROM watch_history
+                WHERE user_id = $1 AND profile_id IS NOT DISTINCT FROM $4
+                UNION
+                SELECT media_item_id FROM reviews WHERE user_id = $1
+                UNION
+                SELECT media_item_id FROM playback_progress
+                WHERE user_id = $1 AND profile_id IS NOT DISTINCT FROM $4 AND finished
+            ),
+            similar AS (
+                SELECT s.similar_item_id AS id,
+                       SUM(seeds.weight * s.score)::double precision AS score
+                FROM seeds
+                JOIN media_similarities s ON s.media_item_id = seeds.media_item_id
+                WHERE NOT EXISTS (SELECT 1 FROM seen WHERE seen.media_item_id = s.similar_item_id)
+                GROUP BY s.similar_item_id
+                HAVING SUM(seeds.weight * s.score) > 0
+                ORDER BY score DESC, id
+                LIMIT $3
+            ),
+            popular AS (
+                SELECT h.media_item_id AS id, COUNT(DISTINCT h.user_id)::double precision AS score
+                FROM watch_history h
+                WHERE h.watched_at > NOW() - INTERVAL '30 days'
+                  AND NOT EXISTS (SELECT 1 FROM seen WHERE seen.media_item_id = h.media_item_id)
+                  AND NOT EXISTS (SELECT 1 FROM similar WHERE similar.id = h.media_item_id)
+                GROUP BY h.media_item_id
+                ORDER BY score DESC, id
+                LIMIT $3
+            )
+            SELECT id, score, 'similar' AS reason, 0 AS tier FROM similar
+            UNION ALL
+            SELECT id, score, 'popular', 1 FROM popular
+            ORDER BY tier, score DESC, id
+            "#,
+        )
+        .bind(user.id)
+        .bind(self.config.history_days)
+        .bind(MAX_CANDIDATES)
+        .bind(user.profile_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| Candidate {
+                id: row.get("id"),
+                score: row.get("score"),
+                reason: match row.get::<&str, _>("reason") {
+                    "similar" => RecommendationReason::Similar,
+                    _ => RecommendationReason::Popular,
+                },
+            })
+            .collect())
+    }
+
+    /// Load the candidates the user may list, in candidate order
+    ///
+    /// Candidates are cached without regard to the viewer, so items above the
+    /// user's maturity limit are only left out here.
+    pub async fn items(
+        &self,
+        user: &AuthUser,
+        candidates: &[Candidate],
+        limit: u32,
+    ) -> Result<Vec<Recommendation>> {
+        let ids: Vec<Uuid> = candidates.iter().map(|candidate| candidate.id).collect();
+
+        let mut builder =
+            QueryBuilder::<Postgres>::new(format!("SELECT {ITEM_COLUMNS} FROM unnest("));
+        builder.push_bind(ids).push(
+            "::uuid[]) WITH ORDINALITY AS c(id, position) JOIN media_items m ON m.id = c.id WHERE ",
+        );
+        push_visible(&mut builder, "m", user, Reach::Listed);
+        builder
+            .push(" ORDER BY c.position LIMIT ")
+            .push_bind(limit as i64);
+
+        let rows = builder.build().fetch_all(&self.pool).await?;
+
+        let candidates: HashMap<Uuid, &Candidate> = candidates
+            .iter()
+            .map(|candidate| (candidate.id, candidate))
+            .collect();
+
+        Ok(rows
+            .iter()
+            .map(media_item_from_row)
+            .filter_map(|item| {
+                let candidate = candidates.get(&item.id)?;
+                Some(Recommendation {
+                    score: candidate.score,
+                    reason: candidate.reason,
+                    item,
+                })
+            })
+            .collect())
+    }
+}
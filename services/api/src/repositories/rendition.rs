@@ -0,0 +1,71 @@
+//! Rendition repository for database operations
+
+use anyhow::Result;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::models::hls::{Rendition, RenditionSegment};
+
+/// Rendition repository for database operations
+#[derive(Clone)]
+pub struct RenditionRepository {
+    pool: PgPool,
+}
+
+impl RenditionRepository {
+    /// Create a new rendition repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Get the renditions of a media item, lowest bandwidth first
+    pub async fn get_renditions(&self, media_id: Uuid) -> Result<Vec<Rendition>> {
+        let rows = sqlx::query(
+            "SELECT name, bandwidth, average_bandwidth, width, height, frame_rate, codecs, target_duration
+             FROM media_renditions
+             WHERE media_item_id = $1
+             ORDER BY bandwidth ASC, name ASC",
+        )
+        .bind(media_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| Rendition {
+                name: row.get("name"),
+                bandwidth: row.get("bandwidth"),
+                average_bandwidth: row.get("average_bandwidth"),
+                width: row.get("width"),
+                height: row.get("height"),
+                frame_rate: row.get("frame_rate"),
+                codecs: row.get("codecs"),
+                target_duration: row.get("target_duration"),
+            })
+            .collect())
+    }
+
+    /// Get the segments of a rendition in playback order
+    pub async fn get_segments(&self, media_id: Uuid, name: &str) -> Result<Vec<RenditionSegment>> {
+        let rows = sqlx::query(
+            "SELECT s.sequence, s.duration, s.s3_key
+             FROM media_rendition_segments s
+             JOIN media_renditions r ON r.id = s.rendition_id
+             WHERE r.media_item_id = $1 AND r.name = $2
+             ORDER BY s.sequence ASC",
+        )
+        .bind(media_id)
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| RenditionSegment {
+                sequence: row.get("sequence"),
+                duration: row.get("duration"),
+                s3_key: row.get("s3_key"),
+            })
+            .collect())
+    }
+}
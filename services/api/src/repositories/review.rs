@@ -0,0 +1,349 @@
+//! Review repository for database operations
+
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, Row, Transaction, postgres::PgRow};
+use uuid::Uuid;
+
+use crate::{
+    middleware::AuthUser,
+    models::review::{
+        ModerationItem, RatingSummary, ReportReason, Review, ReviewConfig, ReviewStatus,
+    },
+};
+
+/// Columns of a review
+const REVIEW_COLUMNS: &str =
+    "r.id, r.media_item_id, r.user_id, r.rating, r.body, r.status, r.created_at, r.updated_at";
+
+/// Review repository for database operations
+///
+/// Every write that changes ratings refreshes the item's row in
+/// `media_rating_stats` in the same transaction.
+#[derive(Clone)]
+pub struct ReviewRepository {
+    pool: PgPool,
+    config: ReviewConfig,
+}
+
+impl ReviewRepository {
+    /// Create a new review repository
+    pub fn new(pool: PgPool, config: ReviewConfig) -> Self {
+        Self { pool, config }
+    }
+
+    /// Rate and optionally review a media item, replacing the user's review
+    pub async fn upsert(
+        &self,
+        user_id: Uuid,
+        media_id: Uuid,
+        rating: i16,
+        body: Option<&str>,
+    ) -> Result<Review> {
+        let mut tx = self.pool.begin().await?;
+        lock_stats(&mut tx, media_id).await?;
+
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO reviews AS r (user_id, media_item_id, rating, body)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id, media_item_id) DO UPDATE SET
+                rating = EXCLUDED.rating,
+                body = EXCLUDED.body
+            RETURNING {REVIEW_COLUMNS}
+            "#
+        ))
+        .bind(user_id)
+        .bind(media_id)
+        .bind(rating)
+        .bind(body)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        refresh_stats(&mut tx, media_id).await?;
+        tx.commit().await?;
+
+        Ok(review_from_row(&row))
+    }
+
+    /// Get a review by ID
+    pub async fn get(&self, id: Uuid) -> Result<Option<Review>> {
+        let row = sqlx::query(&format!(
+            "SELECT {REVIEW_COLUMNS} FROM reviews r WHERE r.id = $1"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(review_from_row))
+    }
+
+    /// Get the user's review of a media item
+    pub async fn get_own(&self, user_id: Uuid, media_id: Uuid) -> Result<Option<Review>> {
+        let row = sqlx::query(&format!(
+            "SELECT {REVIEW_COLUMNS} FROM reviews r WHERE r.user_id = $1 AND r.media_item_id = $2"
+        ))
+        .bind(user_id)
+        .bind(media_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(review_from_row))
+    }
+
+    /// Delete a review by ID
+    pub async fn delete(&self, id: Uuid) -> Result<bool> {
+        let Some(review) = self.get(id).await? else {
+            return Ok(false);
+        };
+
+        let mut tx = self.pool.begin().await?;
+        lock_stats(&mut tx, review.media_id).await?;
+
+        let result = sqlx::query("DELETE FROM reviews WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        refresh_stats(&mut tx, review.media_id).await?;
+        tx.commit().await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// List the reviews of a media item, newest first, with the total count
+    ///
+    /// Only published reviews are listed, except that users always see their
+    /// own review and admins see every review.
+    pub async fn list_for_media(
+        &self,
+        media_id: Uuid,
+        user: &AuthUser,
+        page: u32,
+        limit: u32,
+    ) -> Result<(Vec<Review>, i64)> {
+        let offset = (page - 1) * limit;
+        let visible = "r.media_item_id = $1 AND (r.status = 'published' OR r.user_id = $2 OR $3)";
+
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT {REVIEW_COLUMNS}
+            FROM reviews r
+            WHERE {visible}
+            ORDER BY r.created_at DESC, r.id DESC
+            LIMIT $4 OFFSET $5
+            "#
+        ))
+        .bind(media_id)
+        .bind(user.id)
+        .bind(user.is_admin())
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total: i64 =
+            sqlx::query_scalar(&format!("SELECT COUNT(*) FROM reviews r WHERE {visible}"))
+                .bind(media_id)
+                .bind(user.id)
+                .bind(user.is_admin())
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok((rows.iter().map(review_from_row).collect(), total))
+    }
+
+    /// Get the rating aggregates of a media item
+    pub async fn rating_summary(&self, media_id: Uuid) -> Result<RatingSummary> {
+        let row = sqlx::query(
+            "SELECT rating_average, rating_count FROM media_rating_stats WHERE media_item_id = $1",
+        )
+        .bind(media_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(match row {
+            Some(row) => RatingSummary {
+                average: row.get("rating_average"),
+                count: row.get::<i32, _>("rating_count") as i64,
+            },
+            None => RatingSummary {
+                average: None,
+                count: 0,
+            },
+        })
+    }
+
+    /// Report a review
+    ///
+    /// A user can report a review once. Published reviews reaching the
+    /// configured number of open reports are flagged for moderation. Returns
+    /// `false` if the user had already reported the review.
+    pub async fn report(
+        &self,
+        review_id: Uuid,
+        user_id: Uuid,
+        reason: ReportReason,
+        details: Option<&str>,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            "INSERT INTO review_reports (review_id, user_id, reason, details)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (review_id, user_id) DO NOTHING",
+        )
+        .bind(review_id)
+        .bind(user_id)
+        .bind(reason.as_str())
+        .bind(details)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            "UPDATE reviews SET status = 'flagged'
+             WHERE id = $1 AND status = 'published'
+               AND (SELECT COUNT(*) FROM review_reports
+                    WHERE review_id = $1 AND resolved_at IS NULL) >= $2",
+        )
+        .bind(review_id)
+        .bind(self.config.report_threshold)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// List reviews in a moderation state, most reported first
+    pub async fn moderation_queue(
+        &self,
+        status: ReviewStatus,
+        page: u32,
+        limit: u32,
+    ) -> Result<(Vec<ModerationItem>, i64)> {
+        let offset = (page - 1) * limit;
+
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT {REVIEW_COLUMNS},
+                   COUNT(rr.id) AS open_reports,
+                   COALESCE(array_agg(DISTINCT rr.reason) FILTER (WHERE rr.id IS NOT NULL),
+                            ARRAY[]::varchar[]) AS reasons
+            FROM reviews r
+            LEFT JOIN review_reports rr ON rr.review_id = r.id AND rr.resolved_at IS NULL
+            WHERE r.status = $1
+            GROUP BY r.id
+            ORDER BY open_reports DESC, r.updated_at DESC, r.id
+            LIMIT $2 OFFSET $3
+            "#
+        ))
+        .bind(status.as_str())
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM reviews WHERE status = $1")
+            .bind(status.as_str())
+            .fetch_one(&self.pool)
+            .await?;
+
+        let items = rows
+            .iter()
+            .map(|row| ModerationItem {
+                review: review_from_row(row),
+                open_reports: row.get("open_reports"),
+                reasons: row.get("reasons"),
+            })
+            .collect();
+
+        Ok((items, total))
+    }
+
+    /// Set the moderation state of a review, resolving its open reports
+    pub async fn moderate(&self, id: Uuid, status: ReviewStatus) -> Result<Option<Review>> {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query(&format!(
+            "UPDATE reviews r SET status = $2 WHERE r.id = $1 RETURNING {REVIEW_COLUMNS}"
+        ))
+        .bind(id)
+        .bind(status.as_str())
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if row.is_some() {
+            sqlx::query(
+                "UPDATE review_reports SET resolved_at = NOW()
+                 WHERE review_id = $1 AND resolved_at IS NULL",
+            )
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(row.as_ref().map(review_from_row))
+    }
+}
+
+/// Lock the rating aggregates of a media item for the rest of the transaction
+///
+/// Serializes concurrent rating writes so the refreshed aggregates always
+/// include every committed rating.
+async fn lock_stats(tx: &mut Transaction<'_, Postgres>, media_id: Uuid) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO media_rating_stats (media_item_id) VALUES ($1)
+         ON CONFLICT (media_item_id) DO NOTHING",
+    )
+    .bind(media_id)
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query("SELECT 1 FROM media_rating_stats WHERE media_item_id = $1 FOR UPDATE")
+        .bind(media_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+/// Recompute the rating aggregates of a media item
+async fn refresh_stats(tx: &mut Transaction<'_, Postgres>, media_id: Uuid) -> Result<()> {
+    sqlx::query(
+        "UPDATE media_rating_stats SET
+            rating_count = stats.count,
+            rating_average = stats.average,
+            updated_at = NOW()
+         FROM (SELECT COUNT(*)::integer AS count, AVG(rating)::double precision AS average
+               FROM reviews WHERE media_item_id = $1) AS stats
+         WHERE media_item_id = $1",
+    )
+    .bind(media_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Map a reviews row selected with `REVIEW_COLUMNS`
+fn review_from_row(row: &PgRow) -> Review {
+    let status: String = row.get("status");
+
+    Review {
+        id: row.get("id"),
+        media_id: row.get("media_item_id"),
+        user_id: row.get("user_id"),
+        rating: row.get("rating"),
+        body: row.get("body"),
+        status: ReviewStatus::parse(&status).unwrap_or(ReviewStatus::Hidden),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
@@ -0,0 +1,184 @@
+//! Series repository for database operations
+
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, QueryBuilder, Row, postgres::PgRow};
+use uuid::Uuid;
+
+use crate::{
+    middleware::AuthUser,
+    models::{
+        media::MediaItem,
+        series::{SeasonSummary, Series},
+    },
+    repositories::{
+        access::{Reach, push_visible},
+        media::media_item_from_row,
+    },
+};
+
+/// Columns of a series with counts of its listed episodes
+const SERIES_COLUMNS: &str = r#"
+    se.id, se.title, se.description, se.created_at, se.updated_at,
+    COUNT(DISTINCT sn.id) AS season_count, COUNT(m.id) AS episode_count
+"#;
+
+/// Columns of a media item joined as `m`
+const EPISODE_COLUMNS: &str = r#"
+    m.id, m.type, m.metadata, m.s3_key, m.status, m.user_id, m.created_at,
+    m.updated_at, m.duration, m.width, m.height, m.video_codec, m.audio_codec,
+    m.format, m.bitrate, m.sample_rate, m.channels, m.thumbnail_url,
+    m.visibility, m.deleted_at, m.kind, m.season_id, m.episode_number,
+    m.maturity_rating
+"#;
+
+/// Series repository for database operations
+///
+/// Series and seasons only exist through their episodes: series without any
+/// episode the user may see are reported as missing.
+#[derive(Clone)]
+pub struct SeriesRepository {
+    pool: PgPool,
+}
+
+impl SeriesRepository {
+    /// Create a new series repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// List series by title with the total count
+    pub async fn list(&self, user: &AuthUser, page: u32, limit: u32) -> Result<(Vec<Series>, i64)> {
+        let offset = (page - 1) * limit;
+
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {SERIES_COLUMNS} FROM series se \
+             JOIN seasons sn ON sn.series_id = se.id \
+             JOIN media_items m ON m.season_id = sn.id WHERE "
+        ));
+        push_visible(&mut builder, "m", user, Reach::Listed);
+        builder
+            .push(" GROUP BY se.id ORDER BY LOWER(se.title), se.id LIMIT ")
+            .push_bind(limit as i64)
+            .push(" OFFSET ")
+            .push_bind(offset as i64);
+        let rows = builder.build().fetch_all(&self.pool).await?;
+
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "SELECT COUNT(DISTINCT sn.series_id) FROM seasons sn \
+             JOIN media_items m ON m.season_id = sn.id WHERE ",
+        );
+        push_visible(&mut builder, "m", user, Reach::Listed);
+        let total: i64 = builder.build_query_scalar().fetch_one(&self.pool).await?;
+
+        Ok((rows.iter().map(series_from_row).collect(), total))
+    }
+
+    /// Get a series by ID
+    pub async fn get(&self, id: Uuid, user: &AuthUser) -> Result<Option<Series>> {
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {SERIES_COLUMNS} FROM series se \
+             JOIN seasons sn ON sn.series_id = se.id \
+             JOIN media_items m ON m.season_id = sn.id WHERE se.id = "
+        ));
+        builder.push_bind(id).push(" AND ");
+        push_visible(&mut builder, "m", user, Reach::Listed);
+        builder.push(" GROUP BY se.id");
+        let row = builder.build().fetch_optional(&self.pool).await?;
+
+        Ok(row.as_ref().map(series_from_row))
+    }
+
+    /// Get the seasons of a series with the watched counts of the user's profile
+    pub async fn seasons(&self, series_id: Uuid, user: &AuthUser) -> Result<Vec<SeasonSummary>> {
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "SELECT sn.id, sn.number, sn.title, COUNT(m.id) AS episode_count, \
+             COUNT(m.id) FILTER (WHERE p.finished) AS watched_count \
+             FROM seasons sn \
+             JOIN media_items m ON m.season_id = sn.id \
+             LEFT JOIN playback_progress p ON p.media_item_id = m.id AND p.user_id = ",
+        );
+        builder
+            .push_bind(user.id)
+            .push(" AND p.profile_id IS NOT DISTINCT FROM ")
+            .push_bind(user.profile_id)
+            .push(" WHERE sn.series_id = ")
+            .push_bind(series_id)
+            .push(" AND ");
+        push_visible(&mut builder, "m", user, Reach::Listed);
+        builder.push(" GROUP BY sn.id ORDER BY sn.number");
+        let rows = builder.build().fetch_all(&self.pool).await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| SeasonSummary {
+                id: row.get("id"),
+                number: row.get("number"),
+                title: row.get("title"),
+                episode_count: row.get("episode_count"),
+                watched_count: row.get("watched_count"),
+            })
+            .collect())
+    }
+
+    /// Get the episodes of a season in order
+    pub async fn episodes(
+        &self,
+        series_id: Uuid,
+        season: i32,
+        user: &AuthUser,
+    ) -> Result<Vec<MediaItem>> {
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {EPISODE_COLUMNS} FROM seasons sn \
+             JOIN media_items m ON m.season_id = sn.id WHERE sn.series_id = "
+        ));
+        builder
+            .push_bind(series_id)
+            .push(" AND sn.number = ")
+            .push_bind(season)
+            .push(" AND ");
+        push_visible(&mut builder, "m", user, Reach::Listed);
+        builder.push(" ORDER BY m.episode_number, m.created_at");
+        let rows = builder.build().fetch_all(&self.pool).await?;
+
+        Ok(rows.iter().map(media_item_from_row).collect())
+    }
+
+    /// Get the episode following an episode, for autoplay
+    ///
+    /// This is the next episode of the same season, or the first episode of
+    /// a later season once the season is over.
+    pub async fn next_episode(&self, media_id: Uuid, user: &AuthUser) -> Result<Option<MediaItem>> {
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "WITH current AS (\
+             SELECT sn.series_id, sn.number, c.episode_number \
+             FROM media_items c \
+             JOIN seasons sn ON sn.id = c.season_id \
+             WHERE c.id = ",
+        );
+        builder.push_bind(media_id).push(format!(
+            " AND c.episode_number IS NOT NULL) \
+             SELECT {EPISODE_COLUMNS} FROM current \
+             JOIN seasons sn ON sn.series_id = current.series_id \
+             JOIN media_items m ON m.season_id = sn.id \
+             WHERE (sn.number, m.episode_number) > (current.number, current.episode_number) AND "
+        ));
+        push_visible(&mut builder, "m", user, Reach::Listed);
+        builder.push(" ORDER BY sn.number, m.episode_number, m.created_at LIMIT 1");
+        let row = builder.build().fetch_optional(&self.pool).await?;
+
+        Ok(row.as_ref().map(media_item_from_row))
+    }
+}
+
+/// Map a series row selected with `SERIES_COLUMNS`
+fn series_from_row(row: &PgRow) -> Series {
+    Series {
+        id: row.get("id"),
+        title: row.get("title"),
+        description: row.get("description"),
+        season_count: row.get("season_count"),
+        episode_count: row.get("episode_count"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
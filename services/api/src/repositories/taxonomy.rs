@@ -0,0 +1,280 @@
+//! Tag and genre repository for database operations
+
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, QueryBuilder, Row, Transaction, postgres::PgRow};
+use uuid::Uuid;
+
+use crate::{
+    middleware::AuthUser,
+    models::taxonomy::{Genre, TagSuggestion, slugify},
+    repositories::access::{Reach, push_visible},
+};
+
+/// Tag and genre repository for database operations
+#[derive(Clone)]
+pub struct TaxonomyRepository {
+    pool: PgPool,
+}
+
+impl TaxonomyRepository {
+    /// Create a new taxonomy repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// List all genres by name
+    pub async fn list_genres(&self) -> Result<Vec<Genre>> {
+        let rows = sqlx::query(
+            "SELECT id, name, slug, created_at, updated_at FROM genres ORDER BY name, id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(genre_from_row).collect())
+    }
+
+    /// Find the genres with the given IDs
+    pub async fn find_genres(&self, ids: &[Uuid]) -> Result<Vec<Genre>> {
+        let rows = sqlx::query(
+            "SELECT id, name, slug, created_at, updated_at FROM genres
+             WHERE id = ANY($1) ORDER BY name, id",
+        )
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(genre_from_row).collect())
+    }
+
+    /// Create a genre, returning `None` if one with the same slug exists
+    pub async fn create_genre(&self, name: &str, slug: &str) -> Result<Option<Genre>> {
+        let row = sqlx::query(
+            "INSERT INTO genres (name, slug) VALUES ($1, $2)
+             ON CONFLICT (slug) DO NOTHING
+             RETURNING id, name, slug, created_at, updated_at",
+        )
+        .bind(name)
+        .bind(slug)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(genre_from_row))
+    }
+
+    /// Rename a genre
+    ///
+    /// Returns `Err` if another genre already has the new slug and `Ok(None)`
+    /// if the genre does not exist.
+    pub async fn rename_genre(
+        &self,
+        id: Uuid,
+        name: &str,
+        slug: &str,
+    ) -> Result<Option<Genre>, RenameGenreError> {
+        let taken: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM genres WHERE slug = $1 AND id <> $2)")
+                .bind(slug)
+                .bind(id)
+                .fetch_one(&self.pool)
+                .await?;
+        if taken {
+            return Err(RenameGenreError::SlugTaken);
+        }
+
+        let row = sqlx::query(
+            "UPDATE genres SET name = $2, slug = $3 WHERE id = $1
+             RETURNING id, name, slug, created_at, updated_at",
+        )
+        .bind(id)
+        .bind(name)
+        .bind(slug)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(genre_from_row))
+    }
+
+    /// Delete a genre, unlinking it from all media items
+    pub async fn delete_genre(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM genres WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the genres of a media item
+    pub async fn media_genres(&self, media_id: Uuid) -> Result<Vec<Genre>> {
+        let rows = sqlx::query(
+            "SELECT g.id, g.name, g.slug, g.created_at, g.updated_at
+             FROM media_item_genres mg
+             JOIN genres g ON g.id = mg.genre_id
+             WHERE mg.media_item_id = $1
+             ORDER BY g.name, g.id",
+        )
+        .bind(media_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(genre_from_row).collect())
+    }
+
+    /// Replace the genres of a media item owned by the user
+    ///
+    /// Returns `false` if the item does not exist, is deleted, or the user
+    /// may not change it.
+    pub async fn set_media_genres(
+        &self,
+        media_id: Uuid,
+        user: &AuthUser,
+        genre_ids: &[Uuid],
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let editable: Option<Uuid> = sqlx::query_scalar(
+            "SELECT id FROM media_items
+             WHERE id = $1 AND deleted_at IS NULL AND ($2 OR user_id = $3)
+             FOR UPDATE",
+        )
+        .bind(media_id)
+        .bind(user.is_admin())
+        .bind(user.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if editable.is_none() {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM media_item_genres WHERE media_item_id = $1")
+            .bind(media_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO media_item_genres (media_item_id, genre_id)
+             SELECT $1, id FROM genres WHERE id = ANY($2)",
+        )
+        .bind(media_id)
+        .bind(genre_ids)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Suggest tags starting with partial input, most used first
+    ///
+    /// Usage is counted over the items listed to the user.
+    pub async fn suggest_tags(
+        &self,
+        input: &str,
+        limit: u32,
+        user: &AuthUser,
+    ) -> Result<Vec<TagSuggestion>> {
+        let prefix = slugify(input);
+        if prefix.is_empty() {
+            return Ok(Vec::new());
+        }
+        let pattern = format!(
+            "{}%",
+            prefix
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "SELECT t.name, t.slug, COUNT(m.id) AS count \
+             FROM tags t \
+             JOIN media_item_tags mt ON mt.tag_id = t.id \
+             JOIN media_items m ON m.id = mt.media_item_id \
+             WHERE t.slug LIKE ",
+        );
+        builder.push_bind(pattern).push(" AND ");
+        push_visible(&mut builder, "m", user, Reach::Listed);
+        builder
+            .push(" GROUP BY t.id ORDER BY count DESC, t.slug LIMIT ")
+            .push_bind(limit as i64);
+        let rows = builder.build().fetch_all(&self.pool).await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| TagSuggestion {
+                name: row.get("name"),
+                slug: row.get("slug"),
+                count: row.get("count"),
+            })
+            .collect())
+    }
+}
+
+/// Errors renaming a genre
+#[derive(Debug, thiserror::Error)]
+pub enum RenameGenreError {
+    /// Another genre already has the slug of the new name
+    #[error("Genre slug is already taken")]
+    SlugTaken,
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// Replace the tags linked to a media item
+///
+/// `names` must already be normalized; tags that do not exist yet are
+/// created. Runs inside the caller's transaction so the links always match
+/// the tags kept in the item's metadata.
+pub(crate) async fn sync_media_tags(
+    tx: &mut Transaction<'_, Postgres>,
+    media_id: Uuid,
+    names: &[String],
+    user_id: Uuid,
+) -> Result<()> {
+    let slugs: Vec<String> = names.iter().map(|name| slugify(name)).collect();
+
+    sqlx::query(
+        "INSERT INTO tags (name, slug)
+         SELECT * FROM unnest($1::text[], $2::text[])
+         ON CONFLICT (slug) DO NOTHING",
+    )
+    .bind(names)
+    .bind(&slugs)
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query(
+        "DELETE FROM media_item_tags
+         WHERE media_item_id = $1
+           AND tag_id NOT IN (SELECT id FROM tags WHERE slug = ANY($2))",
+    )
+    .bind(media_id)
+    .bind(&slugs)
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO media_item_tags (media_item_id, tag_id, added_by)
+         SELECT $1, id, $3 FROM tags WHERE slug = ANY($2)
+         ON CONFLICT (media_item_id, tag_id) DO NOTHING",
+    )
+    .bind(media_id)
+    .bind(&slugs)
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Map a genres row
+fn genre_from_row(row: &PgRow) -> Genre {
+    Genre {
+        id: row.get("id"),
+        name: row.get("name"),
+        slug: row.get("slug"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
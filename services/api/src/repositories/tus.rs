@@ -0,0 +1,245 @@
+//! Resumable upload repository for database operations
+
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, Row, Transaction, postgres::PgRow};
+use uuid::Uuid;
+
+use crate::{models::upload::TusUpload, storage::UploadedPart};
+
+/// Columns of a tus_uploads row
+const TUS_UPLOAD_COLUMNS: &str = "id, user_id, upload_length, upload_offset, metadata, s3_key, \
+     multipart_upload_id, part_size, media_item_id, expires_at, completed_at";
+
+/// Postgres error code of a row lock that could not be taken immediately
+const LOCK_NOT_AVAILABLE: &str = "55P03";
+
+/// Resumable upload repository for database operations
+#[derive(Clone)]
+pub struct TusRepository {
+    pool: PgPool,
+}
+
+impl TusRepository {
+    /// Create a new resumable upload repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Record a new resumable upload
+    pub async fn create(&self, upload: &TusUpload) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO tus_uploads (id, user_id, upload_length, upload_offset, metadata, s3_key, multipart_upload_id, part_size, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        )
+        .bind(upload.id)
+        .bind(upload.user_id)
+        .bind(upload.upload_length)
+        .bind(upload.upload_offset)
+        .bind(&upload.metadata)
+        .bind(&upload.s3_key)
+        .bind(&upload.multipart_upload_id)
+        .bind(upload.part_size)
+        .bind(upload.expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Find a resumable upload started by the user
+    pub async fn find_for_user(&self, id: Uuid, user_id: Uuid) -> Result<Option<TusUpload>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM tus_uploads WHERE id = $1 AND user_id = $2",
+            TUS_UPLOAD_COLUMNS
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(tus_upload_from_row))
+    }
+
+    /// Lock a resumable upload started by the user for writing
+    ///
+    /// The row stays locked until the returned upload is committed or
+    /// dropped, across all API instances. Fails with [`TusLockError::Locked`]
+    /// instead of waiting if another request holds the lock.
+    pub async fn lock_for_user(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<LockedTusUpload>, TusLockError> {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM tus_uploads WHERE id = $1 AND user_id = $2 FOR UPDATE NOWAIT",
+            TUS_UPLOAD_COLUMNS
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| match e.as_database_error().and_then(|e| e.code()) {
+            Some(code) if code == LOCK_NOT_AVAILABLE => TusLockError::Locked,
+            _ => TusLockError::Database(e),
+        })?;
+
+        Ok(row.map(|row| LockedTusUpload {
+            tx,
+            upload: tus_upload_from_row(&row),
+        }))
+    }
+
+    /// Delete incomplete uploads past their expiry, returning them so their
+    /// storage can be discarded
+    pub async fn delete_expired(&self) -> Result<Vec<TusUpload>> {
+        let rows = sqlx::query(&format!(
+            "DELETE FROM tus_uploads
+             WHERE completed_at IS NULL AND expires_at < NOW()
+             RETURNING {}",
+            TUS_UPLOAD_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(tus_upload_from_row).collect())
+    }
+}
+
+/// Resumable upload locked for writing by the current request
+pub struct LockedTusUpload {
+    tx: Transaction<'static, Postgres>,
+    pub upload: TusUpload,
+}
+
+impl LockedTusUpload {
+    /// Get the parts uploaded so far in order
+    pub async fn parts(&mut self) -> Result<Vec<UploadedPart>> {
+        let rows = sqlx::query(
+            "SELECT part_number, etag FROM tus_upload_parts
+             WHERE upload_id = $1
+             ORDER BY part_number ASC",
+        )
+        .bind(self.upload.id)
+        .fetch_all(&mut *self.tx)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| UploadedPart {
+                part_number: row.get("part_number"),
+                etag: row.get("etag"),
+            })
+            .collect())
+    }
+
+    /// Record newly uploaded parts and the number of bytes received
+    pub async fn record_progress(&mut self, offset: i64, parts: &[UploadedPart]) -> Result<()> {
+        for part in parts {
+            sqlx::query(
+                "INSERT INTO tus_upload_parts (upload_id, part_number, etag)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (upload_id, part_number) DO UPDATE SET etag = EXCLUDED.etag",
+            )
+            .bind(self.upload.id)
+            .bind(part.part_number)
+            .bind(&part.etag)
+            .execute(&mut *self.tx)
+            .await?;
+        }
+
+        sqlx::query("UPDATE tus_uploads SET upload_offset = $2 WHERE id = $1")
+            .bind(self.upload.id)
+            .bind(offset)
+            .execute(&mut *self.tx)
+            .await?;
+        self.upload.upload_offset = offset;
+
+        Ok(())
+    }
+
+    /// Mark the upload as completed and create its media item
+    ///
+    /// The media item is created in `pending` status so the media service
+    /// picks it up for processing.
+    pub async fn complete(
+        &mut self,
+        media_id: Uuid,
+        media_type: &str,
+        metadata: &serde_json::Value,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO media_items (id, type, metadata, s3_key, status, user_id)
+             VALUES ($1, $2, $3, $4, 'pending', $5)",
+        )
+        .bind(media_id)
+        .bind(media_type)
+        .bind(metadata)
+        .bind(&self.upload.s3_key)
+        .bind(self.upload.user_id)
+        .execute(&mut *self.tx)
+        .await?;
+
+        sqlx::query(
+            "UPDATE tus_uploads SET completed_at = NOW(), media_item_id = $2
+             WHERE id = $1",
+        )
+        .bind(self.upload.id)
+        .bind(media_id)
+        .execute(&mut *self.tx)
+        .await?;
+        self.upload.media_item_id = Some(media_id);
+        self.upload.completed_at = Some(chrono::Utc::now());
+
+        Ok(())
+    }
+
+    /// Delete the upload
+    pub async fn delete(&mut self) -> Result<()> {
+        sqlx::query("DELETE FROM tus_uploads WHERE id = $1")
+            .bind(self.upload.id)
+            .execute(&mut *self.tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Save the changes and release the lock
+    pub async fn commit(self) -> Result<TusUpload> {
+        self.tx.commit().await?;
+        Ok(self.upload)
+    }
+}
+
+/// Errors locking a resumable upload
+#[derive(Debug, thiserror::Error)]
+pub enum TusLockError {
+    /// Another request is writing to the upload
+    #[error("Upload is locked")]
+    Locked,
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// Map a tus_uploads row to a resumable upload
+///
+/// Uploads completed before they were streamed to storage have no
+/// multipart upload or part size.
+fn tus_upload_from_row(row: &PgRow) -> TusUpload {
+    TusUpload {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        upload_length: row.get("upload_length"),
+        upload_offset: row.get("upload_offset"),
+        metadata: row.get("metadata"),
+        s3_key: row.get::<Option<String>, _>("s3_key").unwrap_or_default(),
+        multipart_upload_id: row
+            .get::<Option<String>, _>("multipart_upload_id")
+            .unwrap_or_default(),
+        part_size: row.get::<Option<i64>, _>("part_size").unwrap_or_default(),
+        media_item_id: row.get("media_item_id"),
+        expires_at: row.get("expires_at"),
+        completed_at: row.get("completed_at"),
+    }
+}
@@ -0,0 +1,133 @@
+//! Upload repository for database operations
+
+use anyhow::Result;
+use sqlx::{PgPool, Row, postgres::PgRow};
+use uuid::Uuid;
+
+use crate::models::upload::MediaUpload;
+
+/// Upload repository for database operations
+#[derive(Clone)]
+pub struct UploadRepository {
+    pool: PgPool,
+}
+
+impl UploadRepository {
+    /// Create a new upload repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Record a new upload together with its media item
+    ///
+    /// The media item stays in `awaiting_upload` status, which the media
+    /// service skips, until the upload is completed.
+    pub async fn create(
+        &self,
+        upload: &MediaUpload,
+        media_type: &str,
+        metadata: &serde_json::Value,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO media_items (id, type, metadata, s3_key, status, user_id)
+             VALUES ($1, $2, $3, $4, 'awaiting_upload', $5)",
+        )
+        .bind(upload.media_item_id)
+        .bind(media_type)
+        .bind(metadata)
+        .bind(&upload.s3_key)
+        .bind(upload.user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO media_uploads (id, media_item_id, user_id, s3_key, content_type, size, multipart_upload_id, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(upload.id)
+        .bind(upload.media_item_id)
+        .bind(upload.user_id)
+        .bind(&upload.s3_key)
+        .bind(&upload.content_type)
+        .bind(upload.size)
+        .bind(&upload.multipart_upload_id)
+        .bind(upload.expires_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Find an upload started by the user
+    pub async fn find_for_user(&self, id: Uuid, user_id: Uuid) -> Result<Option<MediaUpload>> {
+        let row = sqlx::query(
+            "SELECT id, media_item_id, user_id, s3_key, content_type, size, multipart_upload_id, expires_at, completed_at
+             FROM media_uploads
+             WHERE id = $1 AND user_id = $2",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(upload_from_row))
+    }
+
+    /// Mark an upload as completed and hand the media item to processing
+    pub async fn mark_completed(&self, upload: &MediaUpload) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("UPDATE media_uploads SET completed_at = NOW() WHERE id = $1")
+            .bind(upload.id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            "UPDATE media_items SET status = 'pending', updated_at = NOW()
+             WHERE id = $1 AND status = 'awaiting_upload'",
+        )
+        .bind(upload.media_item_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Delete uploads that expired before being completed, with their media
+    /// items, returning them so their storage can be discarded
+    pub async fn delete_expired(&self) -> Result<Vec<MediaUpload>> {
+        let rows = sqlx::query(
+            "DELETE FROM media_items m
+             USING media_uploads u
+             WHERE u.media_item_id = m.id AND m.status = 'awaiting_upload'
+                AND u.completed_at IS NULL AND u.expires_at < NOW()
+             RETURNING u.id, u.media_item_id, u.user_id, u.s3_key, u.content_type, u.size,
+                u.multipart_upload_id, u.expires_at, u.completed_at",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(upload_from_row).collect())
+    }
+}
+
+/// Map a media_uploads row to an upload
+fn upload_from_row(row: &PgRow) -> MediaUpload {
+    MediaUpload {
+        id: row.get("id"),
+        media_item_id: row.get("media_item_id"),
+        user_id: row.get("user_id"),
+        s3_key: row.get("s3_key"),
+        content_type: row.get("content_type"),
+        size: row.get("size"),
+        multipart_upload_id: row.get("multipart_upload_id"),
+        expires_at: row.get("expires_at"),
+        completed_at: row.get("completed_at"),
+    }
+}
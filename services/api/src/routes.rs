@@ -1,22 +1,78 @@
 //! API service routes
 
 use axum::{
-    Json, Router,
-    extract::{Path, Query, State},
+    Extension, Json, Router,
+    body::Body,
+    extract::{ConnectInfo, Path, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
     middleware,
-    response::IntoResponse,
-    routing::{delete, get, post},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::{delete, get, head, patch, post, put},
 };
+use futures_util::stream;
 use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
+use tokio::sync::broadcast::error::RecvError;
 use uuid::Uuid;
 
 use crate::{
     AppState,
     error::ApiError,
-    middleware::auth_middleware,
+    hls::{
+        HLS_RENDITION, PLAYLIST_CONTENT_TYPE, master_playlist, parse_segment_name, total_duration,
+        variant_playlist,
+    },
+    middleware::{AuthUser, auth_middleware, authenticate_with_limits},
     models::{
         CreateUserRequest, SessionResponse, UserResponse,
-        media::{MediaItem, MediaListResponse, MediaQuery, MediaRefreshRequest},
+        collection::{
+            AddCollectionItemRequest, Collection, CollectionDetail, CollectionNameRequest,
+            ReorderCollectionRequest, ShareCollectionResponse,
+        },
+        comment::{
+            Comment, CommentListResponse, CommentQuery, CommentStatus, CreateCommentRequest,
+            ModerateCommentRequest, UpdateCommentRequest, validate_body as validate_comment_body,
+        },
+        history::{
+            HISTORY_PAUSED_SETTING, HistoryListResponse, HistoryQuery, HistorySettings,
+            NewHistoryEntry,
+        },
+        media::{
+            MaturityRatingRequest, MediaItem, MediaListResponse, MediaQuery, MediaRefreshRequest,
+            MediaVisibilityRequest, PlaybackUrlRequest, PlaybackUrlResponse, SuggestQuery,
+            SuggestResponse, TrashQuery, Visibility, validate_metadata_patch,
+        },
+        notification::{NotificationListResponse, NotificationQuery},
+        parental::{UpdateParentalControlsRequest, UpdatePinRequest, hash_pin, validate_pin},
+        profile::{CreateProfileRequest, MAX_PROFILES, UpdateProfileRequest},
+        progress::{ContinueWatchingQuery, ProgressQuery, ProgressUpdateRequest},
+        recommendation::RecommendationQuery,
+        review::{
+            ModerateReviewRequest, ModerationListResponse, ModerationQuery, ReportReviewRequest,
+            ReviewListResponse, ReviewQuery, ReviewRequest, ReviewStatus,
+        },
+        series::{SeasonDetail, SeriesDetail, SeriesListResponse, SeriesQuery},
+        taxonomy::{GenreRequest, MediaGenresRequest, TagSuggestQuery},
+        upload::{
+            CompleteUploadRequest, CreateUploadRequest, CreateUploadResponse, MediaUpload,
+            TusUpload, UploadPartUrl, media_type_for, sanitize_filename, title_for,
+        },
+    },
+    playback::{ORIGINAL_RENDITION, PlaybackGrant, PlaybackQuery},
+    repositories::{
+        parental::PinError,
+        profile::ProfileError,
+        taxonomy::RenameGenreError,
+        tus::{LockedTusUpload, TusLockError},
+    },
+    storage::UploadedPart,
+    streaming::{ByteRange, content_type_for, etag_matches, if_range_matches, parse_range},
+    tus::{
+        OFFSET_CONTENT_TYPE, TUS_EXTENSIONS, TUS_VERSION, TusError, check_version, format_expires,
+        parse_length_header, parse_upload_metadata, tus_response,
     },
 };
 
@@ -25,8 +81,114 @@ pub fn create_router(state: AppState) -> Router {
     let protected_routes = Router::new()
         .route("/protected", get(protected_route))
         .route("/media", get(get_media_items))
-        .route("/media/:id", get(get_media_item))
+        .route("/media/trash", get(get_trash))
+        .route(
+            "/media/:id",
+            get(get_media_item)
+                .patch(update_media_item)
+                .delete(delete_media_item),
+        )
+        .route("/media/:id/playback-url", post(create_playback_url))
+        .route("/media/:id/progress", put(update_progress))
+        .route("/media/:id/next", get(get_next_episode))
+        .route("/media/:id/similar", get(get_similar_media))
+        .route("/me/recommendations", get(get_recommendations))
+        .route(
+            "/media/:id/genres",
+            get(get_media_genres).put(update_media_genres),
+        )
+        .route(
+            "/media/:id/review",
+            get(get_own_review)
+                .put(put_own_review)
+                .delete(delete_own_review),
+        )
+        .route("/media/:id/reviews", get(get_media_reviews))
+        .route("/reviews/:id", delete(delete_review))
+        .route("/reviews/:id/reports", post(report_review))
+        .route("/reviews/:id/moderation", put(moderate_review))
+        .route("/admin/reviews", get(get_moderation_queue))
+        .route(
+            "/media/:id/comments",
+            get(get_media_comments).post(create_comment),
+        )
+        .route("/media/:id/comments/live", get(subscribe_media_comments))
+        .route(
+            "/comments/:id",
+            get(get_comment)
+                .patch(update_comment)
+                .delete(delete_comment),
+        )
+        .route("/comments/:id/replies", get(get_comment_replies))
+        .route("/comments/:id/moderation", put(moderate_comment))
+        .route("/admin/comments", get(get_admin_comments))
+        .route("/me/notifications", get(get_notifications))
+        .route("/me/notifications/read", post(read_all_notifications))
+        .route("/me/notifications/:id/read", post(read_notification))
+        .route("/genres", get(get_genres).post(create_genre))
+        .route("/genres/:id", patch(update_genre).delete(delete_genre))
+        .route("/tags/suggest", get(suggest_tags))
+        .route("/series", get(get_series_list))
+        .route("/series/:id", get(get_series))
+        .route("/series/:id/seasons/:number", get(get_season))
+        .route("/me/profiles", get(get_profiles).post(create_profile))
+        .route(
+            "/me/profiles/:id",
+            patch(update_profile).delete(delete_profile),
+        )
+        .route(
+            "/me/parental-controls",
+            get(get_parental_controls).put(update_parental_controls),
+        )
+        .route("/me/parental-controls/pin", put(update_parental_pin))
+        .route("/media/progress", get(get_progress))
+        .route("/me/continue-watching", get(get_continue_watching))
+        .route(
+            "/me/history",
+            get(get_history).post(record_history).delete(clear_history),
+        )
+        .route(
+            "/me/history/settings",
+            get(get_history_settings).put(update_history_settings),
+        )
+        .route("/me/history/:id", delete(delete_history_entry))
+        .route(
+            "/me/collections",
+            get(get_collections).post(create_collection),
+        )
+        .route(
+            "/me/collections/:id",
+            get(get_collection)
+                .patch(update_collection)
+                .delete(delete_collection),
+        )
+        .route("/me/collections/:id/items", post(add_collection_item))
+        .route("/me/collections/:id/items/order", put(reorder_collection))
+        .route(
+            "/me/collections/:id/items/:media_id",
+            delete(remove_collection_item),
+        )
+        .route(
+            "/me/collections/:id/share",
+            post(share_collection).delete(unshare_collection),
+        )
+        .route("/media/:id/restore", post(restore_media_item))
+        .route("/media/:id/visibility", put(update_media_visibility))
+        .route(
+            "/media/:id/maturity-rating",
+            put(update_media_maturity_rating),
+        )
+        .route("/media/search/suggest", get(suggest_media))
         .route("/media/refresh", post(refresh_media))
+        .route("/media/uploads", post(create_upload))
+        .route("/media/uploads/:id/complete", post(complete_upload))
+        .route("/media/tus", post(create_tus_upload).options(tus_options))
+        .route(
+            "/media/tus/:id",
+            head(head_tus_upload)
+                .patch(patch_tus_upload)
+                .delete(delete_tus_upload),
+        )
         .route_layer(middleware::from_fn_with_state(
             state.clone(),
             auth_middleware,
@@ -39,6 +201,11 @@ pub fn create_router(state: AppState) -> Router {
         .route("/users/:id", get(get_user))
         .route("/sessions", get(get_sessions))
         .route("/sessions/:id", delete(delete_session))
+        .route("/collections/shared/:token", get(get_shared_collection))
+        .route("/media/:id/stream", get(stream_media_item))
+        .route("/media/:id/hls/master.m3u8", get(get_hls_master))
+        .route("/media/:id/hls/:rendition/index.m3u8", get(get_hls_variant))
+        .route("/media/:id/hls/:rendition/:segment", get(get_hls_segment))
         .merge(protected_routes)
         .with_state(state)
 }
@@ -104,53 +271,2901 @@ pub async fn get_sessions(State(state): State<AppState>) -> Result<impl IntoResp
 /// Get a media item by ID
 pub async fn get_media_item(
     State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, ApiError> {
     let media_item = state
         .media_repository
-        .get_by_id(id)
+        .get_by_id(id, &user)
         .await
         .map_err(|e| {
             tracing::error!("Failed to get media item: {}", e);
             ApiError::InternalServerError
         })?
-        .ok_or(ApiError::BadRequest("Media item not found".to_string()))?;
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
 
     Ok(Json(media_item))
 }
 
-/// Get media items with pagination, sorting, and filtering
-pub async fn get_media_items(
+/// Issue a signed playback URL for a media item
+///
+/// The `original` rendition is played through `/media/:id/stream`, `hls`
+/// through the HLS master playlist. The URL can be handed to media elements
+/// and CDNs, which cannot send the bearer token, and stops working after
+/// `PLAYBACK_URL_EXPIRY` seconds.
+pub async fn create_playback_url(
     State(state): State<AppState>,
-    Query(query): Query<MediaQuery>,
+    Extension(user): Extension<AuthUser>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(id): Path<Uuid>,
+    payload: Option<Json<PlaybackUrlRequest>>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let (items, total) = state
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    let rendition = payload
+        .rendition
+        .unwrap_or_else(|| ORIGINAL_RENDITION.to_string());
+
+    let path = match rendition.as_str() {
+        ORIGINAL_RENDITION => format!("/media/{}/stream", id),
+        HLS_RENDITION => format!("/media/{}/hls/master.m3u8", id),
+        _ => {
+            return Err(ApiError::BadRequest(format!(
+                "Unknown rendition: {}",
+                rendition
+            )));
+        }
+    };
+
+    state
         .media_repository
-        .get_media_items(&query)
+        .get_by_id(id, &user)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to get media items: {}", e);
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    let access = PlaybackAccess {
+        user_id: user.id,
+        ip: payload.bind_ip.then_some(addr.ip()),
+    };
+    let expires_at =
+        chrono::Utc::now() + chrono::Duration::seconds(state.playback.config().url_expiry as i64);
+
+    Ok(Json(PlaybackUrlResponse {
+        url: format!(
+            "{}?{}",
+            path,
+            sign_playback(&state, id, &rendition, &access, expires_at.timestamp())
+        ),
+        expires_at,
+    }))
+}
+
+/// Caller allowed to play a media item
+struct PlaybackAccess {
+    user_id: Uuid,
+    /// Client IP signed URLs handed to the caller are bound to
+    ip: Option<IpAddr>,
+}
+
+/// Authorize playback of a media item's rendition
+///
+/// Requests carry either a signed playback URL, verified without a database
+/// lookup, or a bearer token checked against the item's visibility and the
+/// user's maturity limit. Signed URLs are only issued after the same check.
+async fn authorize_playback(
+    state: &AppState,
+    id: Uuid,
+    rendition: &str,
+    query: &PlaybackQuery,
+    client_ip: IpAddr,
+    headers: &HeaderMap,
+) -> Result<PlaybackAccess, ApiError> {
+    if query.is_signed() {
+        let user_id = state.playback.verify(
+            id,
+            rendition,
+            query,
+            client_ip,
+            chrono::Utc::now().timestamp(),
+        )?;
+
+        return Ok(PlaybackAccess {
+            user_id,
+            ip: query.bind_ip.then_some(client_ip),
+        });
+    }
+
+    let user = authenticate_with_limits(state, headers).await?;
+    state
+        .media_repository
+        .get_by_id(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    Ok(PlaybackAccess {
+        user_id: user.id,
+        ip: None,
+    })
+}
+
+/// Refuse signed playback of a media item deleted since the URL was signed
+///
+/// Token requests already check this in `authorize_playback`, and the
+/// cached rendition lookups would keep serving a deleted item otherwise.
+async fn ensure_signed_playable(
+    state: &AppState,
+    id: Uuid,
+    query: &PlaybackQuery,
+) -> Result<(), ApiError> {
+    if !query.is_signed() {
+        return Ok(());
+    }
+
+    state
+        .media_repository
+        .get_s3_key(id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    Ok(())
+}
+
+/// Sign a playback URL query for the caller
+fn sign_playback(
+    state: &AppState,
+    id: Uuid,
+    rendition: &str,
+    access: &PlaybackAccess,
+    expires: i64,
+) -> String {
+    state.playback.sign(&PlaybackGrant {
+        media_id: id,
+        rendition: rendition.to_string(),
+        user_id: access.user_id,
+        ip: access.ip,
+        expires,
+    })
+}
+
+/// Stream a media item from storage
+///
+/// Accepts a bearer token or a signed playback URL.
+pub async fn stream_media_item(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(id): Path<Uuid>,
+    Query(playback): Query<PlaybackQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    authorize_playback(
+        &state,
+        id,
+        ORIGINAL_RENDITION,
+        &playback,
+        addr.ip(),
+        &headers,
+    )
+    .await?;
+
+    let s3_key = state
+        .media_repository
+        .get_s3_key(id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    serve_object(&state, &s3_key, &headers).await
+}
+
+/// Get the HLS master playlist of a media item
+///
+/// Lists every rendition with its bandwidth, resolution and codecs; variant
+/// URLs are signed for the caller.
+pub async fn get_hls_master(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(id): Path<Uuid>,
+    Query(playback): Query<PlaybackQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let access =
+        authorize_playback(&state, id, HLS_RENDITION, &playback, addr.ip(), &headers).await?;
+    ensure_signed_playable(&state, id, &playback).await?;
+
+    let renditions = state.hls.renditions(id).await.map_err(|e| {
+        tracing::error!("Failed to get renditions: {}", e);
+        ApiError::InternalServerError
+    })?;
+    if renditions.is_empty() {
+        return Err(ApiError::NotFound("No renditions available".to_string()));
+    }
+
+    let expires = chrono::Utc::now().timestamp() + state.playback.config().url_expiry as i64;
+    let playlist = master_playlist(&renditions, |rendition| {
+        sign_playback(&state, id, &rendition.name, &access, expires)
+    });
+
+    Ok(playlist_response(playlist))
+}
+
+/// Get the playlist of one rendition of a media item
+///
+/// Segment URLs are signed for the caller and stay valid long enough to play
+/// the whole rendition.
+pub async fn get_hls_variant(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path((id, rendition)): Path<(Uuid, String)>,
+    Query(playback): Query<PlaybackQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let access = authorize_playback(&state, id, &rendition, &playback, addr.ip(), &headers).await?;
+    ensure_signed_playable(&state, id, &playback).await?;
+
+    let renditions = state.hls.renditions(id).await.map_err(|e| {
+        tracing::error!("Failed to get renditions: {}", e);
+        ApiError::InternalServerError
+    })?;
+    let rendition = renditions
+        .iter()
+        .find(|candidate| candidate.name == rendition)
+        .ok_or(ApiError::NotFound("Rendition not found".to_string()))?;
+
+    let segments = state.hls.segments(id, &rendition.name).await.map_err(|e| {
+        tracing::error!("Failed to get rendition segments: {}", e);
+        ApiError::InternalServerError
+    })?;
+
+    let expires = chrono::Utc::now().timestamp()
+        + state.playback.config().url_expiry as i64
+        + total_duration(&segments).ceil() as i64;
+    let query = sign_playback(&state, id, &rendition.name, &access, expires);
+
+    Ok(playlist_response(variant_playlist(
+        rendition, &segments, &query,
+    )))
+}
+
+/// Stream one segment of a rendition
+pub async fn get_hls_segment(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path((id, rendition, segment)): Path<(Uuid, String, String)>,
+    Query(playback): Query<PlaybackQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    authorize_playback(&state, id, &rendition, &playback, addr.ip(), &headers).await?;
+    ensure_signed_playable(&state, id, &playback).await?;
+
+    let sequence =
+        parse_segment_name(&segment).ok_or(ApiError::NotFound("Segment not found".to_string()))?;
+
+    let segments = state.hls.segments(id, &rendition).await.map_err(|e| {
+        tracing::error!("Failed to get rendition segments: {}", e);
+        ApiError::InternalServerError
+    })?;
+    let segment = segments
+        .iter()
+        .find(|segment| segment.sequence == sequence)
+        .ok_or(ApiError::NotFound("Segment not found".to_string()))?;
+
+    serve_object(&state, &segment.s3_key, &headers).await
+}
+
+/// Build an HLS playlist response
+fn playlist_response(playlist: String) -> Response {
+    let mut response = playlist.into_response();
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(PLAYLIST_CONTENT_TYPE),
+    );
+    // Playlists embed signatures for the caller and must not be shared
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("private, no-cache"),
+    );
+    response
+}
+
+/// Proxy an object from storage
+///
+/// Supports single byte ranges (`Range`, `If-Range`) for seeking, and
+/// `If-None-Match` revalidation. The object is streamed chunk by chunk.
+async fn serve_object(
+    state: &AppState,
+    s3_key: &str,
+    headers: &HeaderMap,
+) -> Result<Response, ApiError> {
+    let object = state
+        .storage
+        .head_object(s3_key)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media object: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media file not found".to_string()))?;
+
+    let size = object.size.max(0) as u64;
+    let etag = object.etag.as_deref();
+    let header_str = |name: header::HeaderName| headers.get(name).and_then(|v| v.to_str().ok());
+
+    let mut response = Response::new(Body::empty());
+    let response_headers = response.headers_mut();
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    response_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("private"));
+    if let Some(etag) = etag {
+        insert_header(response_headers, header::ETAG, etag.to_string());
+    }
+    if let Some(last_modified) = object.last_modified {
+        insert_header(
+            response_headers,
+            header::LAST_MODIFIED,
+            last_modified
+                .format("%a, %d %b %Y %H:%M:%S GMT")
+                .to_string(),
+        );
+    }
+
+    if header_str(header::IF_NONE_MATCH).is_some_and(|value| etag_matches(value, etag)) {
+        *response.status_mut() = StatusCode::NOT_MODIFIED;
+        return Ok(response);
+    }
+
+    let range = match header_str(header::RANGE) {
+        Some(range)
+            if header_str(header::IF_RANGE)
+                .is_none_or(|value| if_range_matches(value, etag, object.last_modified)) =>
+        {
+            parse_range(range, size)
+        }
+        _ => ByteRange::Full,
+    };
+
+    let (status, range, length) = match range {
+        ByteRange::Full => (StatusCode::OK, None, size),
+        ByteRange::Partial { start, end } => (
+            StatusCode::PARTIAL_CONTENT,
+            Some((start, end)),
+            end - start + 1,
+        ),
+        ByteRange::Unsatisfiable => {
+            *response.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+            insert_header(
+                response.headers_mut(),
+                header::CONTENT_RANGE,
+                format!("bytes */{}", size),
+            );
+            return Ok(response);
+        }
+    };
+
+    let body = state.storage.get_object(s3_key, range).await.map_err(|e| {
+        tracing::error!("Failed to stream media object: {}", e);
+        ApiError::InternalServerError
+    })?;
+
+    let response_headers = response.headers_mut();
+    insert_header(
+        response_headers,
+        header::CONTENT_TYPE,
+        content_type_for(object.content_type.as_deref(), s3_key),
+    );
+    response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(length));
+    if let Some((start, end)) = range {
+        insert_header(
+            response_headers,
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, end, size),
+        );
+    }
+
+    *response.status_mut() = status;
+    *response.body_mut() =
+        Body::from_stream(futures_util::stream::unfold(body, |mut body| async move {
+            body.next().await.map(|chunk| (chunk, body))
+        }));
+
+    Ok(response)
+}
+
+/// Report the playback position of a media item
+///
+/// Reports are throttled per item; the item is marked finished once the
+/// position passes `PROGRESS_FINISHED_THRESHOLD` of its duration.
+pub async fn update_progress(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ProgressUpdateRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if !payload.position.is_finite() || payload.position < 0.0 {
+        return Err(ApiError::BadRequest(
+            "Position must be a non-negative number of seconds".to_string(),
+        ));
+    }
+
+    let media_item = state
+        .media_repository
+        .get_by_id(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    let position = media_item
+        .duration
+        .map_or(payload.position, |duration| payload.position.min(duration));
+    let finished = state
+        .progress_repository
+        .config()
+        .is_finished(position, media_item.duration);
+
+    state
+        .progress_repository
+        .record(&user, id, position, finished)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to record playback progress: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    let progress = state
+        .progress_repository
+        .get_for_media(&user, &[id])
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get playback progress: {}", e);
+            ApiError::InternalServerError
+        })?
+        .pop()
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    Ok(Json(progress))
+}
+
+/// Get the playback progress of several media items
+///
+/// Items the user cannot see, deleted items and items above the user's
+/// maturity limit are left out, so their progress does not leak.
+pub async fn get_progress(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<ProgressQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let ids = query.ids().map_err(ApiError::BadRequest)?;
+
+    let progress = state
+        .progress_repository
+        .get_for_media(&user, &ids)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get playback progress: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(progress))
+}
+
+/// Get the partially watched items of the user, most recent first
+pub async fn get_continue_watching(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<ContinueWatchingQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    let items = state
+        .progress_repository
+        .continue_watching(&user, limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get continue watching: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(items))
+}
+
+/// Record a viewing session in the user's watch history
+///
+/// Nothing is recorded while the user has paused their history.
+pub async fn record_history(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(payload): Json<NewHistoryEntry>,
+) -> Result<Response, ApiError> {
+    payload.validate().map_err(ApiError::BadRequest)?;
+
+    if history_paused(&state, user.id).await? {
+        return Ok(StatusCode::NO_CONTENT.into_response());
+    }
+
+    state
+        .media_repository
+        .get_by_id(payload.media_id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    let entry = state
+        .history_repository
+        .append(&user, &payload)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to record watch history: {}", e);
             ApiError::InternalServerError
         })?;
 
+    Ok((StatusCode::CREATED, Json(entry)).into_response())
+}
+
+/// List the user's watch history, most recent first
+pub async fn get_history(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<impl IntoResponse, ApiError> {
     let page = query.page.unwrap_or(1).max(1);
-    let limit = query.limit.unwrap_or(10).min(100).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    let (items, total) = state
+        .history_repository
+        .list(&user, page, limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get watch history: {}", e);
+            ApiError::InternalServerError
+        })?;
 
-    let response = MediaListResponse {
+    Ok(Json(HistoryListResponse {
         items,
         page,
         limit,
         total,
-    };
+    }))
+}
 
-    Ok(Json(response))
+/// Delete one entry of the user's watch history
+pub async fn delete_history_entry(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
+    let deleted = state
+        .history_repository
+        .delete(&user, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete watch history entry: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    if !deleted {
+        return Err(ApiError::NotFound("History entry not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
-/// Refresh media library or specific media item
-pub async fn refresh_media(
-    State(_state): State<AppState>,
-    Json(payload): Json<MediaRefreshRequest>,
+/// Clear the user's entire watch history
+pub async fn clear_history(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
+    let deleted = state.history_repository.clear(&user).await.map_err(|e| {
+        tracing::error!("Failed to clear watch history: {}", e);
+        ApiError::InternalServerError
+    })?;
+
+    Ok(Json(json!({ "deleted": deleted })))
+}
+
+/// Get the user's watch history settings
+pub async fn get_history_settings(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, ApiError> {
+    Ok(Json(HistorySettings {
+        paused: history_paused(&state, user.id).await?,
+    }))
+}
+
+/// Pause or resume recording of the user's watch history
+pub async fn update_history_settings(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(payload): Json<HistorySettings>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
+    state
+        .user_repository
+        .set_setting(user.id, HISTORY_PAUSED_SETTING, &json!(payload.paused))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update history settings: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(payload))
+}
+
+/// Check whether the user has paused their watch history
+async fn history_paused(state: &AppState, user_id: Uuid) -> Result<bool, ApiError> {
+    let paused = state
+        .user_repository
+        .get_setting(user_id, HISTORY_PAUSED_SETTING)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get user settings: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(paused.and_then(|paused| paused.as_bool()).unwrap_or(false))
+}
+
+/// List the viewer profiles of the user's account
+pub async fn get_profiles(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, ApiError> {
+    let profiles = state.profile_repository.list(user.id).await.map_err(|e| {
+        tracing::error!("Failed to get profiles: {}", e);
+        ApiError::InternalServerError
+    })?;
+
+    Ok(Json(profiles))
+}
+
+/// Create a viewer profile
+///
+/// Only tokens of the account itself, without a selected profile, may
+/// create profiles. The parental control PIN is required if one is set.
+pub async fn create_profile(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(payload): Json<CreateProfileRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    if user.profile_id.is_some() {
+        return Err(ApiError::Forbidden);
+    }
+    let fields = payload.validate().map_err(ApiError::BadRequest)?;
+    check_pin(&state, user.id, payload.pin.as_deref()).await?;
+
+    let profile = state
+        .profile_repository
+        .create(user.id, &fields)
+        .await
+        .map_err(profile_error)?;
+
+    Ok((StatusCode::CREATED, Json(profile)))
+}
+
+/// Edit a viewer profile
+///
+/// A profile token may only edit its own profile, and never its maturity
+/// level. Changing the maturity level requires the parental control PIN if
+/// one is set.
+pub async fn update_profile(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateProfileRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    if user.profile_id.is_some_and(|profile_id| profile_id != id) {
+        return Err(ApiError::Forbidden);
+    }
+
+    let profile = state
+        .profile_repository
+        .get(id, user.id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get profile: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Profile not found".to_string()))?;
+
+    let fields = payload.apply(&profile).map_err(ApiError::BadRequest)?;
+    if fields.maturity_level != profile.maturity_level {
+        if user.profile_id.is_some() {
+            return Err(ApiError::Forbidden);
+        }
+        check_pin(&state, user.id, payload.pin.as_deref()).await?;
+    }
+
+    let profile = state
+        .profile_repository
+        .update(id, user.id, &fields)
+        .await
+        .map_err(profile_error)?
+        .ok_or(ApiError::NotFound("Profile not found".to_string()))?;
+
+    Ok(Json(profile))
+}
+
+/// Delete a viewer profile with its progress, history and collections
+///
+/// Only tokens of the account itself may delete profiles.
+pub async fn delete_profile(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    if user.profile_id.is_some() {
+        return Err(ApiError::Forbidden);
+    }
+
+    let deleted = state
+        .profile_repository
+        .delete(id, user.id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete profile: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    if !deleted {
+        return Err(ApiError::NotFound("Profile not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Map a profile write error to its response
+fn profile_error(error: ProfileError) -> ApiError {
+    match error {
+        ProfileError::LimitReached => ApiError::BadRequest(format!(
+            "An account can have at most {} profiles",
+            MAX_PROFILES
+        )),
+        ProfileError::NameTaken => {
+            ApiError::BadRequest("A profile with this name already exists".to_string())
+        }
+        ProfileError::Database(e) => {
+            tracing::error!("Failed to save profile: {}", e);
+            ApiError::InternalServerError
+        }
+    }
+}
+
+/// Get the parental controls of the user's account
+pub async fn get_parental_controls(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, ApiError> {
+    let controls = state.parental_repository.get(user.id).await.map_err(|e| {
+        tracing::error!("Failed to get parental controls: {}", e);
+        ApiError::InternalServerError
+    })?;
+
+    Ok(Json(controls))
+}
+
+/// Change the maximum maturity rating of the account's own tokens
+///
+/// Only tokens of the account itself may change it, with the parental
+/// control PIN if one is set.
+pub async fn update_parental_controls(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(payload): Json<UpdateParentalControlsRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    if user.profile_id.is_some() {
+        return Err(ApiError::Forbidden);
+    }
+    check_pin(&state, user.id, payload.pin.as_deref()).await?;
+
+    state
+        .parental_repository
+        .set_max_rating(user.id, payload.max_rating)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update parental controls: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    get_parental_controls(State(state), Extension(user)).await
+}
+
+/// Set, change or remove the parental control PIN
+///
+/// Only tokens of the account itself may change it, with the current PIN
+/// if one is set.
+pub async fn update_parental_pin(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(payload): Json<UpdatePinRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    if user.profile_id.is_some() {
+        return Err(ApiError::Forbidden);
+    }
+    if let Some(pin) = &payload.new_pin {
+        validate_pin(pin).map_err(ApiError::BadRequest)?;
+    }
+    check_pin(&state, user.id, payload.current_pin.as_deref()).await?;
+
+    let pin_hash = payload
+        .new_pin
+        .as_deref()
+        .map(hash_pin)
+        .transpose()
+        .map_err(|e| {
+            tracing::error!("Failed to hash PIN: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    state
+        .parental_repository
+        .set_pin_hash(user.id, pin_hash.as_deref())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update parental control PIN: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    get_parental_controls(State(state), Extension(user)).await
+}
+
+/// Check the parental control PIN of the user's account, if one is set
+async fn check_pin(state: &AppState, user_id: Uuid, pin: Option<&str>) -> Result<(), ApiError> {
+    state
+        .parental_repository
+        .check_pin(user_id, pin)
+        .await
+        .map_err(|error| match error {
+            PinError::Required => ApiError::BadRequest("PIN required".to_string()),
+            PinError::Invalid => ApiError::Forbidden,
+            PinError::Locked => {
+                ApiError::TooManyRequests("Too many wrong PINs, try again later".to_string())
+            }
+            PinError::Database(e) => {
+                tracing::error!("Failed to check PIN: {}", e);
+                ApiError::InternalServerError
+            }
+            PinError::Hash(e) => {
+                tracing::error!("Failed to check PIN: {}", e);
+                ApiError::InternalServerError
+            }
+        })
+}
+
+/// List the user's collections, creating the built-in ones on first use
+pub async fn get_collections(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, ApiError> {
+    state
+        .collection_repository
+        .ensure_builtin(&user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create built-in collections: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    let collections = state.collection_repository.list(&user).await.map_err(|e| {
+        tracing::error!("Failed to get collections: {}", e);
+        ApiError::InternalServerError
+    })?;
+
+    Ok(Json(collections))
+}
+
+/// Create a custom collection
+pub async fn create_collection(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(payload): Json<CollectionNameRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    let name = payload.name().map_err(ApiError::BadRequest)?;
+
+    let collection = state
+        .collection_repository
+        .create(&user, &name)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create collection: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok((StatusCode::CREATED, Json(collection)))
+}
+
+/// Get one of the user's collections with its items in order
+pub async fn get_collection(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    let collection = find_collection(&state, id, &user).await?;
+
+    let items = state
+        .collection_repository
+        .items(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get collection items: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(CollectionDetail { collection, items }))
+}
+
+/// Rename a custom collection
+pub async fn update_collection(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<CollectionNameRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    let name = payload.name().map_err(ApiError::BadRequest)?;
+
+    let collection = find_collection(&state, id, &user).await?;
+    if !collection.kind.is_custom() {
+        return Err(ApiError::BadRequest(
+            "Built-in collections cannot be renamed".to_string(),
+        ));
+    }
+
+    state
+        .collection_repository
+        .rename(id, &user, &name)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to rename collection: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(find_collection(&state, id, &user).await?))
+}
+
+/// Delete a custom collection
+pub async fn delete_collection(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
+    let collection = find_collection(&state, id, &user).await?;
+    if !collection.kind.is_custom() {
+        return Err(ApiError::BadRequest(
+            "Built-in collections cannot be deleted".to_string(),
+        ));
+    }
+
+    state
+        .collection_repository
+        .delete(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete collection: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Add a media item to a collection
+///
+/// Responds `201 Created` when the item was added and `200 OK` when it was
+/// already in the collection.
+pub async fn add_collection_item(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<AddCollectionItemRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    find_collection(&state, id, &user).await?;
+
+    state
+        .media_repository
+        .get_by_id(payload.media_id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    let added = state
+        .collection_repository
+        .add_item(id, payload.media_id, payload.position)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to add collection item: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    // Adding an item that is already in the collection leaves it in place
+    Ok(if added {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    })
+}
+
+/// Remove a media item from a collection
+pub async fn remove_collection_item(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path((id, media_id)): Path<(Uuid, Uuid)>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    find_collection(&state, id, &user).await?;
+
+    let removed = state
+        .collection_repository
+        .remove_item(id, media_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to remove collection item: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    if !removed {
+        return Err(ApiError::NotFound(
+            "Media item is not in the collection".to_string(),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Put the items of a collection in a new order
+pub async fn reorder_collection(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ReorderCollectionRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    find_collection(&state, id, &user).await?;
+
+    let reordered = state
+        .collection_repository
+        .reorder(id, &payload.media_ids)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to reorder collection: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    if !reordered {
+        return Err(ApiError::BadRequest(
+            "media_ids must list every item of the collection exactly once".to_string(),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Create a share link for a collection
+///
+/// Sharing an already shared collection returns its existing link.
+pub async fn share_collection(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
+    let share_token = state
+        .collection_repository
+        .share(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to share collection: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Collection not found".to_string()))?;
+
+    Ok(Json(ShareCollectionResponse {
+        url: format!("/collections/shared/{}", share_token),
+        share_token,
+    }))
+}
+
+/// Revoke the share link of a collection
+pub async fn unshare_collection(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    find_collection(&state, id, &user).await?;
+
+    state
+        .collection_repository
+        .unshare(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to unshare collection: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get a collection through its share link
+///
+/// Only the public and unlisted items of the collection are shown.
+pub async fn get_shared_collection(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut collection = state
+        .collection_repository
+        .get_shared(&token)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get shared collection: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Collection not found".to_string()))?;
+
+    let items = state
+        .collection_repository
+        .shared_items(collection.id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get shared collection items: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    collection.share_token = None;
+    collection.item_count = items.len() as i64;
+
+    Ok(Json(CollectionDetail { collection, items }))
+}
+
+/// Find one of the user's collections
+async fn find_collection(
+    state: &AppState,
+    id: Uuid,
+    user: &AuthUser,
+) -> Result<Collection, ApiError> {
+    state
+        .collection_repository
+        .get(id, user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get collection: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Collection not found".to_string()))
+}
+
+/// List series with at least one episode visible to the user
+pub async fn get_series_list(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<SeriesQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    let (items, total) = state
+        .series_repository
+        .list(&user, page, limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get series: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(SeriesListResponse {
+        items,
+        page,
+        limit,
+        total,
+    }))
+}
+
+/// Get a series with its seasons and the user's watched counts
+pub async fn get_series(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    let series = state
+        .series_repository
+        .get(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get series: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Series not found".to_string()))?;
+
+    let seasons = state
+        .series_repository
+        .seasons(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get seasons: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(SeriesDetail::new(series, seasons)))
+}
+
+/// List the episodes of a season in order
+pub async fn get_season(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path((id, number)): Path<(Uuid, i32)>,
+) -> Result<impl IntoResponse, ApiError> {
+    let episodes = state
+        .series_repository
+        .episodes(id, number, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get episodes: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    if episodes.is_empty() {
+        return Err(ApiError::NotFound("Season not found".to_string()));
+    }
+
+    Ok(Json(SeasonDetail {
+        series_id: id,
+        number,
+        episodes,
+    }))
+}
+
+/// Get the episode to autoplay after an episode
+pub async fn get_next_episode(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    state
+        .media_repository
+        .get_by_id(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    let next = state
+        .series_repository
+        .next_episode(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get next episode: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("No next episode".to_string()))?;
+
+    Ok(Json(next))
+}
+
+/// List the items most similar to a media item
+pub async fn get_similar_media(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<RecommendationQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+
+    state
+        .media_repository
+        .get_by_id(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    let items = state
+        .recommender
+        .similar(id, &user, limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get similar media items: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(items))
+}
+
+/// Personalized recommendations, falling back to popular items
+pub async fn get_recommendations(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<RecommendationQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let limit = query.limit.unwrap_or(20).clamp(1, 50);
+
+    let items = state
+        .recommender
+        .for_user(&user, limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get recommendations: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(items))
+}
+
+/// List all genres
+pub async fn get_genres(State(state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
+    let genres = state.taxonomy_repository.list_genres().await.map_err(|e| {
+        tracing::error!("Failed to get genres: {}", e);
+        ApiError::InternalServerError
+    })?;
+
+    Ok(Json(genres))
+}
+
+/// Create a genre (admin only)
+pub async fn create_genre(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(payload): Json<GenreRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_admin()?;
+    user.ensure_not_impersonated()?;
+    let (name, slug) = payload.name_and_slug().map_err(ApiError::BadRequest)?;
+
+    let genre = state
+        .taxonomy_repository
+        .create_genre(&name, &slug)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create genre: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or_else(|| ApiError::BadRequest(format!("Genre already exists: {}", slug)))?;
+
+    Ok((StatusCode::CREATED, Json(genre)))
+}
+
+/// Rename a genre (admin only)
+pub async fn update_genre(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<GenreRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_admin()?;
+    user.ensure_not_impersonated()?;
+    let (name, slug) = payload.name_and_slug().map_err(ApiError::BadRequest)?;
+
+    let genre = state
+        .taxonomy_repository
+        .rename_genre(id, &name, &slug)
+        .await
+        .map_err(|e| match e {
+            RenameGenreError::SlugTaken => {
+                ApiError::BadRequest(format!("Genre already exists: {}", slug))
+            }
+            RenameGenreError::Database(e) => {
+                tracing::error!("Failed to rename genre: {}", e);
+                ApiError::InternalServerError
+            }
+        })?
+        .ok_or(ApiError::NotFound("Genre not found".to_string()))?;
+
+    Ok(Json(genre))
+}
+
+/// Delete a genre, removing it from all media items (admin only)
+pub async fn delete_genre(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_admin()?;
+    user.ensure_not_impersonated()?;
+
+    let deleted = state
+        .taxonomy_repository
+        .delete_genre(id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete genre: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    if !deleted {
+        return Err(ApiError::NotFound("Genre not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get the genres of a media item
+pub async fn get_media_genres(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    state
+        .media_repository
+        .get_by_id(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    let genres = state
+        .taxonomy_repository
+        .media_genres(id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media genres: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(genres))
+}
+
+/// Replace the genres of a media item
+pub async fn update_media_genres(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<MediaGenresRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
+    let mut genre_ids = payload.genre_ids;
+    genre_ids.sort();
+    genre_ids.dedup();
+
+    let genres = state
+        .taxonomy_repository
+        .find_genres(&genre_ids)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get genres: {}", e);
+            ApiError::InternalServerError
+        })?;
+    if genres.len() != genre_ids.len() {
+        return Err(ApiError::BadRequest("Unknown genre".to_string()));
+    }
+
+    let updated = state
+        .taxonomy_repository
+        .set_media_genres(id, &user, &genre_ids)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to set media genres: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    if !updated {
+        return Err(ApiError::NotFound("Media item not found".to_string()));
+    }
+
+    Ok(Json(genres))
+}
+
+/// Suggest tags for autocomplete
+pub async fn suggest_tags(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<TagSuggestQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let limit = query.limit.unwrap_or(10).clamp(1, 20);
+
+    let suggestions = state
+        .taxonomy_repository
+        .suggest_tags(&query.q, limit, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get tag suggestions: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(suggestions))
+}
+
+/// Get the user's own rating and review of a media item
+pub async fn get_own_review(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    let review = state
+        .review_repository
+        .get_own(user.id, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get review: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Review not found".to_string()))?;
+
+    Ok(Json(review))
+}
+
+/// Rate and optionally review a media item, replacing any earlier review
+pub async fn put_own_review(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ReviewRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    let body = payload.validate().map_err(ApiError::BadRequest)?;
+
+    state
+        .media_repository
+        .get_by_id(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    let review = state
+        .review_repository
+        .upsert(user.id, id, payload.rating, body.as_deref())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to save review: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(review))
+}
+
+/// Delete the user's own rating and review of a media item
+pub async fn delete_own_review(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
+    let review = state
+        .review_repository
+        .get_own(user.id, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get review: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Review not found".to_string()))?;
+
+    delete_review_by_id(&state, review.id).await
+}
+
+/// List the reviews of a media item together with its rating aggregates
+pub async fn get_media_reviews(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ReviewQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    state
+        .media_repository
+        .get_by_id(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    let (items, total) = state
+        .review_repository
+        .list_for_media(id, &user, page, limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get reviews: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    let rating = state
+        .review_repository
+        .rating_summary(id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get rating summary: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(ReviewListResponse {
+        items,
+        page,
+        limit,
+        total,
+        rating,
+    }))
+}
+
+/// Report a review for abuse
+pub async fn report_review(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ReportReviewRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    payload.validate().map_err(ApiError::BadRequest)?;
+
+    let review = state
+        .review_repository
+        .get(id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get review: {}", e);
+            ApiError::InternalServerError
+        })?
+        .filter(|review| review.status == ReviewStatus::Published || review.user_id == user.id)
+        .ok_or(ApiError::NotFound("Review not found".to_string()))?;
+
+    state
+        .media_repository
+        .get_by_id(review.media_id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Review not found".to_string()))?;
+
+    if review.user_id == user.id {
+        return Err(ApiError::BadRequest(
+            "Cannot report your own review".to_string(),
+        ));
+    }
+
+    let created = state
+        .review_repository
+        .report(id, user.id, payload.reason, payload.details.as_deref())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to report review: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(if created {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    })
+}
+
+/// List reviews awaiting moderation, flagged ones by default (admin only)
+pub async fn get_moderation_queue(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<ModerationQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_admin()?;
+    let status = query.status.unwrap_or(ReviewStatus::Flagged);
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    let (items, total) = state
+        .review_repository
+        .moderation_queue(status, page, limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get moderation queue: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(ModerationListResponse {
+        items,
+        page,
+        limit,
+        total,
+    }))
+}
+
+/// Publish or hide a review, resolving its open reports (admin only)
+pub async fn moderate_review(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ModerateReviewRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_admin()?;
+    user.ensure_not_impersonated()?;
+
+    if payload.status == ReviewStatus::Flagged {
+        return Err(ApiError::BadRequest(
+            "Reviews can only be published or hidden".to_string(),
+        ));
+    }
+
+    let review = state
+        .review_repository
+        .moderate(id, payload.status)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to moderate review: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Review not found".to_string()))?;
+
+    Ok(Json(review))
+}
+
+/// Delete any review (admin only)
+pub async fn delete_review(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_admin()?;
+    user.ensure_not_impersonated()?;
+
+    delete_review_by_id(&state, id).await
+}
+
+/// Delete a review and refresh the rating aggregates of its media item
+async fn delete_review_by_id(state: &AppState, id: Uuid) -> Result<StatusCode, ApiError> {
+    let deleted = state.review_repository.delete(id).await.map_err(|e| {
+        tracing::error!("Failed to delete review: {}", e);
+        ApiError::InternalServerError
+    })?;
+
+    if !deleted {
+        return Err(ApiError::NotFound("Review not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List the top-level comments of a media item, newest first
+pub async fn get_media_comments(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<CommentQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let cursor = query.cursor().map_err(ApiError::BadRequest)?;
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    state
+        .media_repository
+        .get_by_id(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    let (items, next_cursor) = state
+        .comment_repository
+        .list_thread(id, None, &user, cursor.as_ref(), limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get comments: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(CommentListResponse {
+        items,
+        limit,
+        next_cursor: next_cursor.map(|cursor| cursor.encode()),
+    }))
+}
+
+/// Comment on a media item or reply to a comment
+pub async fn create_comment(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<CreateCommentRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
+    let item = state
+        .media_repository
+        .get_by_id(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+    let body = payload
+        .validate(item.duration)
+        .map_err(ApiError::BadRequest)?;
+
+    if let Some(parent_id) = payload.parent_id {
+        let parent = find_comment(&state, parent_id, &user).await?;
+        if parent.media_id != id || parent.deleted_at.is_some() {
+            return Err(ApiError::BadRequest(
+                "Cannot reply to this comment".to_string(),
+            ));
+        }
+    }
+
+    let comment = state
+        .comment_repository
+        .create(id, user.id, payload.parent_id, payload.timestamp, &body)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create comment: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+/// Subscribe to the comment changes of a media item as server-sent events
+pub async fn subscribe_media_comments(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    state
+        .media_repository
+        .get_by_id(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    let receiver = state.comment_feed.subscribe();
+    let events = stream::unfold(receiver, move |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) if event.comment.media_id == id => {
+                    let sse = Event::default()
+                        .event(event.kind.as_str())
+                        .json_data(&event.comment);
+                    return Some((sse, receiver));
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// Get a comment
+pub async fn get_comment(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    let comment = find_comment(&state, id, &user).await?;
+
+    Ok(Json(comment))
+}
+
+/// List the replies to a comment, oldest first
+pub async fn get_comment_replies(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<CommentQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let cursor = query.cursor().map_err(ApiError::BadRequest)?;
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let comment = find_comment(&state, id, &user).await?;
+
+    let (items, next_cursor) = state
+        .comment_repository
+        .list_thread(comment.media_id, Some(id), &user, cursor.as_ref(), limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get comment replies: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(CommentListResponse {
+        items,
+        limit,
+        next_cursor: next_cursor.map(|cursor| cursor.encode()),
+    }))
+}
+
+/// Edit the text of one of the user's comments
+pub async fn update_comment(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateCommentRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    let body = validate_comment_body(&payload.body).map_err(ApiError::BadRequest)?;
+
+    let comment = find_comment(&state, id, &user).await?;
+    if comment.user_id != user.id {
+        return Err(ApiError::Forbidden);
+    }
+
+    let comment = state
+        .comment_repository
+        .update(id, &body)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update comment: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Comment not found".to_string()))?;
+
+    Ok(Json(comment))
+}
+
+/// Delete a comment (author or admin)
+///
+/// Replies stay in place; the deleted comment is listed without its text
+/// while it has replies.
+pub async fn delete_comment(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
+    let comment = find_comment(&state, id, &user).await?;
+    if comment.user_id != user.id && !user.is_admin() {
+        return Err(ApiError::Forbidden);
+    }
+
+    let deleted = state.comment_repository.delete(id).await.map_err(|e| {
+        tracing::error!("Failed to delete comment: {}", e);
+        ApiError::InternalServerError
+    })?;
+
+    if !deleted {
+        return Err(ApiError::NotFound("Comment not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Hide or republish a comment (admin only)
+pub async fn moderate_comment(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ModerateCommentRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_admin()?;
+    user.ensure_not_impersonated()?;
+
+    let comment = state
+        .comment_repository
+        .moderate(id, payload.status)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to moderate comment: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Comment not found".to_string()))?;
+
+    Ok(Json(comment))
+}
+
+/// List comments across all media items by moderation state, hidden ones by default (admin only)
+pub async fn get_admin_comments(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<CommentQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_admin()?;
+    let cursor = query.cursor().map_err(ApiError::BadRequest)?;
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let status = query.status.unwrap_or(CommentStatus::Hidden);
+
+    let (items, next_cursor) = state
+        .comment_repository
+        .list_by_status(status, cursor.as_ref(), limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get comments: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(CommentListResponse {
+        items,
+        limit,
+        next_cursor: next_cursor.map(|cursor| cursor.encode()),
+    }))
+}
+
+/// Find a comment the user may see on a media item the user may see
+async fn find_comment(state: &AppState, id: Uuid, user: &AuthUser) -> Result<Comment, ApiError> {
+    let comment = state
+        .comment_repository
+        .get(id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get comment: {}", e);
+            ApiError::InternalServerError
+        })?
+        .filter(|comment| {
+            comment.status == CommentStatus::Published
+                || comment.user_id == user.id
+                || user.is_admin()
+        })
+        .ok_or(ApiError::NotFound("Comment not found".to_string()))?;
+
+    state
+        .media_repository
+        .get_by_id(comment.media_id, user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Comment not found".to_string()))?;
+
+    Ok(comment)
+}
+
+/// List the user's notifications, newest first
+pub async fn get_notifications(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<NotificationQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    let (items, total, unread) = state
+        .notification_repository
+        .list(user.id, query.unread, page, limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get notifications: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(NotificationListResponse {
+        items,
+        page,
+        limit,
+        total,
+        unread,
+    }))
+}
+
+/// Mark one of the user's notifications as read
+pub async fn read_notification(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
+    let updated = state
+        .notification_repository
+        .mark_read(user.id, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to mark notification as read: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    if !updated {
+        return Err(ApiError::NotFound("Notification not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Mark all of the user's notifications as read
+pub async fn read_all_notifications(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
+    let updated = state
+        .notification_repository
+        .mark_all_read(user.id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to mark notifications as read: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(json!({ "updated": updated })))
+}
+
+/// Edit the metadata of a media item with JSON Merge Patch semantics
+pub async fn update_media_item(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(patch): Json<serde_json::Value>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    validate_metadata_patch(&patch).map_err(ApiError::BadRequest)?;
+
+    let media_item = state
+        .media_repository
+        .update_metadata(id, &user, &patch)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    Ok(Json(media_item))
+}
+
+/// Move a media item to the trash
+pub async fn delete_media_item(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
+    let deleted = state
+        .media_repository
+        .soft_delete(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete media item: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    if !deleted {
+        return Err(ApiError::NotFound("Media item not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Restore a media item from the trash
+pub async fn restore_media_item(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
+    let restored = state
+        .media_repository
+        .restore(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to restore media item: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    if !restored {
+        return Err(ApiError::NotFound(
+            "Media item not found in trash".to_string(),
+        ));
+    }
+
+    let media_item = state
+        .media_repository
+        .get_by_id(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    Ok(Json(media_item))
+}
+
+/// List the caller's deleted media items
+pub async fn get_trash(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<TrashQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(10).clamp(1, 100);
+
+    let (items, total) = state
+        .media_repository
+        .get_trash(&user, page, limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get trash: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(MediaListResponse {
+        items,
+        page: Some(page),
+        limit,
+        total: Some(total),
+        next_cursor: None,
+        prev_cursor: None,
+        facets: None,
+    }))
+}
+
+/// Change who can see a media item
+pub async fn update_media_visibility(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<MediaVisibilityRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
+    if payload.visibility != Visibility::Shared && !payload.shared_with.is_empty() {
+        return Err(ApiError::BadRequest(
+            "shared_with requires shared visibility".to_string(),
+        ));
+    }
+
+    let updated = state
+        .media_repository
+        .set_visibility(id, &user, payload.visibility, &payload.shared_with)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update media visibility: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    // Items the user cannot change are reported as missing
+    if !updated {
+        return Err(ApiError::NotFound("Media item not found".to_string()));
+    }
+
+    let media_item = state
+        .media_repository
+        .get_by_id(id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    Ok(Json(media_item))
+}
+
+/// Set or clear the maturity rating of a media item (admin only)
+///
+/// Ratings set here are never overwritten by sidecar metadata.
+pub async fn update_media_maturity_rating(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<MaturityRatingRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_admin()?;
+    user.ensure_not_impersonated()?;
+
+    let media_item = state
+        .media_repository
+        .set_maturity_rating(id, payload.maturity_rating)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update maturity rating: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    Ok(Json(media_item))
+}
+
+/// Get media items with pagination, sorting, and filtering
+pub async fn get_media_items(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<MediaQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    query.validate().map_err(ApiError::BadRequest)?;
+
+    let response = state
+        .media_repository
+        .get_media_items(&query, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media items: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(response))
+}
+
+/// Suggest media titles for search type-ahead
+pub async fn suggest_media(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<SuggestQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let limit = query.limit.unwrap_or(10).clamp(1, 20);
+
+    let suggestions = state
+        .media_repository
+        .suggest_titles(&query.q, limit, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get search suggestions: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(Json(SuggestResponse { suggestions }))
+}
+
+/// Start a direct-to-storage upload
+///
+/// Creates a media item owned by the caller and returns presigned URLs the
+/// file is uploaded to, using a multipart upload for large files.
+pub async fn create_upload(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(payload): Json<CreateUploadRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
+    let media_type = media_type_for(&payload.content_type).ok_or_else(|| {
+        ApiError::BadRequest("Only video, audio and image uploads are supported".to_string())
+    })?;
+
+    let config = state.storage.config();
+    if payload.size <= 0 || payload.size > config.max_upload_size {
+        return Err(ApiError::BadRequest(format!(
+            "Upload size must be between 1 and {} bytes",
+            config.max_upload_size
+        )));
+    }
+
+    let filename = sanitize_filename(&payload.filename);
+    let title = title_for(payload.title.as_deref(), &filename);
+
+    let media_id = Uuid::new_v4();
+    let mut upload = MediaUpload {
+        id: Uuid::new_v4(),
+        media_item_id: media_id,
+        user_id: user.id,
+        s3_key: format!("uploads/{}/{}/{}", user.id, media_id, filename),
+        content_type: payload.content_type.clone(),
+        size: payload.size,
+        multipart_upload_id: None,
+        expires_at: chrono::Utc::now() + chrono::Duration::seconds(config.url_expiry as i64),
+        completed_at: None,
+    };
+
+    let storage_error = |e: anyhow::Error| {
+        tracing::error!("Failed to prepare upload: {}", e);
+        ApiError::InternalServerError
+    };
+
+    let mut response = CreateUploadResponse {
+        upload_id: upload.id,
+        media_id,
+        method: "PUT",
+        url: None,
+        parts: Vec::new(),
+        part_size: None,
+        expires_at: upload.expires_at,
+    };
+
+    if payload.size > config.multipart_threshold {
+        let multipart_upload_id = state
+            .storage
+            .create_multipart_upload(&upload.s3_key, &upload.content_type)
+            .await
+            .map_err(storage_error)?;
+
+        let (part_size, part_count) = config.plan_parts(payload.size);
+        for part_number in 1..=part_count as i32 {
+            let url = state
+                .storage
+                .presign_upload_part(&upload.s3_key, &multipart_upload_id, part_number)
+                .await
+                .map_err(storage_error)?;
+            response.parts.push(UploadPartUrl { part_number, url });
+        }
+
+        response.part_size = Some(part_size);
+        upload.multipart_upload_id = Some(multipart_upload_id);
+    } else {
+        let url = state
+            .storage
+            .presign_put(&upload.s3_key, &upload.content_type)
+            .await
+            .map_err(storage_error)?;
+        response.url = Some(url);
+    }
+
+    let metadata = json!({
+        "title": title,
+        "original_filename": payload.filename,
+    });
+
+    state
+        .upload_repository
+        .create(&upload, media_type, &metadata)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create upload: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Complete a direct-to-storage upload
+///
+/// Verifies the uploaded object and hands the media item to the media
+/// service for processing.
+pub async fn complete_upload(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    payload: Option<Json<CompleteUploadRequest>>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+
+    let upload = state
+        .upload_repository
+        .find_for_user(id, user.id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get upload: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Upload not found".to_string()))?;
+
+    if upload.completed_at.is_some() {
+        return Err(ApiError::BadRequest("Upload already completed".to_string()));
+    }
+    if upload.expires_at < chrono::Utc::now() {
+        return Err(ApiError::BadRequest("Upload has expired".to_string()));
+    }
+
+    if let Some(multipart_upload_id) = &upload.multipart_upload_id {
+        if payload.parts.is_empty() {
+            return Err(ApiError::BadRequest(
+                "Parts are required to complete a multipart upload".to_string(),
+            ));
+        }
+
+        let parts: Vec<UploadedPart> = payload
+            .parts
+            .into_iter()
+            .map(|part| UploadedPart {
+                part_number: part.part_number,
+                etag: part.etag,
+            })
+            .collect();
+
+        state
+            .storage
+            .complete_multipart_upload(&upload.s3_key, multipart_upload_id, &parts)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to complete multipart upload: {}", e);
+                ApiError::BadRequest("Failed to complete multipart upload".to_string())
+            })?;
+    }
+
+    let size = state
+        .storage
+        .object_size(&upload.s3_key)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to verify uploaded object: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::BadRequest("Uploaded file not found".to_string()))?;
+
+    if size != upload.size {
+        // Any multipart upload was completed above, so only the object is left
+        state.storage.discard_upload(&upload.s3_key, None).await;
+        return Err(ApiError::BadRequest(format!(
+            "Uploaded file is {} bytes, expected {}",
+            size, upload.size
+        )));
+    }
+
+    state
+        .upload_repository
+        .mark_completed(&upload)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to complete upload: {}", e);
+            ApiError::InternalServerError
+        })?;
+
+    let media_item = state
+        .media_repository
+        .get_by_id(upload.media_item_id, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get media item: {}", e);
+            ApiError::InternalServerError
+        })?
+        .ok_or(ApiError::NotFound("Media item not found".to_string()))?;
+
+    Ok(Json(media_item))
+}
+
+/// Advertise the supported tus protocol version and extensions
+pub async fn tus_options(State(state): State<AppState>) -> Response {
+    let mut response = tus_response(StatusCode::NO_CONTENT);
+    let headers = response.headers_mut();
+    headers.insert("Tus-Version", HeaderValue::from_static(TUS_VERSION));
+    headers.insert("Tus-Extension", HeaderValue::from_static(TUS_EXTENSIONS));
+    headers.insert(
+        "Tus-Max-Size",
+        HeaderValue::from(state.storage.config().max_upload_size),
+    );
+    response
+}
+
+/// Start a resumable upload (tus creation extension)
+///
+/// `Upload-Metadata` must carry the `filetype` of the file and may carry its
+/// `filename` and a `title`.
+pub async fn create_tus_upload(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
+) -> Result<Response, TusError> {
+    check_version(&headers)?;
+    user.ensure_not_impersonated()
+        .map_err(|_| TusError::Forbidden)?;
+
+    if headers.contains_key("Upload-Defer-Length") {
+        return Err(TusError::BadRequest(
+            "Deferred upload length is not supported".to_string(),
+        ));
+    }
+
+    let upload_length = parse_length_header(&headers, "Upload-Length")?;
+    if upload_length == 0 {
+        return Err(TusError::BadRequest(
+            "Upload-Length must not be 0".to_string(),
+        ));
+    }
+    if upload_length > state.storage.config().max_upload_size {
+        return Err(TusError::TooLarge);
+    }
+
+    let metadata = headers
+        .get("Upload-Metadata")
+        .map(|value| {
+            value
+                .to_str()
+                .map_err(|_| "Invalid Upload-Metadata header".to_string())
+                .and_then(parse_upload_metadata)
+        })
+        .transpose()
+        .map_err(TusError::BadRequest)?
+        .unwrap_or_default();
+
+    if metadata
+        .get("filetype")
+        .and_then(|filetype| media_type_for(filetype))
+        .is_none()
+    {
+        return Err(TusError::BadRequest(
+            "The filetype metadata must be a video, audio or image type".to_string(),
+        ));
+    }
+
+    let id = Uuid::new_v4();
+    let filename = sanitize_filename(metadata.get("filename").map_or("upload", String::as_str));
+    let s3_key = format!("uploads/{}/{}/{}", user.id, id, filename);
+    let multipart_upload_id = state
+        .tus_store
+        .create(&s3_key, &metadata["filetype"])
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to start multipart upload: {}", e);
+            TusError::InternalServerError
+        })?;
+
+    let upload = TusUpload {
+        id,
+        user_id: user.id,
+        upload_length,
+        upload_offset: 0,
+        metadata: json!(metadata),
+        s3_key,
+        multipart_upload_id,
+        part_size: state.storage.config().plan_parts(upload_length).0,
+        media_item_id: None,
+        expires_at: chrono::Utc::now()
+            + chrono::Duration::seconds(state.tus_store.config().expiry as i64),
+        completed_at: None,
+    };
+
+    if let Err(e) = state.tus_repository.create(&upload).await {
+        tracing::error!("Failed to create resumable upload: {}", e);
+        state.tus_store.remove(&upload).await;
+        return Err(TusError::InternalServerError);
+    }
+
+    let mut response = tus_response(StatusCode::CREATED);
+    let headers = response.headers_mut();
+    insert_header(
+        headers,
+        header::LOCATION,
+        format!("/media/tus/{}", upload.id),
+    );
+    insert_header(headers, "Upload-Expires", format_expires(upload.expires_at));
+    Ok(response)
+}
+
+/// Get the current offset of a resumable upload
+pub async fn head_tus_upload(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response, TusError> {
+    check_version(&headers)?;
+
+    let upload = find_tus_upload(&state, id, &user).await?;
+
+    Ok(tus_upload_response(StatusCode::OK, &upload))
+}
+
+/// Append a chunk to a resumable upload
+///
+/// The chunk is streamed to object storage while the upload is locked, so
+/// concurrent `PATCH`es are refused on any API instance. Once the last byte
+/// has been received a media item is queued for processing; its ID is
+/// returned in the `X-Media-Id` header.
+pub async fn patch_tus_upload(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response, TusError> {
+    check_version(&headers)?;
+    user.ensure_not_impersonated()
+        .map_err(|_| TusError::Forbidden)?;
+
+    if headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        != Some(OFFSET_CONTENT_TYPE)
+    {
+        return Err(TusError::UnsupportedMediaType);
+    }
+
+    let offset = parse_length_header(&headers, "Upload-Offset")?;
+
+    let mut locked = lock_tus_upload(&state, id, &user).await?;
+    if locked.upload.upload_offset != offset {
+        return Err(TusError::OffsetMismatch);
+    }
+    if locked.upload.completed_at.is_some() || offset == locked.upload.upload_length {
+        return Ok(tus_upload_response(StatusCode::NO_CONTENT, &locked.upload));
+    }
+
+    let previous = locked.upload.clone();
+    let progress = state.tus_store.append(&previous, body).await?;
+
+    let save_error = |e: anyhow::Error| {
+        tracing::error!("Failed to save upload progress: {}", e);
+        TusError::InternalServerError
+    };
+    locked
+        .record_progress(progress.offset, &progress.parts)
+        .await
+        .map_err(save_error)?;
+
+    if progress.offset == locked.upload.upload_length {
+        finish_tus_upload(&state, &mut locked).await?;
+    }
+
+    let upload = locked.commit().await.map_err(save_error)?;
+    state.tus_store.remove_pending(&previous, offset).await;
+
+    Ok(tus_upload_response(StatusCode::NO_CONTENT, &upload))
+}
+
+/// Terminate an incomplete resumable upload
+pub async fn delete_tus_upload(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response, TusError> {
+    check_version(&headers)?;
+    user.ensure_not_impersonated()
+        .map_err(|_| TusError::Forbidden)?;
+
+    let mut locked = lock_tus_upload(&state, id, &user).await?;
+    if locked.upload.completed_at.is_some() {
+        return Err(TusError::BadRequest("Upload already completed".to_string()));
+    }
+
+    let delete_error = |e: anyhow::Error| {
+        tracing::error!("Failed to delete resumable upload: {}", e);
+        TusError::InternalServerError
+    };
+    locked.delete().await.map_err(delete_error)?;
+    let upload = locked.commit().await.map_err(delete_error)?;
+
+    state.tus_store.remove(&upload).await;
+
+    Ok(tus_response(StatusCode::NO_CONTENT))
+}
+
+/// Find a resumable upload of the user that has not expired
+async fn find_tus_upload(
+    state: &AppState,
+    id: Uuid,
+    user: &AuthUser,
+) -> Result<TusUpload, TusError> {
+    let upload = state
+        .tus_repository
+        .find_for_user(id, user.id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get resumable upload: {}", e);
+            TusError::InternalServerError
+        })?
+        .ok_or(TusError::NotFound)?;
+
+    check_tus_expiry(&upload)?;
+
+    Ok(upload)
+}
+
+/// Lock a resumable upload of the user that has not expired for writing
+async fn lock_tus_upload(
+    state: &AppState,
+    id: Uuid,
+    user: &AuthUser,
+) -> Result<LockedTusUpload, TusError> {
+    let locked = state
+        .tus_repository
+        .lock_for_user(id, user.id)
+        .await
+        .map_err(|e| match e {
+            TusLockError::Locked => TusError::Locked,
+            TusLockError::Database(e) => {
+                tracing::error!("Failed to lock resumable upload: {}", e);
+                TusError::InternalServerError
+            }
+        })?
+        .ok_or(TusError::NotFound)?;
+
+    check_tus_expiry(&locked.upload)?;
+
+    Ok(locked)
+}
+
+/// Refuse incomplete uploads past their expiry
+fn check_tus_expiry(upload: &TusUpload) -> Result<(), TusError> {
+    if upload.completed_at.is_none() && upload.expires_at < chrono::Utc::now() {
+        return Err(TusError::Gone);
+    }
+    Ok(())
+}
+
+/// Assemble a fully received upload in object storage and create its media
+/// item, saved when the lock is committed
+async fn finish_tus_upload(state: &AppState, locked: &mut LockedTusUpload) -> Result<(), TusError> {
+    let upload = &locked.upload;
+    let content_type = upload.metadata_value("filetype").unwrap_or_default();
+    let media_type = media_type_for(content_type).ok_or(TusError::BadRequest(
+        "The filetype metadata must be a video, audio or image type".to_string(),
+    ))?;
+
+    let original_filename = upload.metadata_value("filename").unwrap_or("upload");
+    let filename = sanitize_filename(original_filename);
+    let metadata = json!({
+        "title": title_for(upload.metadata_value("title"), &filename),
+        "original_filename": original_filename,
+    });
+
+    let complete_error = |e: anyhow::Error| {
+        tracing::error!("Failed to complete resumable upload: {}", e);
+        TusError::InternalServerError
+    };
+    locked
+        .complete(Uuid::new_v4(), media_type, &metadata)
+        .await
+        .map_err(complete_error)?;
+
+    // Assemble the object last; if anything before fails, the upload stays
+    // at its previous offset and the client resends the last chunk
+    let parts = locked.parts().await.map_err(complete_error)?;
+    state
+        .tus_store
+        .finish(&locked.upload, &parts)
+        .await
+        .map_err(complete_error)?;
+
+    Ok(())
+}
+
+/// Build a response describing the state of a resumable upload
+fn tus_upload_response(status: StatusCode, upload: &TusUpload) -> Response {
+    let mut response = tus_response(status);
+    let headers = response.headers_mut();
+    headers.insert("Upload-Offset", HeaderValue::from(upload.upload_offset));
+    headers.insert("Upload-Length", HeaderValue::from(upload.upload_length));
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+
+    match upload.media_item_id {
+        Some(media_id) => insert_header(headers, "X-Media-Id", media_id.to_string()),
+        None => insert_header(headers, "Upload-Expires", format_expires(upload.expires_at)),
+    }
+
+    response
+}
+
+/// Insert a header whose value is known to be valid
+fn insert_header(
+    headers: &mut HeaderMap,
+    name: impl axum::http::header::IntoHeaderName,
+    value: String,
+) {
+    if let Ok(value) = HeaderValue::from_str(&value) {
+        headers.insert(name, value);
+    }
+}
+
+/// Refresh media library or specific media item
+pub async fn refresh_media(
+    State(_state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(payload): Json<MediaRefreshRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.ensure_not_impersonated()?;
+
     // For now, we'll return a simple success response
     // In a real implementation, this would trigger a background process
     // to refresh the media library or specific media item
@@ -2,13 +2,46 @@
 
 use sqlx::PgPool;
 
-use crate::repositories::{SessionRepository, UserRepository, media::MediaRepository};
+use crate::{
+    hls::HlsCatalog,
+    live::CommentFeed,
+    playback::PlaybackSigner,
+    recommender::Recommender,
+    repositories::{
+        SessionRepository, UserRepository, collection::CollectionRepository,
+        comment::CommentRepository, history::HistoryRepository, media::MediaRepository,
+        notification::NotificationRepository, parental::ParentalControlRepository,
+        profile::ProfileRepository, progress::ProgressRepository, review::ReviewRepository,
+        series::SeriesRepository, taxonomy::TaxonomyRepository, tus::TusRepository,
+        upload::UploadRepository,
+    },
+    storage::Storage,
+    tus::TusStore,
+};
 
 /// Application state shared across handlers
 #[derive(Clone)]
 pub struct AppState {
     pub db_pool: PgPool,
     pub user_repository: UserRepository,
+    pub profile_repository: ProfileRepository,
+    pub parental_repository: ParentalControlRepository,
     pub session_repository: SessionRepository,
     pub media_repository: MediaRepository,
+    pub progress_repository: ProgressRepository,
+    pub history_repository: HistoryRepository,
+    pub collection_repository: CollectionRepository,
+    pub series_repository: SeriesRepository,
+    pub taxonomy_repository: TaxonomyRepository,
+    pub review_repository: ReviewRepository,
+    pub comment_repository: CommentRepository,
+    pub notification_repository: NotificationRepository,
+    pub upload_repository: UploadRepository,
+    pub storage: Storage,
+    pub playback: PlaybackSigner,
+    pub hls: HlsCatalog,
+    pub comment_feed: CommentFeed,
+    pub recommender: Recommender,
+    pub tus_repository: TusRepository,
+    pub tus_store: TusStore,
 }
@@ -0,0 +1,383 @@
+//! Object storage access for the API service
+//!
+//! Clients upload media straight to S3 (or an S3-compatible store such as
+//! MinIO) through presigned URLs. Resumable uploads pass through the API and
+//! are written to S3 part by part.
+
+use anyhow::Result;
+use aws_config::BehaviorVersion;
+use aws_sdk_s3::{
+    Client,
+    presigning::PresigningConfig,
+    primitives::ByteStream,
+    types::{CompletedMultipartUpload, CompletedPart},
+};
+use chrono::{DateTime, Utc};
+use std::env;
+use std::time::Duration;
+use tracing::error;
+
+/// Metadata of a stored object
+#[derive(Debug, Clone)]
+pub struct ObjectInfo {
+    pub size: i64,
+    pub content_type: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// S3 rejects multipart uploads with more parts than this
+const MAX_PARTS: i64 = 10_000;
+
+/// S3 rejects parts smaller than this, except for the last one
+const MIN_PART_SIZE: i64 = 5 * 1024 * 1024;
+
+/// Storage configuration
+#[derive(Debug, Clone)]
+pub struct StorageConfig {
+    /// Bucket the media service ingests from
+    pub bucket_name: String,
+    /// Custom S3 endpoint, e.g. a local MinIO instance
+    pub endpoint_url: Option<String>,
+    /// Use path-style bucket addressing (required by MinIO)
+    pub force_path_style: bool,
+    /// Presigned URL expiration time in seconds
+    pub url_expiry: u64,
+    /// Uploads larger than this many bytes use multipart uploads
+    pub multipart_threshold: i64,
+    /// Preferred multipart part size in bytes
+    pub part_size: i64,
+    /// Maximum upload size in bytes
+    pub max_upload_size: i64,
+}
+
+impl StorageConfig {
+    /// Create a new StorageConfig from environment variables
+    ///
+    /// # Environment Variables
+    /// - `MEDIA_BUCKET_NAME`: Media bucket (default: "media-bucket")
+    /// - `S3_ENDPOINT_URL`: Custom S3 endpoint (optional)
+    /// - `S3_FORCE_PATH_STYLE`: Use path-style addressing (default: false)
+    /// - `UPLOAD_URL_EXPIRY`: Presigned URL expiry in seconds (default: 3600)
+    /// - `UPLOAD_MULTIPART_THRESHOLD`: Multipart threshold in bytes (default: 100 MiB)
+    /// - `UPLOAD_PART_SIZE`: Multipart part size in bytes (default: 64 MiB)
+    /// - `UPLOAD_MAX_SIZE`: Maximum upload size in bytes (default: 50 GiB)
+    pub fn from_env() -> Self {
+        fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+            env::var(key)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(default)
+        }
+
+        StorageConfig {
+            bucket_name: env::var("MEDIA_BUCKET_NAME")
+                .unwrap_or_else(|_| "media-bucket".to_string()),
+            endpoint_url: env::var("S3_ENDPOINT_URL").ok(),
+            force_path_style: env_or("S3_FORCE_PATH_STYLE", false),
+            url_expiry: env_or("UPLOAD_URL_EXPIRY", 3600),
+            multipart_threshold: env_or("UPLOAD_MULTIPART_THRESHOLD", 100 * 1024 * 1024),
+            part_size: env_or("UPLOAD_PART_SIZE", 64 * 1024 * 1024),
+            max_upload_size: env_or("UPLOAD_MAX_SIZE", 50 * 1024 * 1024 * 1024),
+        }
+    }
+
+    /// Part size and part count for a multipart upload of `size` bytes
+    ///
+    /// The configured part size is grown when needed to stay within the S3
+    /// part limits.
+    pub fn plan_parts(&self, size: i64) -> (i64, i64) {
+        let part_size = self
+            .part_size
+            .max(MIN_PART_SIZE)
+            .max((size + MAX_PARTS - 1) / MAX_PARTS);
+        let part_count = ((size + part_size - 1) / part_size).max(1);
+
+        (part_size, part_count)
+    }
+}
+
+/// Part of a multipart upload confirmed by the client
+#[derive(Debug, Clone)]
+pub struct UploadedPart {
+    pub part_number: i32,
+    pub etag: String,
+}
+
+/// S3 object storage client
+#[derive(Clone)]
+pub struct Storage {
+    client: Client,
+    config: StorageConfig,
+}
+
+impl Storage {
+    /// Create a new storage client using the default AWS credential chain
+    pub async fn new(config: StorageConfig) -> Self {
+        let sdk_config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+
+        let mut s3_config = aws_sdk_s3::config::Builder::from(&sdk_config)
+            .force_path_style(config.force_path_style);
+        if let Some(endpoint_url) = &config.endpoint_url {
+            s3_config = s3_config.endpoint_url(endpoint_url);
+        }
+
+        Self {
+            client: Client::from_conf(s3_config.build()),
+            config,
+        }
+    }
+
+    /// Get the storage configuration
+    pub fn config(&self) -> &StorageConfig {
+        &self.config
+    }
+
+    fn presigning_config(&self) -> Result<PresigningConfig> {
+        Ok(PresigningConfig::expires_in(Duration::from_secs(
+            self.config.url_expiry,
+        ))?)
+    }
+
+    /// Presign a single PUT upload of an object
+    pub async fn presign_put(&self, key: &str, content_type: &str) -> Result<String> {
+        let request = self
+            .client
+            .put_object()
+            .bucket(&self.config.bucket_name)
+            .key(key)
+            .content_type(content_type)
+            .presigned(self.presigning_config()?)
+            .await?;
+
+        Ok(request.uri().to_string())
+    }
+
+    /// Start a multipart upload, returning its upload ID
+    pub async fn create_multipart_upload(&self, key: &str, content_type: &str) -> Result<String> {
+        let response = self
+            .client
+            .create_multipart_upload()
+            .bucket(&self.config.bucket_name)
+            .key(key)
+            .content_type(content_type)
+            .send()
+            .await?;
+
+        response
+            .upload_id()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("S3 did not return a multipart upload ID"))
+    }
+
+    /// Presign the upload of one part of a multipart upload
+    pub async fn presign_upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+    ) -> Result<String> {
+        let request = self
+            .client
+            .upload_part()
+            .bucket(&self.config.bucket_name)
+            .key(key)
+            .upload_id(upload_id)
+            .part_number(part_number)
+            .presigned(self.presigning_config()?)
+            .await?;
+
+        Ok(request.uri().to_string())
+    }
+
+    /// Assemble the uploaded parts of a multipart upload into the object
+    pub async fn complete_multipart_upload(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+    ) -> Result<()> {
+        let parts = parts
+            .iter()
+            .map(|part| {
+                CompletedPart::builder()
+                    .part_number(part.part_number)
+                    .e_tag(&part.etag)
+                    .build()
+            })
+            .collect();
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.config.bucket_name)
+            .key(key)
+            .upload_id(upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Upload one part of a multipart upload, returning its ETag
+    pub async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Vec<u8>,
+    ) -> Result<String> {
+        let response = self
+            .client
+            .upload_part()
+            .bucket(&self.config.bucket_name)
+            .key(key)
+            .upload_id(upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(body))
+            .send()
+            .await?;
+
+        response
+            .e_tag()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("S3 did not return an ETag for part {}", part_number))
+    }
+
+    /// Store a small object
+    pub async fn put_object(&self, key: &str, body: Vec<u8>) -> Result<()> {
+        self.client
+            .put_object()
+            .bucket(&self.config.bucket_name)
+            .key(key)
+            .body(ByteStream::from(body))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Read a small object into memory
+    pub async fn read_object(&self, key: &str) -> Result<Vec<u8>> {
+        let body = self.get_object(key, None).await?.collect().await?;
+        Ok(body.to_vec())
+    }
+
+    /// Delete an object; deleting a missing object succeeds
+    pub async fn delete_object(&self, key: &str) -> Result<()> {
+        self.client
+            .delete_object()
+            .bucket(&self.config.bucket_name)
+            .key(key)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Abort a multipart upload, discarding its uploaded parts
+    pub async fn abort_multipart_upload(&self, key: &str, upload_id: &str) {
+        if let Err(e) = self
+            .client
+            .abort_multipart_upload()
+            .bucket(&self.config.bucket_name)
+            .key(key)
+            .upload_id(upload_id)
+            .send()
+            .await
+        {
+            error!("Failed to abort multipart upload for {}: {}", key, e);
+        }
+    }
+
+    /// Discard the storage of an unfinished direct upload
+    ///
+    /// Aborts the multipart upload, if still open, so its parts stop taking
+    /// space, and deletes the object if one was written.
+    pub async fn discard_upload(&self, key: &str, multipart_upload_id: Option<&str>) {
+        if let Some(upload_id) = multipart_upload_id {
+            self.abort_multipart_upload(key, upload_id).await;
+        }
+
+        if let Err(e) = self.delete_object(key).await {
+            error!("Failed to delete uploaded object {}: {}", key, e);
+        }
+    }
+
+    /// Get the size of an object, or `None` if it does not exist
+    pub async fn object_size(&self, key: &str) -> Result<Option<i64>> {
+        Ok(self.head_object(key).await?.map(|object| object.size))
+    }
+
+    /// Get the size, type and version of an object, or `None` if it doesn't exist
+    pub async fn head_object(&self, key: &str) -> Result<Option<ObjectInfo>> {
+        match self
+            .client
+            .head_object()
+            .bucket(&self.config.bucket_name)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(response) => Ok(Some(ObjectInfo {
+                size: response.content_length().unwrap_or(0),
+                content_type: response.content_type().map(str::to_string),
+                etag: response.e_tag().map(str::to_string),
+                last_modified: response
+                    .last_modified()
+                    .and_then(|date| DateTime::from_timestamp(date.secs(), 0)),
+            })),
+            Err(e) => {
+                let e = e.into_service_error();
+                if e.is_not_found() {
+                    Ok(None)
+                } else {
+                    Err(e.into())
+                }
+            }
+        }
+    }
+
+    /// Stream an object, or the inclusive byte range `start..=end` of it
+    pub async fn get_object(&self, key: &str, range: Option<(u64, u64)>) -> Result<ByteStream> {
+        let response = self
+            .client
+            .get_object()
+            .bucket(&self.config.bucket_name)
+            .key(key)
+            .set_range(range.map(|(start, end)| format!("bytes={}-{}", start, end)))
+            .send()
+            .await?;
+
+        Ok(response.body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_parts() {
+        let config = StorageConfig {
+            bucket_name: "media-bucket".to_string(),
+            endpoint_url: None,
+            force_path_style: true,
+            url_expiry: 3600,
+            multipart_threshold: 100 * 1024 * 1024,
+            part_size: 64 * 1024 * 1024,
+            max_upload_size: 50 * 1024 * 1024 * 1024,
+        };
+
+        assert_eq!(config.plan_parts(200 * 1024 * 1024), (64 * 1024 * 1024, 4));
+
+        // Very large uploads grow the part size to stay within 10,000 parts
+        let (part_size, part_count) = config.plan_parts(1024 * 1024 * 1024 * 1024);
+        assert!(part_count <= MAX_PARTS);
+        assert!(part_size * part_count >= 1024 * 1024 * 1024 * 1024);
+    }
+}
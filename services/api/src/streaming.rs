@@ -0,0 +1,217 @@
+//! HTTP range and validator handling for media playback
+
+use chrono::{DateTime, Utc};
+
+/// Part of an object requested with a `Range` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// No (usable) range, send the whole object
+    Full,
+    /// Inclusive byte range within the object
+    Partial { start: u64, end: u64 },
+    /// Range lies outside the object
+    Unsatisfiable,
+}
+
+/// Resolve a `Range` header against an object of `size` bytes
+///
+/// Only single `bytes` ranges are supported; malformed headers and multiple
+/// ranges are ignored as RFC 9110 permits, serving the full object.
+pub fn parse_range(header: &str, size: u64) -> ByteRange {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        // Suffix range: the last `n` bytes
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return ByteRange::Unsatisfiable,
+            Ok(suffix) if size > 0 => (size.saturating_sub(suffix), size - 1),
+            Ok(_) => return ByteRange::Unsatisfiable,
+            Err(_) => return ByteRange::Full,
+        },
+        (start, end) => {
+            let Ok(start) = start.parse::<u64>() else {
+                return ByteRange::Full;
+            };
+            let end = if end.is_empty() {
+                u64::MAX
+            } else {
+                match end.parse::<u64>() {
+                    Ok(end) if end >= start => end,
+                    _ => return ByteRange::Full,
+                }
+            };
+            if start >= size {
+                return ByteRange::Unsatisfiable;
+            }
+            (start, end.min(size - 1))
+        }
+    };
+
+    ByteRange::Partial { start, end }
+}
+
+/// Check whether an `If-Range` validator still matches the object
+///
+/// Entity tags must match strongly; dates must equal the last modification.
+pub fn if_range_matches(
+    value: &str,
+    etag: Option<&str>,
+    last_modified: Option<DateTime<Utc>>,
+) -> bool {
+    let value = value.trim();
+    if value.starts_with("W/") {
+        return false;
+    }
+    if value.starts_with('"') {
+        return etag == Some(value);
+    }
+
+    match (DateTime::parse_from_rfc2822(value), last_modified) {
+        (Ok(date), Some(last_modified)) => date.with_timezone(&Utc) == last_modified,
+        _ => false,
+    }
+}
+
+/// Check an `If-None-Match` header against the object's entity tag
+pub fn etag_matches(if_none_match: &str, etag: Option<&str>) -> bool {
+    let Some(etag) = etag else {
+        return false;
+    };
+    let etag = etag.trim_start_matches("W/");
+
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+/// Content type to serve for a stored object
+///
+/// Objects ingested from the bucket often carry a generic type, in which case
+/// the type is derived from the key's extension.
+pub fn content_type_for(stored: Option<&str>, key: &str) -> String {
+    match stored {
+        Some(content_type)
+            if !content_type.is_empty()
+                && content_type != "application/octet-stream"
+                && content_type != "binary/octet-stream" =>
+        {
+            content_type.to_string()
+        }
+        _ => guess_content_type(key).to_string(),
+    }
+}
+
+/// Guess the content type of a media file from its extension
+fn guess_content_type(key: &str) -> &'static str {
+    let extension = key
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "mp4" | "m4v" => "video/mp4",
+        "mkv" => "video/x-matroska",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        "avi" => "video/x-msvideo",
+        "ts" => "video/mp2t",
+        "mp3" => "audio/mpeg",
+        "m4a" | "aac" => "audio/mp4",
+        "flac" => "audio/flac",
+        "wav" => "audio/wav",
+        "ogg" | "oga" => "audio/ogg",
+        "opus" => "audio/opus",
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(
+            parse_range("bytes=0-99", 1000),
+            ByteRange::Partial { start: 0, end: 99 }
+        );
+        assert_eq!(
+            parse_range("bytes=900-", 1000),
+            ByteRange::Partial {
+                start: 900,
+                end: 999
+            }
+        );
+        assert_eq!(
+            parse_range("bytes=-100", 1000),
+            ByteRange::Partial {
+                start: 900,
+                end: 999
+            }
+        );
+        assert_eq!(
+            parse_range("bytes=-5000", 1000),
+            ByteRange::Partial { start: 0, end: 999 }
+        );
+        assert_eq!(
+            parse_range("bytes=500-5000", 1000),
+            ByteRange::Partial {
+                start: 500,
+                end: 999
+            }
+        );
+        assert_eq!(parse_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-", 0), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=9-5", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=0-1,5-9", 1000), ByteRange::Full);
+        assert_eq!(parse_range("items=0-1", 1000), ByteRange::Full);
+    }
+
+    #[test]
+    fn test_validators() {
+        let etag = Some("\"abc\"");
+        let last_modified = DateTime::from_timestamp(784111777, 0);
+
+        assert!(if_range_matches("\"abc\"", etag, last_modified));
+        assert!(!if_range_matches("W/\"abc\"", etag, last_modified));
+        assert!(!if_range_matches("\"def\"", etag, last_modified));
+        assert!(if_range_matches(
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            etag,
+            last_modified
+        ));
+        assert!(!if_range_matches(
+            "Sun, 06 Nov 1994 08:49:38 GMT",
+            etag,
+            last_modified
+        ));
+
+        assert!(etag_matches("\"x\", W/\"abc\"", etag));
+        assert!(etag_matches("*", etag));
+        assert!(!etag_matches("\"x\"", etag));
+    }
+
+    #[test]
+    fn test_content_type_for() {
+        assert_eq!(content_type_for(Some("video/webm"), "a.mp4"), "video/webm");
+        assert_eq!(
+            content_type_for(Some("binary/octet-stream"), "movies/A.MKV"),
+            "video/x-matroska"
+        );
+        assert_eq!(content_type_for(None, "notes"), "application/octet-stream");
+    }
+}
@@ -0,0 +1,392 @@
+//! Resumable uploads following the tus 1.0 protocol
+//!
+//! Chunks sent with `PATCH` are streamed into an S3 multipart upload, one
+//! part per `part_size` bytes. Bytes short of a full part are kept in a
+//! small object next to the upload until the next chunk completes the part.
+//! Once the full length has been received the multipart upload is completed
+//! and a `pending` media item is created for the media service.
+//!
+//! Supported extensions: creation, expiration and termination.
+
+use anyhow::Result;
+use axum::{
+    body::Body,
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    models::upload::TusUpload,
+    storage::{Storage, UploadedPart},
+};
+
+/// Protocol version implemented by this server
+pub const TUS_VERSION: &str = "1.0.0";
+
+/// Protocol extensions implemented by this server
+pub const TUS_EXTENSIONS: &str = "creation,expiration,termination";
+
+/// Prefix of the objects holding bytes short of a full part, which the
+/// media service skips
+const PENDING_PREFIX: &str = "tus-pending/";
+
+/// Content type required for `PATCH` requests
+pub const OFFSET_CONTENT_TYPE: &str = "application/offset+octet-stream";
+
+/// tus configuration
+#[derive(Debug, Clone)]
+pub struct TusConfig {
+    /// Time in seconds an incomplete upload is kept
+    pub expiry: u64,
+}
+
+impl TusConfig {
+    /// Create a new TusConfig from environment variables
+    ///
+    /// # Environment Variables
+    /// - `TUS_UPLOAD_EXPIRY`: Expiry of incomplete uploads in seconds (default: 86400)
+    pub fn from_env() -> Self {
+        let expiry = std::env::var("TUS_UPLOAD_EXPIRY")
+            .unwrap_or_else(|_| "86400".to_string()) // 1 day
+            .parse()
+            .unwrap_or(86400);
+
+        TusConfig { expiry }
+    }
+}
+
+/// Bytes received by a `PATCH`
+#[derive(Debug)]
+pub struct TusProgress {
+    /// New offset of the upload
+    pub offset: i64,
+    /// Parts uploaded for the received bytes
+    pub parts: Vec<UploadedPart>,
+}
+
+/// Object storage of tus uploads
+#[derive(Clone)]
+pub struct TusStore {
+    storage: Storage,
+    config: TusConfig,
+}
+
+impl TusStore {
+    /// Create a new store writing to object storage
+    pub fn new(storage: Storage, config: TusConfig) -> Self {
+        Self { storage, config }
+    }
+
+    /// Get the tus configuration
+    pub fn config(&self) -> &TusConfig {
+        &self.config
+    }
+
+    /// Start the multipart upload of a new upload, returning its ID
+    pub async fn create(&self, s3_key: &str, content_type: &str) -> Result<String> {
+        self.storage
+            .create_multipart_upload(s3_key, content_type)
+            .await
+    }
+
+    /// Stream a request body into the upload at its current offset
+    ///
+    /// At most the bytes missing from the upload are accepted. Full parts
+    /// are uploaded as they fill up, and the last part once the upload is
+    /// complete; other bytes left over are kept for the next `PATCH`. Bytes
+    /// received before the client disconnected are kept so the upload can
+    /// be resumed from there.
+    pub async fn append(&self, upload: &TusUpload, body: Body) -> Result<TusProgress, TusError> {
+        let storage_error = |e: anyhow::Error| {
+            error!("Failed to write upload chunk for {}: {}", upload.id, e);
+            TusError::InternalServerError
+        };
+
+        let (mut part_count, pending) = part_layout(upload.upload_offset, upload.part_size);
+        let mut buffer = Vec::new();
+        if pending > 0 {
+            buffer = self
+                .storage
+                .read_object(&pending_key(upload.id, upload.upload_offset))
+                .await
+                .map_err(storage_error)?;
+            if buffer.len() as i64 != pending {
+                error!("Pending bytes of upload {} are incomplete", upload.id);
+                return Err(TusError::InternalServerError);
+            }
+        }
+
+        let max_length = upload.upload_length - upload.upload_offset;
+        let part_size = upload.part_size as usize;
+        let mut written = 0i64;
+        let mut parts = Vec::new();
+        let mut stream = body.into_data_stream();
+        while let Some(chunk) = stream.next().await {
+            let Ok(chunk) = chunk else {
+                break;
+            };
+
+            if written + chunk.len() as i64 > max_length {
+                return Err(TusError::TooLarge);
+            }
+            written += chunk.len() as i64;
+            buffer.extend_from_slice(&chunk);
+
+            while buffer.len() >= part_size {
+                let rest = buffer.split_off(part_size);
+                part_count += 1;
+                parts.push(self.upload_part(upload, part_count, buffer).await?);
+                buffer = rest;
+            }
+        }
+
+        let offset = upload.upload_offset + written;
+        if !buffer.is_empty() {
+            if offset == upload.upload_length {
+                part_count += 1;
+                parts.push(self.upload_part(upload, part_count, buffer).await?);
+            } else {
+                self.storage
+                    .put_object(&pending_key(upload.id, offset), buffer)
+                    .await
+                    .map_err(storage_error)?;
+            }
+        }
+
+        Ok(TusProgress { offset, parts })
+    }
+
+    async fn upload_part(
+        &self,
+        upload: &TusUpload,
+        part_number: i64,
+        body: Vec<u8>,
+    ) -> Result<UploadedPart, TusError> {
+        let part_number = part_number as i32;
+        let etag = self
+            .storage
+            .upload_part(
+                &upload.s3_key,
+                &upload.multipart_upload_id,
+                part_number,
+                body,
+            )
+            .await
+            .map_err(|e| {
+                error!("Failed to upload part of upload {}: {}", upload.id, e);
+                TusError::InternalServerError
+            })?;
+
+        Ok(UploadedPart { part_number, etag })
+    }
+
+    /// Assemble the uploaded parts into the object
+    pub async fn finish(&self, upload: &TusUpload, parts: &[UploadedPart]) -> Result<()> {
+        self.storage
+            .complete_multipart_upload(&upload.s3_key, &upload.multipart_upload_id, parts)
+            .await
+    }
+
+    /// Remove the pending bytes an upload had at an offset, if any
+    pub async fn remove_pending(&self, upload: &TusUpload, offset: i64) {
+        let (_, pending) = part_layout(offset, upload.part_size);
+        if pending == 0 {
+            return;
+        }
+
+        let key = pending_key(upload.id, offset);
+        if let Err(e) = self.storage.delete_object(&key).await {
+            tracing::warn!("Failed to remove pending bytes {}: {}", key, e);
+        }
+    }
+
+    /// Discard the storage of an incomplete upload
+    pub async fn remove(&self, upload: &TusUpload) {
+        self.storage
+            .abort_multipart_upload(&upload.s3_key, &upload.multipart_upload_id)
+            .await;
+        self.remove_pending(upload, upload.upload_offset).await;
+    }
+}
+
+/// Number of full parts and of pending bytes making up an offset
+///
+/// Every part but the last is exactly `part_size` bytes.
+fn part_layout(offset: i64, part_size: i64) -> (i64, i64) {
+    (offset / part_size, offset % part_size)
+}
+
+/// Key of the object holding the pending bytes of an upload at an offset
+///
+/// Naming it after the offset keeps the bytes of the recorded offset intact
+/// if a later `PATCH` fails before its progress is saved.
+fn pending_key(upload_id: Uuid, offset: i64) -> String {
+    format!("{}{}/{}", PENDING_PREFIX, upload_id, offset)
+}
+
+/// Parse an `Upload-Metadata` header into key/value pairs
+///
+/// The header is a comma separated list of keys, each optionally followed by
+/// a space and its base64 encoded value.
+pub fn parse_upload_metadata(header: &str) -> Result<HashMap<String, String>, String> {
+    let mut metadata = HashMap::new();
+
+    for pair in header
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+    {
+        let (key, value) = match pair.split_once(' ') {
+            Some((key, value)) => {
+                let value = STANDARD
+                    .decode(value.trim())
+                    .ok()
+                    .and_then(|value| String::from_utf8(value).ok())
+                    .ok_or_else(|| format!("Invalid metadata value for {}", key))?;
+                (key, value)
+            }
+            None => (pair, String::new()),
+        };
+
+        if metadata.insert(key.to_string(), value).is_some() {
+            return Err(format!("Duplicate metadata key: {}", key));
+        }
+    }
+
+    Ok(metadata)
+}
+
+/// Format a timestamp for the `Upload-Expires` header
+pub fn format_expires(expires_at: DateTime<Utc>) -> String {
+    expires_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Check that a request speaks the supported protocol version
+pub fn check_version(headers: &HeaderMap) -> Result<(), TusError> {
+    match headers.get("Tus-Resumable").and_then(|v| v.to_str().ok()) {
+        Some(TUS_VERSION) => Ok(()),
+        _ => Err(TusError::UnsupportedVersion),
+    }
+}
+
+/// Parse a non-negative integer header
+pub fn parse_length_header(headers: &HeaderMap, name: &str) -> Result<i64, TusError> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|v| *v >= 0)
+        .ok_or_else(|| TusError::BadRequest(format!("Missing or invalid {} header", name)))
+}
+
+/// Build a tus response carrying the protocol version header
+pub fn tus_response(status: StatusCode) -> Response {
+    let mut response = status.into_response();
+    response
+        .headers_mut()
+        .insert("Tus-Resumable", HeaderValue::from_static(TUS_VERSION));
+    response
+}
+
+/// Errors of the tus endpoints
+#[derive(Debug)]
+pub enum TusError {
+    /// Client speaks another protocol version
+    UnsupportedVersion,
+    /// Malformed request
+    BadRequest(String),
+    /// Upload does not exist or belongs to another user
+    NotFound,
+    /// Upload has expired
+    Gone,
+    /// `Upload-Offset` does not match the current offset
+    OffsetMismatch,
+    /// Another request is writing to the upload
+    Locked,
+    /// Upload is larger than allowed
+    TooLarge,
+    /// `PATCH` without the offset content type
+    UnsupportedMediaType,
+    /// Read-only or impersonated token
+    Forbidden,
+    InternalServerError,
+}
+
+impl IntoResponse for TusError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            TusError::UnsupportedVersion => (
+                StatusCode::PRECONDITION_FAILED,
+                "Unsupported tus version".to_string(),
+            ),
+            TusError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            TusError::NotFound => (StatusCode::NOT_FOUND, "Upload not found".to_string()),
+            TusError::Gone => (StatusCode::GONE, "Upload has expired".to_string()),
+            TusError::OffsetMismatch => (
+                StatusCode::CONFLICT,
+                "Upload-Offset does not match the current offset".to_string(),
+            ),
+            TusError::Locked => (
+                StatusCode::LOCKED,
+                "Upload is being written by another request".to_string(),
+            ),
+            TusError::TooLarge => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "Upload exceeds the maximum size".to_string(),
+            ),
+            TusError::UnsupportedMediaType => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("Content-Type must be {}", OFFSET_CONTENT_TYPE),
+            ),
+            TusError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden".to_string()),
+            TusError::InternalServerError => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            ),
+        };
+
+        let mut response = (status, message).into_response();
+        response
+            .headers_mut()
+            .insert("Tus-Resumable", HeaderValue::from_static(TUS_VERSION));
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_upload_metadata() {
+        let metadata =
+            parse_upload_metadata("filename bW92aWUubXA0,filetype dmlkZW8vbXA0, is_confidential")
+                .unwrap();
+
+        assert_eq!(metadata["filename"], "movie.mp4");
+        assert_eq!(metadata["filetype"], "video/mp4");
+        assert_eq!(metadata["is_confidential"], "");
+
+        assert!(parse_upload_metadata("filename !!!").is_err());
+        assert!(parse_upload_metadata("a YQ==,a Yg==").is_err());
+    }
+
+    #[test]
+    fn test_part_layout() {
+        let part_size = 5 * 1024 * 1024;
+
+        assert_eq!(part_layout(0, part_size), (0, 0));
+        assert_eq!(part_layout(1024, part_size), (0, 1024));
+        assert_eq!(part_layout(part_size, part_size), (1, 0));
+        assert_eq!(part_layout(2 * part_size + 7, part_size), (2, 7));
+        let id = Uuid::new_v4();
+        assert_ne!(pending_key(id, 7), pending_key(id, 8));
+        assert!(pending_key(id, 7).starts_with(PENDING_PREFIX));
+    }
+}
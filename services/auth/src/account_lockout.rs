@@ -0,0 +1,209 @@
+//! Per-account progressive lockout after repeated login failures
+//!
+//! Complements the IP based [`RateLimiter`](crate::rate_limiter::RateLimiter):
+//! failures are counted per account regardless of where they come from, and
+//! each successive lockout lasts twice as long as the previous one.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::info;
+use uuid::Uuid;
+
+/// Account lockout configuration
+#[derive(Debug, Clone)]
+pub struct AccountLockoutConfig {
+    /// Number of failed attempts before the account is locked
+    pub max_failures: u32,
+    /// Time window in seconds in which failures are counted
+    pub failure_window_seconds: u64,
+    /// Duration of the first lockout in seconds
+    pub base_lockout_seconds: u64,
+    /// Upper bound for the lockout duration in seconds
+    pub max_lockout_seconds: u64,
+}
+
+impl Default for AccountLockoutConfig {
+    fn default() -> Self {
+        Self {
+            max_failures: 5,
+            failure_window_seconds: 900, // 15 minutes
+            base_lockout_seconds: 60,    // 1 minute
+            max_lockout_seconds: 86400,  // 1 day
+        }
+    }
+}
+
+impl AccountLockoutConfig {
+    /// Create a new AccountLockoutConfig from environment variables
+    ///
+    /// # Environment Variables
+    /// - `ACCOUNT_LOCKOUT_MAX_FAILURES`: Failed attempts before locking (default: 5)
+    /// - `ACCOUNT_LOCKOUT_FAILURE_WINDOW`: Window for counting failures in seconds (default: 900)
+    /// - `ACCOUNT_LOCKOUT_BASE_DURATION`: First lockout duration in seconds (default: 60)
+    /// - `ACCOUNT_LOCKOUT_MAX_DURATION`: Maximum lockout duration in seconds (default: 86400)
+    pub fn from_env() -> Self {
+        fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+            std::env::var(key)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(default)
+        }
+
+        let defaults = Self::default();
+
+        Self {
+            max_failures: env_or("ACCOUNT_LOCKOUT_MAX_FAILURES", defaults.max_failures),
+            failure_window_seconds: env_or(
+                "ACCOUNT_LOCKOUT_FAILURE_WINDOW",
+                defaults.failure_window_seconds,
+            ),
+            base_lockout_seconds: env_or(
+                "ACCOUNT_LOCKOUT_BASE_DURATION",
+                defaults.base_lockout_seconds,
+            ),
+            max_lockout_seconds: env_or(
+                "ACCOUNT_LOCKOUT_MAX_DURATION",
+                defaults.max_lockout_seconds,
+            ),
+        }
+    }
+}
+
+/// Account lockout entry
+#[derive(Debug)]
+struct AccountLockoutEntry {
+    /// Failed attempts in the current window
+    failures: u32,
+    /// Time of the first failure in the current window
+    window_start: Instant,
+    /// Number of lockouts so far, used to grow the lockout duration
+    lockouts: u32,
+    /// Lockout expiration time
+    locked_until: Option<Instant>,
+}
+
+/// Account lockout tracker
+#[derive(Debug, Clone)]
+pub struct AccountLockout {
+    /// Account lockout configuration
+    config: AccountLockoutConfig,
+    /// Account lockout entries
+    entries: Arc<Mutex<HashMap<Uuid, AccountLockoutEntry>>>,
+}
+
+impl AccountLockout {
+    /// Create a new account lockout tracker
+    pub fn new(config: AccountLockoutConfig) -> Self {
+        Self {
+            config,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Get the remaining lockout time for an account, if it is locked
+    pub async fn locked_for(&self, user_id: Uuid) -> Option<Duration> {
+        let entries = self.entries.lock().await;
+        let now = Instant::now();
+
+        entries
+            .get(&user_id)
+            .and_then(|entry| entry.locked_until)
+            .filter(|locked_until| *locked_until > now)
+            .map(|locked_until| locked_until - now)
+    }
+
+    /// Record a failed login attempt
+    ///
+    /// Returns the lockout duration if this failure locked the account.
+    pub async fn record_failure(&self, user_id: Uuid) -> Option<Duration> {
+        let mut entries = self.entries.lock().await;
+        let now = Instant::now();
+
+        let entry = entries.entry(user_id).or_insert(AccountLockoutEntry {
+            failures: 0,
+            window_start: now,
+            lockouts: 0,
+            locked_until: None,
+        });
+
+        // Check if window has expired
+        if now.duration_since(entry.window_start)
+            >= Duration::from_secs(self.config.failure_window_seconds)
+        {
+            entry.failures = 0;
+            entry.window_start = now;
+        }
+
+        entry.failures += 1;
+
+        if entry.failures < self.config.max_failures {
+            return None;
+        }
+
+        // Lock the account, doubling the duration with every lockout
+        let lockout_seconds = self
+            .config
+            .base_lockout_seconds
+            .saturating_mul(2u64.saturating_pow(entry.lockouts))
+            .min(self.config.max_lockout_seconds);
+        let lockout = Duration::from_secs(lockout_seconds);
+
+        entry.failures = 0;
+        entry.window_start = now;
+        entry.lockouts += 1;
+        entry.locked_until = Some(now + lockout);
+
+        info!(
+            "Locked account {} for {} seconds after repeated login failures",
+            user_id, lockout_seconds
+        );
+
+        Some(lockout)
+    }
+
+    /// Record a successful login, clearing the failure history
+    pub async fn record_success(&self, user_id: Uuid) {
+        let mut entries = self.entries.lock().await;
+        entries.remove(&user_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_progressive_lockout() {
+        let lockout = AccountLockout::new(AccountLockoutConfig {
+            max_failures: 2,
+            failure_window_seconds: 60,
+            base_lockout_seconds: 10,
+            max_lockout_seconds: 25,
+        });
+        let user_id = Uuid::new_v4();
+
+        assert_eq!(lockout.record_failure(user_id).await, None);
+        assert_eq!(
+            lockout.record_failure(user_id).await,
+            Some(Duration::from_secs(10))
+        );
+        assert!(lockout.locked_for(user_id).await.is_some());
+
+        lockout.record_failure(user_id).await;
+        assert_eq!(
+            lockout.record_failure(user_id).await,
+            Some(Duration::from_secs(20))
+        );
+
+        lockout.record_failure(user_id).await;
+        assert_eq!(
+            lockout.record_failure(user_id).await,
+            Some(Duration::from_secs(25))
+        );
+
+        lockout.record_success(user_id).await;
+        assert_eq!(lockout.locked_for(user_id).await, None);
+    }
+}
@@ -0,0 +1,138 @@
+//! Offline breached-password checks
+//!
+//! Passwords are checked against a locally stored corpus in the k-anonymity
+//! range format used by Have I Been Pwned: the SHA-1 hash of the password is
+//! split into a 5 character prefix, which names a range file, and a 35
+//! character suffix, which is looked up in that file as `SUFFIX:COUNT` lines.
+
+use anyhow::Result;
+use sha1::{Digest, Sha1};
+use std::path::PathBuf;
+use tracing::info;
+
+/// A source of known breached passwords
+pub trait BreachedPasswordChecker: Send + Sync {
+    /// Check whether a password appears in the breached-password corpus
+    fn is_breached(&self, password: &str) -> Result<bool>;
+}
+
+/// Breached-password checker backed by a directory of range files
+///
+/// The directory contains one file per SHA-1 prefix, named either `ABCDE`
+/// or `ABCDE.txt`. Missing range files are treated as empty ranges.
+#[derive(Debug, Clone)]
+pub struct RangeFileChecker {
+    /// Directory containing the range files
+    directory: PathBuf,
+    /// Minimum breach count for a password to be rejected
+    min_count: u64,
+}
+
+impl RangeFileChecker {
+    /// Create a new range file checker
+    pub fn new(directory: impl Into<PathBuf>, min_count: u64) -> Self {
+        Self {
+            directory: directory.into(),
+            min_count,
+        }
+    }
+
+    /// Create a new RangeFileChecker from environment variables
+    ///
+    /// Returns `None` when no corpus is configured.
+    ///
+    /// # Environment Variables
+    /// - `BREACHED_PASSWORDS_DIR`: Directory containing the range files
+    /// - `BREACHED_PASSWORDS_MIN_COUNT`: Minimum breach count to reject a password (default: 1)
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(directory) = std::env::var("BREACHED_PASSWORDS_DIR") else {
+            return Ok(None);
+        };
+
+        let directory = PathBuf::from(directory);
+        if !directory.is_dir() {
+            return Err(anyhow::anyhow!(
+                "Breached passwords directory does not exist: {}",
+                directory.display()
+            ));
+        }
+
+        let min_count = std::env::var("BREACHED_PASSWORDS_MIN_COUNT")
+            .unwrap_or_else(|_| "1".to_string())
+            .parse()
+            .unwrap_or(1);
+
+        info!(
+            "Breached password checks enabled using corpus at {}",
+            directory.display()
+        );
+
+        Ok(Some(Self::new(directory, min_count)))
+    }
+
+    fn read_range(&self, prefix: &str) -> Result<Option<String>> {
+        for file_name in [prefix.to_string(), format!("{}.txt", prefix)] {
+            match std::fs::read_to_string(self.directory.join(file_name)) {
+                Ok(contents) => return Ok(Some(contents)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl BreachedPasswordChecker for RangeFileChecker {
+    fn is_breached(&self, password: &str) -> Result<bool> {
+        let hash: String = Sha1::digest(password.as_bytes())
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect();
+        let (prefix, suffix) = hash.split_at(5);
+
+        let Some(range) = self.read_range(prefix)? else {
+            return Ok(false);
+        };
+
+        for line in range.lines() {
+            let Some((line_suffix, count)) = line.trim().split_once(':') else {
+                continue;
+            };
+
+            if line_suffix.eq_ignore_ascii_case(suffix) {
+                let count: u64 = count.trim().parse().unwrap_or(1);
+                return Ok(count >= self.min_count);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_file_lookup() {
+        let directory = std::env::temp_dir().join(format!("breached-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        // SHA-1("password") = 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+        std::fs::write(
+            directory.join("5BAA6.txt"),
+            "003D68EB55068C33ACE09247EE4C639306B:3\r\n1E4C9B93F3F0682250B6CF8331B7EE68FD8:9545824\r\n",
+        )
+        .unwrap();
+
+        let checker = RangeFileChecker::new(&directory, 1);
+        assert!(checker.is_breached("password").unwrap());
+        assert!(!checker.is_breached("correct horse battery staple").unwrap());
+
+        let strict_checker = RangeFileChecker::new(&directory, 10_000_000);
+        assert!(!strict_checker.is_breached("password").unwrap());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
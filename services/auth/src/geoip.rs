@@ -0,0 +1,54 @@
+//! Country lookups for client IP addresses from a local GeoIP database
+
+use anyhow::Result;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Resolves IP addresses to ISO country codes
+pub trait GeoIpLookup: Send + Sync {
+    /// Look up the ISO 3166-1 country code for an IP address
+    fn country(&self, ip: IpAddr) -> Option<String>;
+}
+
+/// GeoIP lookup backed by a MaxMind database file (GeoLite2/GeoIP2 Country or City)
+pub struct MaxMindGeoIp {
+    reader: maxminddb::Reader<Vec<u8>>,
+}
+
+impl MaxMindGeoIp {
+    /// Open a MaxMind database file
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let reader = maxminddb::Reader::open_readfile(&path).map_err(|e| {
+            anyhow::anyhow!("Failed to open GeoIP database {}: {}", path.display(), e)
+        })?;
+
+        info!("Loaded GeoIP database from {}", path.display());
+        Ok(Self { reader })
+    }
+
+    /// Open the GeoIP database configured in the environment
+    ///
+    /// Returns `None` when no database is configured.
+    ///
+    /// # Environment Variables
+    /// - `GEOIP_DATABASE_PATH`: Path to a MaxMind `.mmdb` file
+    pub fn from_env() -> Result<Option<Self>> {
+        match std::env::var("GEOIP_DATABASE_PATH") {
+            Ok(path) => Ok(Some(Self::open(path)?)),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+impl GeoIpLookup for MaxMindGeoIp {
+    fn country(&self, ip: IpAddr) -> Option<String> {
+        let record: maxminddb::geoip2::Country = self.reader.lookup(ip).ok()?;
+
+        record
+            .country
+            .and_then(|country| country.iso_code)
+            .map(|iso_code| iso_code.to_string())
+    }
+}
@@ -24,6 +24,8 @@ pub struct JwtConfig {
     pub access_token_expiry: u64,
     /// Refresh token expiration time in seconds (default: 7 days)
     pub refresh_token_expiry: u64,
+    /// Impersonation token expiration time in seconds (default: 10 minutes)
+    pub impersonation_token_expiry: u64,
 }
 
 impl JwtConfig {
@@ -34,6 +36,7 @@ impl JwtConfig {
     /// - `JWT_PUBLIC_KEY`: Public key for verifying tokens (PEM format) or path to public key file
     /// - `JWT_ACCESS_TOKEN_EXPIRY`: Access token expiry in seconds (default: 900)
     /// - `JWT_REFRESH_TOKEN_EXPIRY`: Refresh token expiry in seconds (default: 604800)
+    /// - `JWT_IMPERSONATION_TOKEN_EXPIRY`: Impersonation token expiry in seconds (default: 600)
     pub fn from_env() -> Result<Self> {
         let private_key = std::env::var("JWT_PRIVATE_KEY")
             .map_err(|_| anyhow::anyhow!("JWT_PRIVATE_KEY environment variable not set"))?;
@@ -82,17 +85,23 @@ impl JwtConfig {
             .parse()
             .unwrap_or(604800);
 
+        let impersonation_token_expiry = std::env::var("JWT_IMPERSONATION_TOKEN_EXPIRY")
+            .unwrap_or_else(|_| "600".to_string()) // 10 minutes
+            .parse()
+            .unwrap_or(600);
+
         Ok(JwtConfig {
             private_key,
             public_key,
             access_token_expiry,
             refresh_token_expiry,
+            impersonation_token_expiry,
         })
     }
 }
 
 /// JWT claims structure
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     /// User ID
     pub sub: Uuid,
@@ -104,8 +113,27 @@ pub struct Claims {
     pub iat: u64,
     /// Expiration time
     pub exp: u64,
-    /// Token type (access or refresh)
+    /// Token type (access, refresh or magic link)
     pub token_type: TokenType,
+    /// Unique token identifier, used for single-use tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    /// Actor acting on behalf of the subject (RFC 8693), set for impersonation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Actor>,
+    /// Whether the token may only be used for read operations
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+    /// Viewer profile selected for the token, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_id: Option<Uuid>,
+}
+
+/// Actor claim identifying who is acting on behalf of the subject
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Actor {
+    /// Actor user ID
+    pub sub: Uuid,
 }
 
 /// Token type enum
@@ -115,6 +143,8 @@ pub enum TokenType {
     Access,
     /// Refresh token
     Refresh,
+    /// Single-use magic link token
+    MagicLink,
 }
 
 /// JWT service
@@ -142,8 +172,13 @@ impl JwtService {
         })
     }
 
-    /// Generate an access token for a user
-    pub fn generate_access_token(&self, user: &User, roles: &[Role]) -> Result<String> {
+    /// Generate an access token for a user, optionally for one of their profiles
+    pub fn generate_access_token(
+        &self,
+        user: &User,
+        roles: &[Role],
+        profile_id: Option<Uuid>,
+    ) -> Result<String> {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map_err(|e| anyhow::anyhow!("Failed to get current time: {}", e))?
@@ -162,6 +197,10 @@ impl JwtService {
             iat: now,
             exp: now + self.config.access_token_expiry,
             token_type: TokenType::Access,
+            jti: None,
+            act: None,
+            read_only: false,
+            profile_id,
         };
 
         let token = encode(
@@ -172,8 +211,8 @@ impl JwtService {
         Ok(token)
     }
 
-    /// Generate a refresh token for a user
-    pub fn generate_refresh_token(&self, user: &User) -> Result<String> {
+    /// Generate a refresh token for a user, bound to one of their profiles if any
+    pub fn generate_refresh_token(&self, user: &User, profile_id: Option<Uuid>) -> Result<String> {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map_err(|e| anyhow::anyhow!("Failed to get current time: {}", e))?
@@ -186,6 +225,107 @@ impl JwtService {
             iat: now,
             exp: now + self.config.refresh_token_expiry,
             token_type: TokenType::Refresh,
+            jti: None,
+            act: None,
+            read_only: false,
+            profile_id,
+        };
+
+        let token = encode(
+            &Header::new(jsonwebtoken::Algorithm::RS256),
+            &claims,
+            &self.encoding_key,
+        )?;
+        Ok(token)
+    }
+
+    /// Generate a short-lived access token for an admin acting as another user
+    ///
+    /// The token carries the admin in the `act` claim. No refresh token is ever
+    /// issued alongside it.
+    pub fn generate_impersonation_token(
+        &self,
+        user: &User,
+        roles: &[Role],
+        actor_id: Uuid,
+        read_only: bool,
+    ) -> Result<String> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| anyhow::anyhow!("Failed to get current time: {}", e))?
+            .as_secs();
+
+        let roles_vec: Vec<String> = roles.iter().map(|r| r.name.clone()).collect();
+        let permissions_vec: Vec<String> = roles
+            .iter()
+            .flat_map(|r| r.permissions.keys().cloned())
+            .collect();
+
+        let claims = Claims {
+            sub: user.id,
+            roles: roles_vec,
+            permissions: permissions_vec,
+            iat: now,
+            exp: now + self.config.impersonation_token_expiry,
+            token_type: TokenType::Access,
+            jti: None,
+            act: Some(Actor { sub: actor_id }),
+            read_only,
+            profile_id: None,
+        };
+
+        let token = encode(
+            &Header::new(jsonwebtoken::Algorithm::RS256),
+            &claims,
+            &self.encoding_key,
+        )?;
+        Ok(token)
+    }
+
+    /// Re-issue an access token for another profile of its user
+    ///
+    /// Everything but the profile is kept, including the expiry, so switching
+    /// profiles never extends a token, e.g. one issued for impersonation.
+    pub fn generate_profile_token(
+        &self,
+        claims: &Claims,
+        profile_id: Option<Uuid>,
+    ) -> Result<String> {
+        if claims.token_type != TokenType::Access {
+            return Err(anyhow::anyhow!("Token is not an access token"));
+        }
+
+        let claims = Claims {
+            profile_id,
+            ..claims.clone()
+        };
+
+        let token = encode(
+            &Header::new(jsonwebtoken::Algorithm::RS256),
+            &claims,
+            &self.encoding_key,
+        )?;
+        Ok(token)
+    }
+
+    /// Generate a single-use magic link token for a user
+    pub fn generate_magic_link_token(&self, user: &User, jti: &str, expiry: u64) -> Result<String> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| anyhow::anyhow!("Failed to get current time: {}", e))?
+            .as_secs();
+
+        let claims = Claims {
+            sub: user.id,
+            roles: vec![],
+            permissions: vec![],
+            iat: now,
+            exp: now + expiry,
+            token_type: TokenType::MagicLink,
+            jti: Some(jti.to_string()),
+            act: None,
+            read_only: false,
+            profile_id: None,
         };
 
         let token = encode(
@@ -230,6 +370,11 @@ impl JwtService {
         self.config.access_token_expiry
     }
 
+    /// Get the impersonation token expiry time
+    pub fn impersonation_token_expiry(&self) -> u64 {
+        self.config.impersonation_token_expiry
+    }
+
     /// Get the refresh token expiry time
     pub fn refresh_token_expiry(&self) -> u64 {
         self.config.refresh_token_expiry
@@ -237,12 +382,14 @@ impl JwtService {
 
     /// Rotate a refresh token
     ///
-    /// This function blacklists the old refresh token and generates a new one
+    /// This function blacklists the old refresh token and generates a new one,
+    /// bound to the given profile
     pub async fn rotate_refresh_token(
         &self,
         redis_pool: &super::cache::RedisPool,
         user: &User,
         old_refresh_token: &str,
+        profile_id: Option<Uuid>,
     ) -> Result<String> {
         // Validate the old refresh token
         let claims = self.validate_token(old_refresh_token)?;
@@ -287,7 +434,7 @@ impl JwtService {
             .await?;
 
         // Generate a new refresh token
-        let new_refresh_token = self.generate_refresh_token(user)?;
+        let new_refresh_token = self.generate_refresh_token(user, profile_id)?;
 
         Ok(new_refresh_token)
     }
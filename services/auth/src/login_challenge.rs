@@ -0,0 +1,143 @@
+//! Step-up verification for suspicious logins
+//!
+//! When a login looks suspicious, no tokens are issued. Instead a one-time
+//! code is emailed to the account owner and the login has to be completed by
+//! presenting the code together with the challenge ID.
+
+use anyhow::Result;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{cache::RedisPool, login_risk::LoginContext};
+
+/// Login challenge configuration
+#[derive(Debug, Clone)]
+pub struct LoginChallengeConfig {
+    /// Challenge expiration time in seconds
+    pub expiry: u64,
+    /// Number of wrong codes before the challenge is discarded
+    pub max_attempts: u32,
+}
+
+impl Default for LoginChallengeConfig {
+    fn default() -> Self {
+        Self {
+            expiry: 600, // 10 minutes
+            max_attempts: 5,
+        }
+    }
+}
+
+/// Verify a challenge code atomically
+///
+/// `KEYS[1]` is the challenge and `KEYS[2]` its attempt counter; `ARGV[1]`
+/// is the code and `ARGV[2]` the maximum number of attempts. Returns the
+/// challenge if the code matches, deleting it with its counter. A wrong code
+/// increments the counter, which expires with the challenge, and discards
+/// both once the limit is reached; the challenge itself is never rewritten.
+const VERIFY_SCRIPT: &str = r#"
+local challenge = redis.call('GET', KEYS[1])
+if not challenge then
+    return false
+end
+if cjson.decode(challenge).code == ARGV[1] then
+    redis.call('DEL', KEYS[1], KEYS[2])
+    return challenge
+end
+local attempts = redis.call('INCR', KEYS[2])
+if attempts == 1 then
+    redis.call('PEXPIRE', KEYS[2], math.max(redis.call('PTTL', KEYS[1]), 1))
+end
+if attempts >= tonumber(ARGV[2]) then
+    redis.call('DEL', KEYS[1], KEYS[2])
+end
+return false
+"#;
+
+/// Pending login challenge stored in Redis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginChallenge {
+    pub user_id: Uuid,
+    pub context: LoginContext,
+    code: String,
+    expires_at: u64,
+}
+
+/// Login challenge service
+#[derive(Clone)]
+pub struct LoginChallengeService {
+    redis_pool: RedisPool,
+    config: LoginChallengeConfig,
+}
+
+impl LoginChallengeService {
+    /// Create a new login challenge service
+    pub fn new(redis_pool: RedisPool, config: LoginChallengeConfig) -> Self {
+        Self { redis_pool, config }
+    }
+
+    /// Create a challenge for a login, returning the challenge ID and code
+    pub async fn create(&self, user_id: Uuid, context: LoginContext) -> Result<(String, String)> {
+        info!("Creating login challenge for user: {}", user_id);
+
+        let challenge_id = Uuid::new_v4().to_string();
+        let code = format!("{:06}", rand::thread_rng().gen_range(0..1_000_000));
+
+        let challenge = LoginChallenge {
+            user_id,
+            context,
+            code: code.clone(),
+            expires_at: now()? + self.config.expiry,
+        };
+
+        let key = format!("login_challenge:{}", challenge_id);
+        self.redis_pool
+            .set(
+                &key,
+                &serde_json::to_string(&challenge)?,
+                Some(self.config.expiry),
+            )
+            .await?;
+
+        Ok((challenge_id, code))
+    }
+
+    /// Verify a challenge code, consuming the challenge on success
+    ///
+    /// Wrong codes count towards the attempt limit, after which the challenge
+    /// is discarded and the login has to be restarted.
+    pub async fn verify(&self, challenge_id: &str, code: &str) -> Result<Option<LoginChallenge>> {
+        let key = format!("login_challenge:{}", challenge_id);
+        let attempts_key = format!("login_challenge_attempts:{}", challenge_id);
+        let max_attempts = self.config.max_attempts.to_string();
+
+        let challenge_json: Option<String> = self
+            .redis_pool
+            .eval(
+                VERIFY_SCRIPT,
+                &[&key, &attempts_key],
+                &[code, &max_attempts],
+            )
+            .await?;
+
+        challenge_json
+            .map(|challenge_json| serde_json::from_str(&challenge_json))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    /// Get the challenge expiry time
+    pub fn expiry(&self) -> u64 {
+        self.config.expiry
+    }
+}
+
+fn now() -> Result<u64> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| anyhow::anyhow!("Failed to get current time: {}", e))?
+        .as_secs())
+}
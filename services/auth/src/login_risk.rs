@@ -0,0 +1,129 @@
+//! Login risk assessment based on previously seen devices, IPs and countries
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::net::IpAddr;
+
+use crate::geoip::GeoIpLookup;
+
+/// Where a login attempt comes from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginContext {
+    /// Client IP address
+    pub ip: IpAddr,
+    /// Hash identifying the client device
+    pub device_hash: String,
+    /// Client user agent
+    pub user_agent: Option<String>,
+    /// ISO country code resolved from the IP address, if GeoIP is configured
+    pub country: Option<String>,
+}
+
+impl LoginContext {
+    /// Build the login context for a request
+    ///
+    /// The device is identified by the client-provided device ID when present,
+    /// falling back to the user agent.
+    pub fn new(
+        ip: IpAddr,
+        device_id: Option<&str>,
+        user_agent: Option<&str>,
+        geoip: Option<&dyn GeoIpLookup>,
+    ) -> Self {
+        let device_source = device_id.or(user_agent).unwrap_or_default();
+        let device_hash = Sha256::digest(device_source.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        Self {
+            ip,
+            device_hash,
+            user_agent: user_agent.map(str::to_string),
+            country: geoip.and_then(|geoip| geoip.country(ip)),
+        }
+    }
+}
+
+/// How familiar a login context is for a user
+#[derive(Debug, Clone, Default)]
+pub struct LoginAssessment {
+    /// Whether the user has logged in before
+    pub has_history: bool,
+    /// Whether the device has been used before
+    pub known_device: bool,
+    /// Whether the IP address has been used before
+    pub known_ip: bool,
+    /// Whether the country has been seen before
+    pub known_country: bool,
+}
+
+impl LoginAssessment {
+    /// Get the reasons why a login looks suspicious
+    ///
+    /// The first recorded login is never suspicious. The IP address is only
+    /// used as a signal when the country cannot be resolved, since IPs change
+    /// frequently on mobile networks.
+    pub fn suspicious_reasons(&self, context: &LoginContext) -> Vec<&'static str> {
+        let mut reasons = Vec::new();
+
+        if !self.has_history {
+            return reasons;
+        }
+
+        if !self.known_device {
+            reasons.push("new device");
+        }
+
+        match context.country {
+            Some(_) if !self.known_country => reasons.push("new country"),
+            None if !self.known_ip => reasons.push("new IP address"),
+            _ => {}
+        }
+
+        reasons
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(country: Option<&str>) -> LoginContext {
+        LoginContext {
+            ip: "203.0.113.7".parse().unwrap(),
+            device_hash: "device".to_string(),
+            user_agent: None,
+            country: country.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_suspicious_reasons() {
+        let first_login = LoginAssessment::default();
+        assert!(first_login.suspicious_reasons(&context(None)).is_empty());
+
+        let familiar = LoginAssessment {
+            has_history: true,
+            known_device: true,
+            known_ip: false,
+            known_country: true,
+        };
+        assert!(familiar.suspicious_reasons(&context(Some("CM"))).is_empty());
+        assert_eq!(
+            familiar.suspicious_reasons(&context(None)),
+            vec!["new IP address"]
+        );
+
+        let new_device_and_country = LoginAssessment {
+            has_history: true,
+            known_device: false,
+            known_ip: true,
+            known_country: false,
+        };
+        assert_eq!(
+            new_device_and_country.suspicious_reasons(&context(Some("FR"))),
+            vec!["new device", "new country"]
+        );
+    }
+}
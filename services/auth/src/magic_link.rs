@@ -0,0 +1,119 @@
+//! Passwordless login through single-use magic links
+//!
+//! A magic link carries a short-lived signed token. The token's identifier is
+//! also stored in Redis with the same lifetime and is removed atomically when
+//! the link is redeemed, so each link can only be used once and stops working
+//! as soon as the Redis key expires.
+
+use anyhow::Result;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    cache::RedisPool,
+    jwt::{JwtService, TokenType},
+    models::User,
+};
+
+/// Magic link configuration
+#[derive(Debug, Clone)]
+pub struct MagicLinkConfig {
+    /// URL the token is appended to as the `token` query parameter
+    pub base_url: String,
+    /// Link expiration time in seconds (default: 15 minutes)
+    pub expiry: u64,
+}
+
+impl MagicLinkConfig {
+    /// Create a new MagicLinkConfig from environment variables
+    ///
+    /// # Environment Variables
+    /// - `MAGIC_LINK_BASE_URL`: URL the token is appended to (default: "http://localhost:3000/auth/magic-link/verify")
+    /// - `MAGIC_LINK_EXPIRY`: Link expiry in seconds (default: 900)
+    pub fn from_env() -> Result<Self> {
+        let base_url = std::env::var("MAGIC_LINK_BASE_URL")
+            .unwrap_or_else(|_| "http://localhost:3000/auth/magic-link/verify".to_string());
+
+        let expiry = std::env::var("MAGIC_LINK_EXPIRY")
+            .unwrap_or_else(|_| "900".to_string()) // 15 minutes
+            .parse()
+            .unwrap_or(900);
+
+        Ok(MagicLinkConfig { base_url, expiry })
+    }
+}
+
+/// Magic link service for issuing and redeeming login links
+#[derive(Clone)]
+pub struct MagicLinkService {
+    redis_pool: RedisPool,
+    jwt_service: JwtService,
+    config: MagicLinkConfig,
+}
+
+impl MagicLinkService {
+    /// Create a new magic link service
+    pub fn new(redis_pool: RedisPool, jwt_service: JwtService, config: MagicLinkConfig) -> Self {
+        Self {
+            redis_pool,
+            jwt_service,
+            config,
+        }
+    }
+
+    /// Issue a magic link for a user and return its URL
+    pub async fn issue(&self, user: &User) -> Result<String> {
+        info!("Issuing magic link for user: {}", user.id);
+
+        let jti = Uuid::new_v4().to_string();
+        let token = self
+            .jwt_service
+            .generate_magic_link_token(user, &jti, self.config.expiry)?;
+
+        let key = format!("magic_link:{}", jti);
+        self.redis_pool
+            .set(&key, &user.id.to_string(), Some(self.config.expiry))
+            .await?;
+
+        let separator = if self.config.base_url.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
+
+        Ok(format!(
+            "{}{}token={}",
+            self.config.base_url, separator, token
+        ))
+    }
+
+    /// Redeem a magic link token, returning the user ID if it is valid
+    ///
+    /// The token is consumed; any later attempt to redeem it fails.
+    pub async fn redeem(&self, token: &str) -> Result<Option<Uuid>> {
+        let Ok(claims) = self.jwt_service.validate_token(token) else {
+            return Ok(None);
+        };
+
+        if claims.token_type != TokenType::MagicLink {
+            return Ok(None);
+        }
+
+        let Some(jti) = claims.jti else {
+            return Ok(None);
+        };
+
+        let key = format!("magic_link:{}", jti);
+        let stored_user_id = self.redis_pool.get_and_delete(&key).await?;
+
+        match stored_user_id {
+            Some(user_id) if user_id == claims.sub.to_string() => Ok(Some(claims.sub)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Get the link expiry time
+    pub fn expiry(&self) -> u64 {
+        self.config.expiry
+    }
+}
@@ -0,0 +1,135 @@
+//! Outgoing email abstraction
+//!
+//! Handlers send email through the [`Mailer`] trait so the delivery mechanism
+//! can be swapped per environment. The file sink writes every message to a
+//! local directory, which makes sent mail easy to inspect in development and
+//! tests.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+/// Outgoing email message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Email delivery backend
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    /// Send an email
+    async fn send(&self, email: &Email) -> Result<()>;
+}
+
+/// Mailer that writes each email as a JSON file into a directory
+#[derive(Debug, Clone)]
+pub struct FileMailer {
+    directory: PathBuf,
+}
+
+impl FileMailer {
+    /// Create a new file mailer, creating the directory if needed
+    pub fn new(directory: impl Into<PathBuf>) -> Result<Self> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)?;
+        Ok(Self { directory })
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: &Email) -> Result<()> {
+        let file_name = format!(
+            "{}-{}.json",
+            chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f"),
+            Uuid::new_v4()
+        );
+        let path = self.directory.join(file_name);
+
+        tokio::fs::write(&path, serde_json::to_vec_pretty(email)?).await?;
+        info!("Wrote email to {} into {}", email.to, path.display());
+
+        Ok(())
+    }
+}
+
+/// Mailer that only logs emails, used when no delivery is configured
+#[derive(Debug, Clone, Default)]
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: &Email) -> Result<()> {
+        info!(
+            "Email to {} not delivered (no mailer configured): {}",
+            email.to, email.subject
+        );
+        Ok(())
+    }
+}
+
+/// Mailer configuration
+#[derive(Debug, Clone, Default)]
+pub struct MailerConfig {
+    /// Directory to write emails into; emails are only logged when unset
+    pub file_sink_dir: Option<PathBuf>,
+}
+
+impl MailerConfig {
+    /// Create a new MailerConfig from environment variables
+    ///
+    /// # Environment Variables
+    /// - `MAILER_FILE_SINK_DIR`: Directory to write emails into (optional)
+    pub fn from_env() -> Result<Self> {
+        let file_sink_dir = std::env::var("MAILER_FILE_SINK_DIR")
+            .ok()
+            .map(PathBuf::from);
+
+        Ok(MailerConfig { file_sink_dir })
+    }
+
+    /// Build the configured mailer
+    pub fn build(&self) -> Result<Arc<dyn Mailer>> {
+        match &self.file_sink_dir {
+            Some(directory) => Ok(Arc::new(FileMailer::new(directory)?)),
+            None => Ok(Arc::new(LogMailer)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_mailer_writes_email() {
+        let directory = std::env::temp_dir().join(format!("mailer-{}", Uuid::new_v4()));
+        let mailer = FileMailer::new(&directory).unwrap();
+
+        let email = Email {
+            to: "viewer@example.com".to_string(),
+            subject: "Hello".to_string(),
+            body: "World".to_string(),
+        };
+        mailer.send(&email).await.unwrap();
+
+        let entries: Vec<_> = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(entries.len(), 1);
+
+        let sent: Email = serde_json::from_slice(&std::fs::read(&entries[0]).unwrap()).unwrap();
+        assert_eq!(sent.to, email.to);
+        assert_eq!(sent.subject, email.subject);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -2,12 +2,20 @@ use anyhow::Result;
 use tracing::{Level, info};
 use tracing_subscriber::FmtSubscriber;
 
+mod account_lockout;
+mod breached_passwords;
 mod cache;
 mod database;
+mod geoip;
 mod jwt;
+mod login_challenge;
+mod login_risk;
+mod magic_link;
+mod mailer;
 mod middleware;
 mod models;
 mod oauth;
+mod password;
 mod rate_limiter;
 mod repositories;
 mod routes;
@@ -16,6 +24,8 @@ mod validation;
 
 use axum::Router;
 use sqlx::PgPool;
+use std::net::SocketAddr;
+use std::sync::Arc;
 use tokio::net::TcpListener;
 
 use crate::{cache::RedisPool, jwt::JwtService};
@@ -27,8 +37,20 @@ pub struct AppState {
     pub redis_pool: RedisPool,
     pub jwt_service: JwtService,
     pub user_repository: crate::repositories::UserRepository,
+    pub audit_repository: crate::repositories::AuditRepository,
+    pub profile_repository: crate::repositories::ProfileRepository,
+    pub password_policy: crate::validation::PasswordPolicy,
+    pub breached_password_checker:
+        Option<Arc<dyn crate::breached_passwords::BreachedPasswordChecker>>,
     pub rate_limiter: crate::rate_limiter::RateLimiter,
+    pub account_lockout: crate::account_lockout::AccountLockout,
+    pub login_device_repository: crate::repositories::LoginDeviceRepository,
+    pub login_challenge_service: crate::login_challenge::LoginChallengeService,
+    pub geoip: Option<Arc<dyn crate::geoip::GeoIpLookup>>,
     pub session_manager: crate::session::SessionManager,
+    pub magic_link_service: crate::magic_link::MagicLinkService,
+    pub magic_link_rate_limiter: crate::rate_limiter::RateLimiter,
+    pub mailer: Arc<dyn crate::mailer::Mailer>,
     pub google_oauth_client: Option<crate::oauth::OAuthClient>,
     pub apple_oauth_client: Option<crate::oauth::OAuthClient>,
 }
@@ -65,12 +87,61 @@ async fn main() -> Result<()> {
     let redis_config = cache::RedisConfig::from_env()?;
     let redis_pool = cache::RedisPool::new(&redis_config).await?;
 
-    let user_repository = crate::repositories::UserRepository::new(pool.clone());
+    // Initialize password hasher
+    let argon2_config = crate::password::Argon2Config::from_env()?;
+    let password_hasher = crate::password::PasswordHasher::new(&argon2_config)?;
+
+    let user_repository = crate::repositories::UserRepository::new(pool.clone(), password_hasher);
+
+    // Password checks only look for an empty hash, so older OAuth accounts
+    // must not keep the hash of an empty password
+    let cleared = user_repository.clear_legacy_empty_passwords().await?;
+    if cleared > 0 {
+        info!(
+            "Cleared empty password hashes of {} OAuth accounts",
+            cleared
+        );
+    }
+    let audit_repository = crate::repositories::AuditRepository::new(pool.clone());
+    let profile_repository = crate::repositories::ProfileRepository::new(pool.clone());
+    let password_policy = crate::validation::PasswordPolicy::from_env();
+    let breached_password_checker =
+        crate::breached_passwords::RangeFileChecker::from_env()?.map(|checker| {
+            Arc::new(checker) as Arc<dyn crate::breached_passwords::BreachedPasswordChecker>
+        });
+
     let rate_limiter =
         crate::rate_limiter::RateLimiter::new(crate::rate_limiter::RateLimiterConfig::default());
 
+    let account_lockout = crate::account_lockout::AccountLockout::new(
+        crate::account_lockout::AccountLockoutConfig::from_env(),
+    );
+    let login_device_repository = crate::repositories::LoginDeviceRepository::new(pool.clone());
+    let login_challenge_service = crate::login_challenge::LoginChallengeService::new(
+        redis_pool.clone(),
+        crate::login_challenge::LoginChallengeConfig::default(),
+    );
+    let geoip = crate::geoip::MaxMindGeoIp::from_env()?
+        .map(|geoip| Arc::new(geoip) as Arc<dyn crate::geoip::GeoIpLookup>);
+
     let session_manager =
         crate::session::SessionManager::new(redis_pool.clone(), jwt_service.clone());
+
+    let magic_link_config = crate::magic_link::MagicLinkConfig::from_env()?;
+    let magic_link_service = crate::magic_link::MagicLinkService::new(
+        redis_pool.clone(),
+        jwt_service.clone(),
+        magic_link_config,
+    );
+    let magic_link_rate_limiter =
+        crate::rate_limiter::RateLimiter::new(crate::rate_limiter::RateLimiterConfig {
+            max_attempts: 3,
+            window_seconds: 900,        // 15 minutes
+            ban_duration_seconds: 3600, // 1 hour
+        });
+
+    let mailer = crate::mailer::MailerConfig::from_env()?.build()?;
+
     let google_oauth_client = None;
     let apple_oauth_client = None;
 
@@ -79,8 +150,19 @@ async fn main() -> Result<()> {
         redis_pool,
         jwt_service,
         user_repository,
+        audit_repository,
+        profile_repository,
+        password_policy,
+        breached_password_checker,
         rate_limiter,
+        account_lockout,
+        login_device_repository,
+        login_challenge_service,
+        geoip,
         session_manager,
+        magic_link_service,
+        magic_link_rate_limiter,
+        mailer,
         google_oauth_client,
         apple_oauth_client,
     };
@@ -91,7 +173,11 @@ async fn main() -> Result<()> {
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
     info!("Authentication service listening on 0.0.0.0:3000");
 
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
@@ -10,7 +10,11 @@ use axum::{
 };
 use tracing::{error, info};
 
-use crate::{AppState, jwt::JwtService, models::User};
+use crate::{
+    AppState,
+    jwt::{JwtService, TokenType},
+    models::User,
+};
 
 /// Extract and validate JWT token from Authorization header
 pub async fn auth_middleware(
@@ -39,6 +43,11 @@ pub async fn auth_middleware(
         StatusCode::UNAUTHORIZED
     })?;
 
+    // Only access tokens may be used to call protected endpoints
+    if claims.token_type != TokenType::Access {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
     // Check if the token is blacklisted
     let is_blacklisted = state
         .jwt_service
@@ -53,8 +62,9 @@ pub async fn auth_middleware(
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    // Add user ID to request extensions for use in handlers
+    // Add user ID and claims to request extensions for use in handlers
     req.extensions_mut().insert(claims.sub);
+    req.extensions_mut().insert(claims);
 
     // Continue with the request
     Ok(next.run(req).await)
@@ -0,0 +1,225 @@
+//! Password hashing with configurable Argon2 parameters
+//!
+//! Hashes are produced with Argon2id using the configured cost parameters.
+//! Verification works for any Argon2 hash regardless of the parameters it was
+//! created with, which allows stored hashes to be upgraded transparently on
+//! the next successful login.
+
+use anyhow::Result;
+use argon2::{
+    Algorithm, Argon2, Params, Version,
+    password_hash::{PasswordHash, PasswordHasher as _, PasswordVerifier as _, SaltString},
+};
+use uuid::Uuid;
+
+/// Argon2 configuration
+#[derive(Debug, Clone)]
+pub struct Argon2Config {
+    /// Memory cost in KiB
+    pub memory_cost: u32,
+    /// Number of iterations
+    pub time_cost: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl Default for Argon2Config {
+    fn default() -> Self {
+        Self {
+            memory_cost: Params::DEFAULT_M_COST, // 19 MiB
+            time_cost: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl Argon2Config {
+    /// Create a new Argon2Config from environment variables
+    ///
+    /// # Environment Variables
+    /// - `ARGON2_MEMORY_COST`: Memory cost in KiB (default: 19456)
+    /// - `ARGON2_TIME_COST`: Number of iterations (default: 2)
+    /// - `ARGON2_PARALLELISM`: Degree of parallelism (default: 1)
+    pub fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+
+        let memory_cost = std::env::var("ARGON2_MEMORY_COST")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(defaults.memory_cost);
+
+        let time_cost = std::env::var("ARGON2_TIME_COST")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(defaults.time_cost);
+
+        let parallelism = std::env::var("ARGON2_PARALLELISM")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(defaults.parallelism);
+
+        Ok(Argon2Config {
+            memory_cost,
+            time_cost,
+            parallelism,
+        })
+    }
+}
+
+/// Password hasher
+#[derive(Debug, Clone)]
+pub struct PasswordHasher {
+    params: Params,
+    /// Hash of a random password, verified against when there is no user
+    /// so that unknown accounts take as long as known ones
+    dummy_hash: String,
+}
+
+impl PasswordHasher {
+    /// Create a new password hasher, validating the configured parameters
+    pub fn new(config: &Argon2Config) -> Result<Self> {
+        let params = Params::new(
+            config.memory_cost,
+            config.time_cost,
+            config.parallelism,
+            None,
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid Argon2 parameters: {}", e))?;
+
+        let mut hasher = Self {
+            params,
+            dummy_hash: String::new(),
+        };
+        hasher.dummy_hash = hasher.hash(&Uuid::new_v4().to_string())?;
+
+        Ok(hasher)
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+
+    /// Hash a password with the configured parameters
+    pub fn hash(&self, password: &str) -> Result<String> {
+        let salt = SaltString::generate(&mut rand::thread_rng());
+        let password_hash = self
+            .argon2()
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| anyhow::anyhow!("Failed to hash password: {}", e))?
+            .to_string();
+
+        Ok(password_hash)
+    }
+
+    /// Verify a password against a stored hash
+    ///
+    /// The parameters encoded in the stored hash are used, so hashes created
+    /// with older parameters still verify.
+    pub fn verify(&self, password_hash: &str, password: &str) -> Result<bool> {
+        let parsed_hash = PasswordHash::new(password_hash)
+            .map_err(|e| anyhow::anyhow!("Failed to parse password hash: {}", e))?;
+
+        let result = self
+            .argon2()
+            .verify_password(password.as_bytes(), &parsed_hash);
+
+        Ok(result.is_ok())
+    }
+
+    /// Verify a password against a hash no password matches
+    ///
+    /// Takes as long as [`verify`](Self::verify) for a real account, so that
+    /// rejecting unknown or locked accounts does not reveal them by timing.
+    pub fn verify_dummy(&self, password: &str) {
+        let _ = self.verify(&self.dummy_hash, password);
+    }
+
+    /// Check whether a stored hash was created with outdated parameters
+    pub fn needs_rehash(&self, password_hash: &str) -> bool {
+        let Ok(parsed_hash) = PasswordHash::new(password_hash) else {
+            return true;
+        };
+
+        if Algorithm::try_from(parsed_hash.algorithm) != Ok(Algorithm::Argon2id) {
+            return true;
+        }
+
+        if parsed_hash.version != Some(Version::V0x13.into()) {
+            return true;
+        }
+
+        match Params::try_from(&parsed_hash) {
+            Ok(params) => {
+                params.m_cost() != self.params.m_cost()
+                    || params.t_cost() != self.params.t_cost()
+                    || params.p_cost() != self.params.p_cost()
+            }
+            Err(_) => true,
+        }
+    }
+
+    /// Check whether a stored hash allows password login at all
+    ///
+    /// Accounts created through OAuth are stored with an empty hash.
+    pub fn has_password(&self, password_hash: &str) -> bool {
+        !password_hash.is_empty()
+    }
+
+    /// Check whether a stored hash is the hash of an empty password, as
+    /// older OAuth accounts were stored with
+    pub fn is_empty_password(&self, password_hash: &str) -> bool {
+        self.has_password(password_hash) && self.verify(password_hash, "").unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cheap_config(time_cost: u32) -> Argon2Config {
+        Argon2Config {
+            memory_cost: 1024,
+            time_cost,
+            parallelism: 1,
+        }
+    }
+
+    #[test]
+    fn test_hash_and_verify() {
+        let hasher = PasswordHasher::new(&cheap_config(1)).unwrap();
+        let hash = hasher.hash("Secret123!").unwrap();
+
+        assert!(hasher.verify(&hash, "Secret123!").unwrap());
+        assert!(!hasher.verify(&hash, "secret123!").unwrap());
+        assert!(!hasher.needs_rehash(&hash));
+    }
+
+    #[test]
+    fn test_needs_rehash_with_outdated_params() {
+        let old_hasher = PasswordHasher::new(&cheap_config(1)).unwrap();
+        let new_hasher = PasswordHasher::new(&cheap_config(2)).unwrap();
+        let hash = old_hasher.hash("Secret123!").unwrap();
+
+        assert!(new_hasher.needs_rehash(&hash));
+        assert!(new_hasher.verify(&hash, "Secret123!").unwrap());
+    }
+
+    #[test]
+    fn test_has_password() {
+        let hasher = PasswordHasher::new(&cheap_config(1)).unwrap();
+
+        assert!(!hasher.has_password(""));
+        assert!(hasher.has_password(&hasher.hash("Secret123!").unwrap()));
+        assert!(hasher.is_empty_password(&hasher.hash("").unwrap()));
+        assert!(!hasher.is_empty_password(&hasher.hash("Secret123!").unwrap()));
+        assert!(!hasher.is_empty_password(""));
+    }
+
+    #[test]
+    fn test_dummy_hash_uses_configured_params() {
+        let hasher = PasswordHasher::new(&cheap_config(1)).unwrap();
+
+        assert!(!hasher.needs_rehash(&hasher.dummy_hash));
+        assert!(!hasher.verify(&hasher.dummy_hash, "").unwrap());
+    }
+}
@@ -0,0 +1,59 @@
+//! Audit repository for recording security-relevant actions
+
+use anyhow::Result;
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+/// Audit event to record
+#[derive(Debug, Clone)]
+pub struct NewAuditEvent {
+    /// User who performed the action
+    pub actor_id: Uuid,
+    /// Action name, e.g. `impersonation.start`
+    pub action: String,
+    /// User affected by the action
+    pub target_user_id: Option<Uuid>,
+    /// Additional details
+    pub details: serde_json::Value,
+    /// Client IP address
+    pub ip_address: Option<String>,
+}
+
+/// Audit repository
+#[derive(Clone)]
+pub struct AuditRepository {
+    pool: PgPool,
+}
+
+impl AuditRepository {
+    /// Create a new audit repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Record an audit event
+    pub async fn record(&self, event: &NewAuditEvent) -> Result<Uuid> {
+        info!(
+            "Audit: {} by {} (target: {:?})",
+            event.action, event.actor_id, event.target_user_id
+        );
+
+        let id = sqlx::query_scalar(
+            r#"
+            INSERT INTO audit_events (actor_id, action, target_user_id, details, ip_address)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id
+            "#,
+        )
+        .bind(event.actor_id)
+        .bind(&event.action)
+        .bind(event.target_user_id)
+        .bind(&event.details)
+        .bind(&event.ip_address)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+}
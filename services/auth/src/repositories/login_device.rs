@@ -0,0 +1,73 @@
+//! Login device repository for database operations
+
+use anyhow::Result;
+use sqlx::{PgPool, Row};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::login_risk::{LoginAssessment, LoginContext};
+
+/// Login device repository
+#[derive(Clone)]
+pub struct LoginDeviceRepository {
+    pool: PgPool,
+}
+
+impl LoginDeviceRepository {
+    /// Create a new login device repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Compare a login context against the user's previous logins
+    pub async fn assess(&self, user_id: Uuid, context: &LoginContext) -> Result<LoginAssessment> {
+        let row = sqlx::query(
+            r#"
+            SELECT COUNT(*) > 0 AS has_history,
+                   COALESCE(BOOL_OR(device_hash = $2), false) AS known_device,
+                   COALESCE(BOOL_OR(ip_address = $3), false) AS known_ip,
+                   COALESCE(BOOL_OR(country = $4), false) AS known_country
+            FROM known_login_devices
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(&context.device_hash)
+        .bind(context.ip.to_string())
+        .bind(&context.country)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(LoginAssessment {
+            has_history: row.get("has_history"),
+            known_device: row.get("known_device"),
+            known_ip: row.get("known_ip"),
+            known_country: row.get("known_country"),
+        })
+    }
+
+    /// Remember a login context as familiar for the user
+    pub async fn record(&self, user_id: Uuid, context: &LoginContext) -> Result<()> {
+        info!("Recording login device for user: {}", user_id);
+
+        sqlx::query(
+            r#"
+            INSERT INTO known_login_devices (user_id, device_hash, ip_address, country, user_agent)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (user_id, device_hash, ip_address) DO UPDATE SET
+                country = EXCLUDED.country,
+                user_agent = EXCLUDED.user_agent,
+                last_seen_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(&context.device_hash)
+        .bind(context.ip.to_string())
+        .bind(&context.country)
+        .bind(&context.user_agent)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,6 +1,12 @@
 //! Repositories module
 
+pub mod audit;
+pub mod login_device;
+pub mod profile;
 pub mod user;
 
 // Re-export for convenience
+pub use audit::{AuditRepository, NewAuditEvent};
+pub use login_device::LoginDeviceRepository;
+pub use profile::ProfileRepository;
 pub use user::UserRepository;
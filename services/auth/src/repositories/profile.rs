@@ -0,0 +1,192 @@
+//! Profile repository for database operations
+
+use anyhow::Result;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHash, PasswordVerifier as _},
+};
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+/// Maturity levels from the most to the least restricted, as stored
+const MATURITY_LEVELS: [&str; 4] = ["all", "kids", "teen", "adult"];
+
+/// Failed PIN attempts allowed before the PIN is locked, as in the API service
+const MAX_PIN_FAILURES: i32 = 5;
+
+/// Minutes the PIN stays locked after too many failed attempts, as in the API service
+const PIN_LOCKOUT_MINUTES: i32 = 15;
+
+/// Profile repository
+#[derive(Clone)]
+pub struct ProfileRepository {
+    pool: PgPool,
+}
+
+impl ProfileRepository {
+    /// Create a new profile repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Check whether a viewer profile belongs to a user
+    pub async fn belongs_to(&self, profile_id: Uuid, user_id: Uuid) -> Result<bool> {
+        let exists = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM profiles WHERE id = $1 AND user_id = $2)",
+        )
+        .bind(profile_id)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+
+    /// Get the maximum maturity rating of a token for the user and profile
+    ///
+    /// Profile tokens are limited by the profile's maturity level, account
+    /// tokens by the account's maximum rating; `None` is unrestricted. A
+    /// deleted profile is limited to media for all ages, like in the API.
+    pub async fn max_rating(
+        &self,
+        user_id: Uuid,
+        profile_id: Option<Uuid>,
+    ) -> Result<Option<String>> {
+        let rating: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT CASE
+                WHEN $2::uuid IS NULL THEN u.max_maturity_rating
+                ELSE COALESCE(
+                    (SELECT maturity_level FROM profiles WHERE id = $2 AND user_id = u.id),
+                    'all'
+                )
+            END
+            FROM users u
+            WHERE u.id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(profile_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .flatten();
+
+        Ok(rating)
+    }
+
+    /// Check the parental control PIN of an account
+    ///
+    /// Passes if the account has no PIN. Wrong PINs count towards the same
+    /// lockout as in the API service.
+    pub async fn check_pin(&self, user_id: Uuid, pin: Option<&str>) -> Result<(), PinError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Serialize attempts so failures are counted exactly
+        let row = sqlx::query(
+            "SELECT parental_pin_hash, parental_pin_locked_until > NOW() AS locked
+             FROM users WHERE id = $1 FOR UPDATE",
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(());
+        };
+        let Some(pin_hash) = row.get::<Option<String>, _>("parental_pin_hash") else {
+            return Ok(());
+        };
+        if row.get::<Option<bool>, _>("locked").unwrap_or(false) {
+            return Err(PinError::Locked);
+        }
+        let Some(pin) = pin else {
+            return Err(PinError::Required);
+        };
+
+        let parsed_hash = PasswordHash::new(&pin_hash)
+            .map_err(|e| anyhow::anyhow!("Failed to parse PIN hash: {}", e))?;
+        if Argon2::default()
+            .verify_password(pin.as_bytes(), &parsed_hash)
+            .is_ok()
+        {
+            sqlx::query(
+                "UPDATE users SET parental_pin_failures = 0, parental_pin_locked_until = NULL
+                 WHERE id = $1",
+            )
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE users SET
+                parental_pin_failures = CASE
+                    WHEN parental_pin_failures + 1 >= $2 THEN 0
+                    ELSE parental_pin_failures + 1
+                END,
+                parental_pin_locked_until = CASE
+                    WHEN parental_pin_failures + 1 >= $2 THEN NOW() + make_interval(mins => $3)
+                    ELSE parental_pin_locked_until
+                END
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(MAX_PIN_FAILURES)
+        .bind(PIN_LOCKOUT_MINUTES)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Err(PinError::Invalid)
+    }
+}
+
+/// Check whether a maturity limit lets more through than another
+///
+/// `None` is unrestricted, the same as `adult`.
+pub fn is_less_restrictive(target: Option<&str>, current: Option<&str>) -> bool {
+    let rank = |rating: Option<&str>| {
+        rating
+            .and_then(|rating| MATURITY_LEVELS.iter().position(|level| *level == rating))
+            .unwrap_or(MATURITY_LEVELS.len() - 1)
+    };
+    rank(target) > rank(current)
+}
+
+/// Errors checking a parental control PIN
+#[derive(Debug, thiserror::Error)]
+pub enum PinError {
+    /// The account has a PIN but none was given
+    #[error("PIN required")]
+    Required,
+    /// The given PIN is wrong
+    #[error("Invalid PIN")]
+    Invalid,
+    /// Too many wrong PINs were given recently
+    #[error("PIN locked")]
+    Locked,
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Hash(#[from] anyhow::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_less_restrictive() {
+        // A kids profile switching to an unrestricted account
+        assert!(is_less_restrictive(None, Some("kids")));
+        assert!(is_less_restrictive(Some("adult"), Some("teen")));
+        assert!(is_less_restrictive(Some("teen"), Some("kids")));
+        assert!(!is_less_restrictive(Some("kids"), Some("kids")));
+        assert!(!is_less_restrictive(Some("kids"), None));
+        assert!(!is_less_restrictive(None, Some("adult")));
+    }
+}
@@ -1,23 +1,28 @@
 //! User repository for database operations
 
 use anyhow::Result;
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier, password_hash::SaltString};
-use sqlx::{PgPool, Row};
+use sqlx::{PgPool, Row, types::Json};
+use std::collections::HashMap;
 use tracing::info;
 use uuid::Uuid;
 
-use crate::models::{LoginCredentials, NewUser, User};
+use crate::models::{LoginCredentials, NewUser, Role, User};
+use crate::password::PasswordHasher;
 
 /// User repository
 #[derive(Clone)]
 pub struct UserRepository {
     pool: PgPool,
+    password_hasher: PasswordHasher,
 }
 
 impl UserRepository {
     /// Create a new user repository
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pool: PgPool, password_hasher: PasswordHasher) -> Self {
+        Self {
+            pool,
+            password_hasher,
+        }
     }
 
     /// Create a new user
@@ -25,13 +30,23 @@ impl UserRepository {
         info!("Creating new user: {}", new_user.username);
 
         // Hash the password
-        let salt = SaltString::generate(&mut rand::thread_rng());
-        let argon2 = Argon2::default();
-        let password_hash = argon2
-            .hash_password(new_user.password_hash.as_bytes(), &salt)
-            .map_err(|e| anyhow::anyhow!("Failed to hash password: {}", e))?
-            .to_string();
+        let password_hash = self.password_hasher.hash(&new_user.password_hash)?;
+
+        self.insert(&new_user.username, &new_user.email, &password_hash)
+            .await
+    }
+
+    /// Create a new user without a password (e.g. signed up through OAuth)
+    ///
+    /// The stored hash is left empty, so password login is always rejected
+    /// for this account.
+    pub async fn create_without_password(&self, username: &str, email: &str) -> Result<User> {
+        info!("Creating new passwordless user: {}", username);
+
+        self.insert(username, email, "").await
+    }
 
+    async fn insert(&self, username: &str, email: &str, password_hash: &str) -> Result<User> {
         let row = sqlx::query(
             r#"
             INSERT INTO users (username, email, password_hash)
@@ -39,9 +54,9 @@ impl UserRepository {
             RETURNING id, username, email, password_hash, created_at, updated_at
             "#,
         )
-        .bind(&new_user.username)
-        .bind(&new_user.email)
-        .bind(&password_hash)
+        .bind(username)
+        .bind(email)
+        .bind(password_hash)
         .fetch_one(&self.pool)
         .await?;
 
@@ -88,15 +103,113 @@ impl UserRepository {
         }
     }
 
+    /// Find the roles assigned to a user
+    pub async fn find_roles(&self, user_id: Uuid) -> Result<Vec<Role>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT r.id, r.name, r.permissions, r.created_at, r.updated_at
+            FROM roles r
+            INNER JOIN user_roles ur ON ur.role_id = r.id
+            WHERE ur.user_id = $1
+            ORDER BY r.name
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let roles = rows
+            .into_iter()
+            .map(|row| {
+                let permissions: Json<HashMap<String, bool>> = row.get("permissions");
+                Role {
+                    id: row.get("id"),
+                    name: row.get("name"),
+                    permissions: permissions.0,
+                    created_at: row.get("created_at"),
+                    updated_at: row.get("updated_at"),
+                }
+            })
+            .collect();
+
+        Ok(roles)
+    }
+
     /// Verify a user's password
     pub async fn verify_password(&self, user: &User, password: &str) -> Result<bool> {
-        let parsed_hash = PasswordHash::new(&user.password_hash)
-            .map_err(|e| anyhow::anyhow!("Failed to parse password hash: {}", e))?;
+        self.password_hasher.verify(&user.password_hash, password)
+    }
 
-        let argon2 = Argon2::default();
-        let result = argon2.verify_password(password.as_bytes(), &parsed_hash);
+    /// Spend as long as verifying a password without a user to check it for
+    pub fn verify_dummy_password(&self, password: &str) {
+        self.password_hasher.verify_dummy(password);
+    }
+
+    /// Check whether the user can log in with a password at all
+    pub fn has_password(&self, user: &User) -> bool {
+        self.password_hasher.has_password(&user.password_hash)
+    }
+
+    /// Clear the password hashes older OAuth accounts were created with
+    ///
+    /// Those accounts were named after their provider and stored with the
+    /// hash of an empty password; clearing it lets `has_password` check for
+    /// an empty hash only. Returns how many accounts were cleared.
+    pub async fn clear_legacy_empty_passwords(&self) -> Result<u64> {
+        let rows = sqlx::query(
+            "SELECT id, password_hash FROM users
+             WHERE password_hash <> '' AND username ~ '^(google|apple)_'",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut cleared = 0;
+        for row in rows {
+            let password_hash: String = row.get("password_hash");
+            if !self.password_hasher.is_empty_password(&password_hash) {
+                continue;
+            }
+
+            cleared += sqlx::query(
+                "UPDATE users SET password_hash = '' WHERE id = $1 AND password_hash = $2",
+            )
+            .bind(row.get::<Uuid, _>("id"))
+            .bind(&password_hash)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        }
+
+        Ok(cleared)
+    }
+
+    /// Check whether the user's password hash uses outdated parameters
+    pub fn needs_rehash(&self, user: &User) -> bool {
+        self.password_hasher.needs_rehash(&user.password_hash)
+    }
+
+    /// Rehash a user's password with the current parameters
+    ///
+    /// Must only be called with a password that was just verified.
+    pub async fn rehash_password(&self, user: &User, password: &str) -> Result<()> {
+        info!("Upgrading password hash for user: {}", user.id);
+
+        let password_hash = self.password_hasher.hash(password)?;
+
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET password_hash = $1
+            WHERE id = $2 AND password_hash = $3
+            "#,
+        )
+        .bind(&password_hash)
+        .bind(user.id)
+        .bind(&user.password_hash)
+        .execute(&self.pool)
+        .await?;
 
-        Ok(result.is_ok())
+        Ok(())
     }
 
     /// Find a user by ID
@@ -129,4 +242,108 @@ impl UserRepository {
             None => Ok(None),
         }
     }
+
+    /// Check whether a password matches the user's current or recent passwords
+    pub async fn is_password_reused(
+        &self,
+        user: &User,
+        password: &str,
+        history_size: u32,
+    ) -> Result<bool> {
+        if self.password_hasher.has_password(&user.password_hash)
+            && self.password_hasher.verify(&user.password_hash, password)?
+        {
+            return Ok(true);
+        }
+
+        if history_size == 0 {
+            return Ok(false);
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT password_hash
+            FROM password_history
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(user.id)
+        .bind(history_size as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for row in rows {
+            let password_hash: String = row.get("password_hash");
+            if self.password_hasher.verify(&password_hash, password)? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Change a user's password, recording the previous hash in the history
+    ///
+    /// Only the most recent `history_size` previous hashes are kept.
+    pub async fn update_password(
+        &self,
+        user: &User,
+        new_password: &str,
+        history_size: u32,
+    ) -> Result<()> {
+        info!("Updating password for user: {}", user.id);
+
+        let password_hash = self.password_hasher.hash(new_password)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        if !user.password_hash.is_empty() && history_size > 0 {
+            sqlx::query(
+                r#"
+                INSERT INTO password_history (user_id, password_hash)
+                VALUES ($1, $2)
+                "#,
+            )
+            .bind(user.id)
+            .bind(&user.password_hash)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET password_hash = $1
+            WHERE id = $2
+            "#,
+        )
+        .bind(&password_hash)
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM password_history
+            WHERE user_id = $1
+              AND id NOT IN (
+                  SELECT id
+                  FROM password_history
+                  WHERE user_id = $1
+                  ORDER BY created_at DESC
+                  LIMIT $2
+              )
+            "#,
+        )
+        .bind(user.id)
+        .bind(history_size as i64)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
 }
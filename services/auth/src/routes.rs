@@ -3,25 +3,37 @@
 use anyhow::Result;
 use axum::{
     Json, Router,
-    extract::{ConnectInfo, State},
-    http::StatusCode,
+    extract::{ConnectInfo, Extension, State},
+    http::{HeaderMap, StatusCode, header},
+    middleware,
     response::{IntoResponse, Response},
     routing::{get, post},
 };
 use oauth2::TokenResponse;
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use tracing::{error, info};
+use uuid::Uuid;
 
 use crate::{
     AppState,
     jwt::Claims,
-    models::{LoginCredentials, NewUser, User},
+    login_risk::LoginContext,
+    mailer::Email,
+    middleware::auth_middleware,
+    models::{LoginCredentials, NewUser, Role, User},
     oauth::OAuthProvider,
     rate_limiter::RateLimiter,
-    repositories::UserRepository,
+    repositories::{
+        NewAuditEvent, UserRepository,
+        profile::{PinError, is_less_restrictive},
+    },
     validation,
 };
 
+/// Name of the role allowed to use admin endpoints
+const ADMIN_ROLE: &str = "admin";
+
 /// Response for token generation
 #[derive(Serialize)]
 pub struct TokenGenerationResponse {
@@ -35,12 +47,19 @@ pub struct TokenGenerationResponse {
 #[derive(Deserialize)]
 pub struct RefreshTokenRequest {
     pub refresh_token: String,
+    /// Viewer profile the new access token is for; the refresh token's
+    /// profile if none
+    pub profile_id: Option<Uuid>,
+    /// Parental control PIN, required to switch to a less restricted profile
+    pub pin: Option<String>,
 }
 
 /// Response for token refresh
 #[derive(Serialize)]
 pub struct TokenRefreshResponse {
     pub access_token: String,
+    /// Rotated refresh token, bound to the token's profile
+    pub refresh_token: String,
     pub token_type: String,
     pub expires_in: u64,
 }
@@ -58,6 +77,15 @@ pub struct RegisterRequest {
 pub struct LoginRequest {
     pub username_or_email: String,
     pub password: String,
+    /// Stable client-generated device identifier
+    pub device_id: Option<String>,
+}
+
+/// Request for suspicious login verification
+#[derive(Deserialize)]
+pub struct LoginVerifyRequest {
+    pub challenge_id: String,
+    pub code: String,
 }
 
 /// Request for logout
@@ -72,6 +100,47 @@ pub struct LogoutAllRequest {
     pub refresh_token: String,
 }
 
+/// Request for password change
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// Request for admin impersonation
+#[derive(Deserialize)]
+pub struct ImpersonateRequest {
+    pub user_id: Uuid,
+    /// Why the user is being impersonated, recorded in the audit trail
+    pub reason: String,
+    /// Whether the token is limited to read operations (default: true)
+    pub read_only: Option<bool>,
+}
+
+/// Request for selecting a viewer profile
+#[derive(Deserialize)]
+pub struct SelectProfileRequest {
+    /// Profile to use, or none for the account itself
+    pub profile_id: Option<Uuid>,
+    /// Parental control PIN, required to switch to a less restricted profile
+    pub pin: Option<String>,
+    /// Refresh token of the session, rotated and bound to the selected
+    /// profile so later refreshes keep its maturity limit
+    pub refresh_token: Option<String>,
+}
+
+/// Request for a magic link
+#[derive(Deserialize)]
+pub struct MagicLinkRequest {
+    pub email: String,
+}
+
+/// Request for magic link exchange
+#[derive(Deserialize)]
+pub struct MagicLinkVerifyRequest {
+    pub token: String,
+}
+
 /// Request for OAuth authorization
 #[derive(Deserialize)]
 pub struct OAuthAuthRequest {
@@ -96,16 +165,29 @@ pub struct LoginResponse {
 
 /// Create the router for the authentication service
 pub fn create_router(state: AppState) -> Router {
+    let protected_routes = Router::new()
+        .route("/auth/password/change", post(change_password))
+        .route("/auth/admin/impersonate", post(impersonate))
+        .route("/auth/profiles/select", post(select_profile))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ));
+
     Router::new()
         .route("/health", get(health_check))
         .route("/auth/register", post(register))
         .route("/auth/login", post(login))
+        .route("/auth/login/verify", post(verify_login))
+        .route("/auth/magic-link", post(request_magic_link))
+        .route("/auth/magic-link/verify", post(verify_magic_link))
         .route("/auth/oauth/authorize", post(oauth_authorize))
         .route("/auth/oauth/callback", post(oauth_callback))
         .route("/auth/refresh", post(refresh_token))
         .route("/auth/logout", post(logout))
         .route("/auth/logout-all", post(logout_all))
         .route("/health/redis", get(redis_health_check))
+        .merge(protected_routes)
         .with_state(state)
 }
 
@@ -152,7 +234,7 @@ pub async fn register(
     // Validate input
     validation::validate_username(&payload.username).map_err(|e| AuthError::BadRequest(e))?;
     validation::validate_email(&payload.email).map_err(|e| AuthError::BadRequest(e))?;
-    validation::validate_password(&payload.password).map_err(|e| AuthError::BadRequest(e))?;
+    check_new_password(&state, &payload.password, None).await?;
 
     // Check if user already exists
     if let Some(_) = state
@@ -200,10 +282,16 @@ pub async fn register(
 }
 
 /// User login endpoint
+///
+/// Logins from an unfamiliar device or location do not return tokens right
+/// away; a verification code is emailed instead and the login is completed
+/// through `/auth/login/verify`.
 pub async fn login(
     State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
-) -> Result<impl IntoResponse, AuthError> {
+) -> Result<Response, AuthError> {
     info!("Login attempt for user: {}", payload.username_or_email);
 
     // Validate input
@@ -217,6 +305,20 @@ pub async fn login(
         return Err(AuthError::BadRequest("Password is required".to_string()));
     }
 
+    // Rate limit by client IP
+    let is_allowed = state
+        .rate_limiter
+        .is_allowed(&addr.ip().to_string())
+        .await
+        .map_err(|e| {
+            error!("Failed to check login rate limit: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+    if !is_allowed {
+        return Err(AuthError::TooManyRequests);
+    }
+
     // Find user by username or email
     let user = state
         .user_repository
@@ -225,8 +327,29 @@ pub async fn login(
         .map_err(|e| {
             error!("Failed to find user: {}", e);
             AuthError::InternalServerError
-        })?
-        .ok_or(AuthError::Unauthorized)?;
+        })?;
+
+    // Unknown and locked accounts are rejected like a wrong password, after
+    // as long a hash, so that neither can be told apart from a real account.
+    // The owner of a locked account was notified by email when it was locked.
+    let user = match user {
+        Some(user) if state.account_lockout.locked_for(user.id).await.is_none() => user,
+        _ => {
+            state
+                .user_repository
+                .verify_dummy_password(&payload.password);
+            return Err(AuthError::Unauthorized);
+        }
+    };
+
+    // Accounts created through OAuth have no password to log in with
+    if !state.user_repository.has_password(&user) {
+        info!("Rejected password login for passwordless user: {}", user.id);
+        state
+            .user_repository
+            .verify_dummy_password(&payload.password);
+        return Err(AuthError::Unauthorized);
+    }
 
     // Verify password
     let is_valid = state
@@ -239,13 +362,183 @@ pub async fn login(
         })?;
 
     if !is_valid {
+        if let Some(lockout) = state.account_lockout.record_failure(user.id).await {
+            let email = Email {
+                to: user.email.clone(),
+                subject: "Your account has been temporarily locked".to_string(),
+                body: format!(
+                    "We locked your account for {} minutes after several failed sign-in attempts. \
+                     If this was not you, consider changing your password.\n",
+                    lockout.as_secs().div_ceil(60)
+                ),
+            };
+
+            if let Err(e) = state.mailer.send(&email).await {
+                error!("Failed to send lockout notification: {}", e);
+            }
+        }
+
         return Err(AuthError::Unauthorized);
     }
 
-    // Generate tokens
+    state.account_lockout.record_success(user.id).await;
+
+    // Upgrade the stored hash if it was created with outdated parameters
+    if state.user_repository.needs_rehash(&user)
+        && let Err(e) = state
+            .user_repository
+            .rehash_password(&user, &payload.password)
+            .await
+    {
+        error!("Failed to upgrade password hash: {}", e);
+    }
+
+    // Check whether the login comes from a familiar device and location
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok());
+    let context = LoginContext::new(
+        addr.ip(),
+        payload.device_id.as_deref(),
+        user_agent,
+        state.geoip.as_deref(),
+    );
+
+    let assessment = state
+        .login_device_repository
+        .assess(user.id, &context)
+        .await
+        .map_err(|e| {
+            error!("Failed to assess login: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+    let reasons = assessment.suspicious_reasons(&context);
+    if !reasons.is_empty() {
+        info!(
+            "Suspicious login for user {} ({}), requiring verification",
+            user.id,
+            reasons.join(", ")
+        );
+
+        let (challenge_id, code) = state
+            .login_challenge_service
+            .create(user.id, context.clone())
+            .await
+            .map_err(|e| {
+                error!("Failed to create login challenge: {}", e);
+                AuthError::InternalServerError
+            })?;
+
+        let email = Email {
+            to: user.email.clone(),
+            subject: "Confirm your sign-in".to_string(),
+            body: format!(
+                "We noticed a sign-in from a {} (IP {}{}).\n\n\
+                 If this was you, enter this code to continue: {}\n\n\
+                 The code expires in {} minutes. If this was not you, change your password.\n",
+                reasons.join(" and "),
+                context.ip,
+                context
+                    .country
+                    .as_deref()
+                    .map(|country| format!(", {}", country))
+                    .unwrap_or_default(),
+                code,
+                state.login_challenge_service.expiry() / 60
+            ),
+        };
+
+        state.mailer.send(&email).await.map_err(|e| {
+            error!("Failed to send login verification email: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+        let response = serde_json::json!({
+            "step_up_required": true,
+            "challenge_id": challenge_id,
+            "message": "Verification code sent to the account email"
+        });
+
+        return Ok((StatusCode::ACCEPTED, Json(response)).into_response());
+    }
+
+    state
+        .login_device_repository
+        .record(user.id, &context)
+        .await
+        .map_err(|e| {
+            error!("Failed to record login device: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+    let response = issue_tokens(&state, &user).await?;
+
+    Ok((StatusCode::OK, Json(response)).into_response())
+}
+
+/// Login verification endpoint for suspicious logins
+pub async fn verify_login(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(payload): Json<LoginVerifyRequest>,
+) -> Result<impl IntoResponse, AuthError> {
+    info!("Login verification request");
+
+    // Rate limit by client IP, separately from password attempts
+    let is_allowed = state
+        .rate_limiter
+        .is_allowed(&format!("login_verify:{}", addr.ip()))
+        .await
+        .map_err(|e| {
+            error!("Failed to check login verification rate limit: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+    if !is_allowed {
+        return Err(AuthError::TooManyRequests);
+    }
+
+    let challenge = state
+        .login_challenge_service
+        .verify(&payload.challenge_id, &payload.code)
+        .await
+        .map_err(|e| {
+            error!("Failed to verify login challenge: {}", e);
+            AuthError::InternalServerError
+        })?
+        .ok_or(AuthError::Unauthorized)?;
+
+    let user = state
+        .user_repository
+        .find_by_id(challenge.user_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to find user: {}", e);
+            AuthError::InternalServerError
+        })?
+        .ok_or(AuthError::Unauthorized)?;
+
+    state
+        .login_device_repository
+        .record(user.id, &challenge.context)
+        .await
+        .map_err(|e| {
+            error!("Failed to record login device: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+    let response = issue_tokens(&state, &user).await?;
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Generate an access and refresh token pair and store the session
+async fn issue_tokens(state: &AppState, user: &User) -> Result<TokenGenerationResponse, AuthError> {
+    let roles = find_roles(state, user.id).await?;
     let access_token = state
         .jwt_service
-        .generate_access_token(&user, &[])
+        .generate_access_token(user, &roles, None)
         .map_err(|e| {
             error!("Failed to generate access token: {}", e);
             AuthError::InternalServerError
@@ -253,7 +546,7 @@ pub async fn login(
 
     let refresh_token = state
         .jwt_service
-        .generate_refresh_token(&user)
+        .generate_refresh_token(user, None)
         .map_err(|e| {
             error!("Failed to generate refresh token: {}", e);
             AuthError::InternalServerError
@@ -269,13 +562,513 @@ pub async fn login(
             AuthError::InternalServerError
         })?;
 
-    let response = TokenGenerationResponse {
+    Ok(TokenGenerationResponse {
         access_token,
         refresh_token,
         token_type: "Bearer".to_string(),
         expires_in: state.jwt_service.access_token_expiry(),
+    })
+}
+
+/// Load the roles assigned to a user
+async fn find_roles(state: &AppState, user_id: Uuid) -> Result<Vec<Role>, AuthError> {
+    state
+        .user_repository
+        .find_roles(user_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to load user roles: {}", e);
+            AuthError::InternalServerError
+        })
+}
+
+/// Check a switch between the account and its viewer profiles
+///
+/// The target profile must belong to the user. Switching to a less
+/// restricted maturity limit than the current token's, such as from a kids
+/// profile to the account itself, requires the parental control PIN.
+async fn check_profile_switch(
+    state: &AppState,
+    user_id: Uuid,
+    current: Option<Uuid>,
+    target: Option<Uuid>,
+    pin: Option<&str>,
+) -> Result<(), AuthError> {
+    check_profile(state, user_id, target).await?;
+    if current == target {
+        return Ok(());
+    }
+
+    let max_rating = |profile_id| async move {
+        state
+            .profile_repository
+            .max_rating(user_id, profile_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to get maturity limit: {}", e);
+                AuthError::InternalServerError
+            })
+    };
+    let current_rating = max_rating(current).await?;
+    let target_rating = max_rating(target).await?;
+    if !is_less_restrictive(target_rating.as_deref(), current_rating.as_deref()) {
+        return Ok(());
+    }
+
+    state
+        .profile_repository
+        .check_pin(user_id, pin)
+        .await
+        .map_err(|error| match error {
+            PinError::Required => AuthError::BadRequest("PIN required".to_string()),
+            PinError::Invalid => AuthError::Forbidden,
+            PinError::Locked => AuthError::TooManyRequests,
+            PinError::Database(e) => {
+                error!("Failed to check PIN: {}", e);
+                AuthError::InternalServerError
+            }
+            PinError::Hash(e) => {
+                error!("Failed to check PIN: {}", e);
+                AuthError::InternalServerError
+            }
+        })
+}
+
+/// Check that a requested viewer profile belongs to the user
+async fn check_profile(
+    state: &AppState,
+    user_id: Uuid,
+    profile_id: Option<Uuid>,
+) -> Result<(), AuthError> {
+    let Some(profile_id) = profile_id else {
+        return Ok(());
     };
 
+    let owned = state
+        .profile_repository
+        .belongs_to(profile_id, user_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to check profile ownership: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+    if owned {
+        Ok(())
+    } else {
+        Err(AuthError::BadRequest("Profile not found".to_string()))
+    }
+}
+
+/// Check a new password against the password policy, the breached-password
+/// corpus and, for existing users, their recent passwords
+async fn check_new_password(
+    state: &AppState,
+    password: &str,
+    user: Option<&User>,
+) -> Result<(), AuthError> {
+    validation::validate_password(password, &state.password_policy)
+        .map_err(AuthError::BadRequest)?;
+
+    if let Some(checker) = &state.breached_password_checker {
+        let is_breached = checker.is_breached(password).map_err(|e| {
+            error!("Failed to check breached passwords: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+        if is_breached {
+            return Err(AuthError::BadRequest(
+                "Password has appeared in a data breach, please choose another one".to_string(),
+            ));
+        }
+    }
+
+    if let Some(user) = user {
+        let is_reused = state
+            .user_repository
+            .is_password_reused(user, password, state.password_policy.history_size)
+            .await
+            .map_err(|e| {
+                error!("Failed to check password history: {}", e);
+                AuthError::InternalServerError
+            })?;
+
+        if is_reused {
+            return Err(AuthError::BadRequest(
+                "Password must not match a recently used password".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Change password endpoint
+pub async fn change_password(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<impl IntoResponse, AuthError> {
+    let user_id = claims.sub;
+    info!("Password change request for user: {}", user_id);
+
+    // Impersonated sessions must never change credentials
+    if claims.act.is_some() {
+        return Err(AuthError::Forbidden);
+    }
+
+    if payload.current_password.is_empty() {
+        return Err(AuthError::BadRequest(
+            "Current password is required".to_string(),
+        ));
+    }
+
+    let user = state
+        .user_repository
+        .find_by_id(user_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to find user: {}", e);
+            AuthError::InternalServerError
+        })?
+        .ok_or(AuthError::Unauthorized)?;
+
+    if !state.user_repository.has_password(&user) {
+        return Err(AuthError::BadRequest(
+            "Account does not have a password".to_string(),
+        ));
+    }
+
+    let is_valid = state
+        .user_repository
+        .verify_password(&user, &payload.current_password)
+        .await
+        .map_err(|e| {
+            error!("Failed to verify password: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+    if !is_valid {
+        return Err(AuthError::Unauthorized);
+    }
+
+    check_new_password(&state, &payload.new_password, Some(&user)).await?;
+
+    state
+        .user_repository
+        .update_password(
+            &user,
+            &payload.new_password,
+            state.password_policy.history_size,
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to update password: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({"message": "Password changed successfully"})),
+    ))
+}
+
+/// Admin impersonation endpoint
+///
+/// Issues a short-lived access token for another user carrying the admin in
+/// the `act` claim. No refresh token is issued, tokens are read-only unless
+/// explicitly requested otherwise, and every impersonation is audited.
+pub async fn impersonate(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(payload): Json<ImpersonateRequest>,
+) -> Result<impl IntoResponse, AuthError> {
+    info!(
+        "Impersonation request by {} for user {}",
+        claims.sub, payload.user_id
+    );
+
+    // Only admins acting as themselves may impersonate
+    if claims.act.is_some() || !claims.roles.iter().any(|role| role == ADMIN_ROLE) {
+        return Err(AuthError::Forbidden);
+    }
+
+    let reason = payload.reason.trim();
+    if reason.is_empty() {
+        return Err(AuthError::BadRequest(
+            "A reason for the impersonation is required".to_string(),
+        ));
+    }
+
+    if payload.user_id == claims.sub {
+        return Err(AuthError::BadRequest(
+            "Cannot impersonate yourself".to_string(),
+        ));
+    }
+
+    let user = state
+        .user_repository
+        .find_by_id(payload.user_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to find user: {}", e);
+            AuthError::InternalServerError
+        })?
+        .ok_or_else(|| AuthError::BadRequest("User not found".to_string()))?;
+
+    let roles = find_roles(&state, user.id).await?;
+    if roles.iter().any(|role| role.name == ADMIN_ROLE) {
+        return Err(AuthError::Forbidden);
+    }
+
+    let read_only = payload.read_only.unwrap_or(true);
+    let access_token = state
+        .jwt_service
+        .generate_impersonation_token(&user, &roles, claims.sub, read_only)
+        .map_err(|e| {
+            error!("Failed to generate impersonation token: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+    let event = NewAuditEvent {
+        actor_id: claims.sub,
+        action: "impersonation.start".to_string(),
+        target_user_id: Some(user.id),
+        details: serde_json::json!({
+            "reason": reason,
+            "read_only": read_only,
+            "expires_in": state.jwt_service.impersonation_token_expiry(),
+        }),
+        ip_address: Some(addr.ip().to_string()),
+    };
+
+    state.audit_repository.record(&event).await.map_err(|e| {
+        error!("Failed to record impersonation audit event: {}", e);
+        AuthError::InternalServerError
+    })?;
+
+    let response = serde_json::json!({
+        "access_token": access_token,
+        "token_type": "Bearer",
+        "expires_in": state.jwt_service.impersonation_token_expiry(),
+        "user_id": user.id.to_string(),
+        "read_only": read_only,
+    });
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Profile selection endpoint
+///
+/// Re-issues the caller's access token for one of their viewer profiles, or
+/// for the account itself when no profile is given. The new token expires
+/// when the current one does; later refreshes pass the profile along.
+pub async fn select_profile(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<SelectProfileRequest>,
+) -> Result<impl IntoResponse, AuthError> {
+    info!(
+        "Profile selection by {}: {:?}",
+        claims.sub, payload.profile_id
+    );
+
+    check_profile_switch(
+        &state,
+        claims.sub,
+        claims.profile_id,
+        payload.profile_id,
+        payload.pin.as_deref(),
+    )
+    .await?;
+
+    let access_token = state
+        .jwt_service
+        .generate_profile_token(&claims, payload.profile_id)
+        .map_err(|e| {
+            error!("Failed to generate profile token: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| {
+            error!("Failed to get current time: {}", e);
+            AuthError::InternalServerError
+        })?
+        .as_secs();
+
+    let refresh_token = match &payload.refresh_token {
+        Some(refresh_token) => {
+            Some(rebind_refresh_token(&state, claims.sub, refresh_token, payload.profile_id).await?)
+        }
+        None => None,
+    };
+
+    let response = serde_json::json!({
+        "access_token": access_token,
+        "refresh_token": refresh_token,
+        "token_type": "Bearer",
+        "expires_in": claims.exp.saturating_sub(now),
+        "profile_id": payload.profile_id,
+    });
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Rotate a session's refresh token into one bound to a profile
+async fn rebind_refresh_token(
+    state: &AppState,
+    user_id: Uuid,
+    refresh_token: &str,
+    profile_id: Option<Uuid>,
+) -> Result<String, AuthError> {
+    let claims = state
+        .jwt_service
+        .validate_token(refresh_token)
+        .map_err(|_| AuthError::Unauthorized)?;
+    if claims.token_type != crate::jwt::TokenType::Refresh || claims.sub != user_id {
+        return Err(AuthError::Unauthorized);
+    }
+
+    let is_blacklisted = state
+        .jwt_service
+        .is_token_blacklisted(&state.redis_pool, refresh_token)
+        .await
+        .map_err(|e| {
+            error!("Failed to check if token is blacklisted: {}", e);
+            AuthError::InternalServerError
+        })?;
+    if is_blacklisted {
+        return Err(AuthError::Unauthorized);
+    }
+
+    let user = state
+        .user_repository
+        .find_by_id(user_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch user from database: {}", e);
+            AuthError::InternalServerError
+        })?
+        .ok_or(AuthError::Unauthorized)?;
+
+    let new_refresh_token = state
+        .jwt_service
+        .rotate_refresh_token(&state.redis_pool, &user, refresh_token, profile_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to rotate refresh token: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+    state
+        .session_manager
+        .update_session(user.id, &new_refresh_token)
+        .await
+        .map_err(|e| {
+            error!("Failed to update session: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+    Ok(new_refresh_token)
+}
+
+/// Magic link request endpoint
+///
+/// Always responds with the same message so the endpoint cannot be used to
+/// discover which email addresses have an account.
+pub async fn request_magic_link(
+    State(state): State<AppState>,
+    Json(payload): Json<MagicLinkRequest>,
+) -> Result<impl IntoResponse, AuthError> {
+    info!("Magic link request");
+
+    validation::validate_email(&payload.email).map_err(AuthError::BadRequest)?;
+
+    let email = payload.email.to_lowercase();
+    let is_allowed = state
+        .magic_link_rate_limiter
+        .is_allowed(&email)
+        .await
+        .map_err(|e| {
+            error!("Failed to check magic link rate limit: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+    if !is_allowed {
+        return Err(AuthError::TooManyRequests);
+    }
+
+    let user = state
+        .user_repository
+        .find_by_username_or_email(&payload.email)
+        .await
+        .map_err(|e| {
+            error!("Failed to find user: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+    if let Some(user) = user.filter(|user| user.email.eq_ignore_ascii_case(&payload.email)) {
+        let link = state.magic_link_service.issue(&user).await.map_err(|e| {
+            error!("Failed to issue magic link: {}", e);
+            AuthError::InternalServerError
+        })?;
+
+        let email = Email {
+            to: user.email.clone(),
+            subject: "Your sign-in link".to_string(),
+            body: format!(
+                "Use the link below to sign in. It expires in {} minutes and can only be used once.\n\n{}\n",
+                state.magic_link_service.expiry() / 60,
+                link
+            ),
+        };
+
+        state.mailer.send(&email).await.map_err(|e| {
+            error!("Failed to send magic link email: {}", e);
+            AuthError::InternalServerError
+        })?;
+    }
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({
+            "message": "If an account exists for this email, a sign-in link has been sent"
+        })),
+    ))
+}
+
+/// Magic link exchange endpoint
+pub async fn verify_magic_link(
+    State(state): State<AppState>,
+    Json(payload): Json<MagicLinkVerifyRequest>,
+) -> Result<impl IntoResponse, AuthError> {
+    info!("Magic link exchange request");
+
+    let user_id = state
+        .magic_link_service
+        .redeem(&payload.token)
+        .await
+        .map_err(|e| {
+            error!("Failed to redeem magic link: {}", e);
+            AuthError::InternalServerError
+        })?
+        .ok_or(AuthError::Unauthorized)?;
+
+    let user = state
+        .user_repository
+        .find_by_id(user_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to find user: {}", e);
+            AuthError::InternalServerError
+        })?
+        .ok_or(AuthError::Unauthorized)?;
+
+    let response = issue_tokens(&state, &user).await?;
+
     Ok((StatusCode::OK, Json(response)))
 }
 
@@ -325,10 +1118,20 @@ pub async fn refresh_token(
             AuthError::Unauthorized
         })?;
 
-    // Generate a new access token
+    // Generate a new access token, for the requested profile if any
+    let profile_id = refresh_profile(payload.profile_id, claims.profile_id);
+    check_profile_switch(
+        &state,
+        user.id,
+        claims.profile_id,
+        profile_id,
+        payload.pin.as_deref(),
+    )
+    .await?;
+    let roles = find_roles(&state, user.id).await?;
     let access_token = state
         .jwt_service
-        .generate_access_token(&user, &[])
+        .generate_access_token(&user, &roles, profile_id)
         .map_err(|e| {
             error!("Failed to generate access token: {}", e);
             AuthError::InternalServerError
@@ -337,7 +1140,7 @@ pub async fn refresh_token(
     // Rotate the refresh token
     let new_refresh_token = state
         .jwt_service
-        .rotate_refresh_token(&state.redis_pool, &user, &payload.refresh_token)
+        .rotate_refresh_token(&state.redis_pool, &user, &payload.refresh_token, profile_id)
         .await
         .map_err(|e| {
             error!("Failed to rotate refresh token: {}", e);
@@ -346,6 +1149,7 @@ pub async fn refresh_token(
 
     let response = TokenRefreshResponse {
         access_token,
+        refresh_token: new_refresh_token.clone(),
         token_type: "Bearer".to_string(),
         expires_in: state.jwt_service.access_token_expiry(),
     };
@@ -363,6 +1167,12 @@ pub async fn refresh_token(
     Ok((StatusCode::OK, Json(response)))
 }
 
+/// Profile a refreshed token is for: the requested one, or else the
+/// refresh token's own, so a plain refresh never leaves a profile
+fn refresh_profile(requested: Option<Uuid>, current: Option<Uuid>) -> Option<Uuid> {
+    requested.or(current)
+}
+
 /// Logout endpoint
 pub async fn logout(
     State(state): State<AppState>,
@@ -651,22 +1461,23 @@ pub async fn oauth_callback(
     } else {
         // Create new user
         info!("Creating new user with OAuth provider");
-        let new_user = crate::models::NewUser {
-            username: format!("{}_{}", session.provider.as_str(), user_profile.id),
-            email: user_profile.email.clone(),
-            password_hash: "".to_string(), // OAuth users don't have passwords
-        };
+        let username = format!("{}_{}", session.provider.as_str(), user_profile.id);
 
-        state.user_repository.create(&new_user).await.map_err(|e| {
-            error!("Failed to create user: {}", e);
-            AuthError::InternalServerError
-        })?
+        state
+            .user_repository
+            .create_without_password(&username, &user_profile.email)
+            .await
+            .map_err(|e| {
+                error!("Failed to create user: {}", e);
+                AuthError::InternalServerError
+            })?
     };
 
     // Generate JWT tokens
+    let roles = find_roles(&state, user.id).await?;
     let access_token = state
         .jwt_service
-        .generate_access_token(&user, &[])
+        .generate_access_token(&user, &roles, None)
         .map_err(|e| {
             error!("Failed to generate access token: {}", e);
             AuthError::InternalServerError
@@ -674,7 +1485,7 @@ pub async fn oauth_callback(
 
     let refresh_token = state
         .jwt_service
-        .generate_refresh_token(&user)
+        .generate_refresh_token(&user, None)
         .map_err(|e| {
             error!("Failed to generate refresh token: {}", e);
             AuthError::InternalServerError
@@ -712,7 +1523,9 @@ pub async fn oauth_callback(
 #[derive(Debug)]
 pub enum AuthError {
     Unauthorized,
+    Forbidden,
     BadRequest(String),
+    TooManyRequests,
     InternalServerError,
 }
 
@@ -720,7 +1533,12 @@ impl IntoResponse for AuthError {
     fn into_response(self) -> Response {
         let (status, error_message) = match self {
             AuthError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized".to_string()),
+            AuthError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden".to_string()),
             AuthError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            AuthError::TooManyRequests => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Too many requests".to_string(),
+            ),
             AuthError::InternalServerError => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal server error".to_string(),
@@ -734,3 +1552,18 @@ impl IntoResponse for AuthError {
         (status, body).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_refresh_keeps_profile() {
+        let kids = Uuid::new_v4();
+        let teen = Uuid::new_v4();
+
+        assert_eq!(refresh_profile(None, Some(kids)), Some(kids));
+        assert_eq!(refresh_profile(Some(teen), Some(kids)), Some(teen));
+        assert_eq!(refresh_profile(None, None), None);
+    }
+}
@@ -51,18 +51,92 @@ pub fn validate_email(email: &str) -> Result<(), String> {
     Ok(())
 }
 
-/// Validate password
-pub fn validate_password(password: &str) -> Result<(), String> {
+/// Password policy
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    /// Minimum password length
+    pub min_length: usize,
+    /// Maximum password length
+    pub max_length: usize,
+    /// Require at least one uppercase letter
+    pub require_uppercase: bool,
+    /// Require at least one lowercase letter
+    pub require_lowercase: bool,
+    /// Require at least one digit
+    pub require_digit: bool,
+    /// Require at least one special character
+    pub require_special: bool,
+    /// Number of previous passwords that cannot be reused
+    pub history_size: u32,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_special: true,
+            history_size: 5,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Create a new PasswordPolicy from environment variables
+    ///
+    /// # Environment Variables
+    /// - `PASSWORD_MIN_LENGTH`: Minimum password length (default: 8)
+    /// - `PASSWORD_MAX_LENGTH`: Maximum password length (default: 128)
+    /// - `PASSWORD_REQUIRE_UPPERCASE`: Require an uppercase letter (default: true)
+    /// - `PASSWORD_REQUIRE_LOWERCASE`: Require a lowercase letter (default: true)
+    /// - `PASSWORD_REQUIRE_DIGIT`: Require a digit (default: true)
+    /// - `PASSWORD_REQUIRE_SPECIAL`: Require a special character (default: true)
+    /// - `PASSWORD_HISTORY_SIZE`: Number of previous passwords that cannot be reused (default: 5)
+    pub fn from_env() -> Self {
+        fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+            std::env::var(key)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(default)
+        }
+
+        let defaults = Self::default();
+
+        Self {
+            min_length: env_or("PASSWORD_MIN_LENGTH", defaults.min_length),
+            max_length: env_or("PASSWORD_MAX_LENGTH", defaults.max_length),
+            require_uppercase: env_or("PASSWORD_REQUIRE_UPPERCASE", defaults.require_uppercase),
+            require_lowercase: env_or("PASSWORD_REQUIRE_LOWERCASE", defaults.require_lowercase),
+            require_digit: env_or("PASSWORD_REQUIRE_DIGIT", defaults.require_digit),
+            require_special: env_or("PASSWORD_REQUIRE_SPECIAL", defaults.require_special),
+            history_size: env_or("PASSWORD_HISTORY_SIZE", defaults.history_size),
+        }
+    }
+}
+
+/// Validate password against a policy
+pub fn validate_password(password: &str, policy: &PasswordPolicy) -> Result<(), String> {
     if password.is_empty() {
         return Err("Password is required".to_string());
     }
 
-    if password.len() < 8 {
-        return Err("Password must be at least 8 characters long".to_string());
+    let length = password.chars().count();
+
+    if length < policy.min_length {
+        return Err(format!(
+            "Password must be at least {} characters long",
+            policy.min_length
+        ));
     }
 
-    if password.len() > 128 {
-        return Err("Password must be at most 128 characters long".to_string());
+    if length > policy.max_length {
+        return Err(format!(
+            "Password must be at most {} characters long",
+            policy.max_length
+        ));
     }
 
     let mut has_upper = false;
@@ -82,19 +156,19 @@ pub fn validate_password(password: &str) -> Result<(), String> {
         }
     }
 
-    if !has_upper {
+    if policy.require_uppercase && !has_upper {
         return Err("Password must contain at least one uppercase letter".to_string());
     }
 
-    if !has_lower {
+    if policy.require_lowercase && !has_lower {
         return Err("Password must contain at least one lowercase letter".to_string());
     }
 
-    if !has_digit {
+    if policy.require_digit && !has_digit {
         return Err("Password must contain at least one digit".to_string());
     }
 
-    if !has_special {
+    if policy.require_special && !has_special {
         return Err("Password must contain at least one special character".to_string());
     }
 
@@ -0,0 +1,46 @@
+-- Add a full-text search vector over title, description, tags and filename
+ALTER TABLE media_items
+ADD COLUMN IF NOT EXISTS search_vector TSVECTOR;
+
+-- Create function to keep the search vector in sync with metadata and s3_key
+CREATE OR REPLACE FUNCTION update_media_items_search_vector()
+RETURNS TRIGGER AS $$
+DECLARE
+    tags TEXT;
+    filename TEXT;
+BEGIN
+    SELECT string_agg(tag, ' ') INTO tags
+    FROM jsonb_array_elements_text(
+        CASE WHEN jsonb_typeof(NEW.metadata->'tags') = 'array'
+             THEN NEW.metadata->'tags'
+             ELSE '[]'::jsonb
+        END
+    ) AS tag;
+
+    -- Last path segment without extension, with separators turned into spaces
+    filename := regexp_replace(
+        regexp_replace(regexp_replace(NEW.s3_key, '^.*/', ''), '\.[^.]*$', ''),
+        '[._-]+', ' ', 'g'
+    );
+
+    NEW.search_vector :=
+        setweight(to_tsvector('simple', coalesce(NEW.metadata->>'title', '')), 'A') ||
+        setweight(to_tsvector('simple', coalesce(tags, '')), 'B') ||
+        setweight(to_tsvector('simple', coalesce(NEW.metadata->>'description', '')), 'C') ||
+        setweight(to_tsvector('simple', coalesce(filename, '')), 'D');
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+-- Create trigger to maintain the search vector
+CREATE TRIGGER update_media_items_search_vector BEFORE INSERT OR UPDATE OF metadata, s3_key ON media_items
+    FOR EACH ROW EXECUTE FUNCTION update_media_items_search_vector();
+
+-- Populate the search vector for existing rows through the trigger above,
+-- without touching updated_at, which sorting and its cursors rely on
+ALTER TABLE media_items DISABLE TRIGGER update_media_items_updated_at;
+UPDATE media_items SET metadata = metadata;
+ALTER TABLE media_items ENABLE TRIGGER update_media_items_updated_at;
+
+-- Create a GIN index for full-text queries
+CREATE INDEX IF NOT EXISTS idx_media_items_search_vector ON media_items USING GIN(search_vector);
@@ -0,0 +1,17 @@
+-- Add visibility to media_items
+-- private: owner only, unlisted: anyone with the ID, shared: owner and listed users, public: everyone
+ALTER TABLE media_items
+ADD COLUMN IF NOT EXISTS visibility VARCHAR(20) NOT NULL DEFAULT 'private'
+CHECK (visibility IN ('private', 'unlisted', 'shared', 'public'));
+
+-- Create table for users a media item is shared with
+CREATE TABLE IF NOT EXISTS media_item_shares (
+    media_item_id UUID NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (media_item_id, user_id)
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_media_items_visibility ON media_items(visibility);
+CREATE INDEX IF NOT EXISTS idx_media_item_shares_user_id ON media_item_shares(user_id);
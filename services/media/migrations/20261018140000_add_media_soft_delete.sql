@@ -0,0 +1,7 @@
+-- Add soft delete support to media_items
+ALTER TABLE media_items
+ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+-- Create indexes for the trash view and the purge job
+CREATE INDEX IF NOT EXISTS idx_media_items_deleted_at ON media_items(deleted_at)
+WHERE deleted_at IS NOT NULL;
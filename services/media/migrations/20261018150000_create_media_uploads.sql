@@ -0,0 +1,22 @@
+-- Create table to track direct-to-S3 uploads
+CREATE TABLE IF NOT EXISTS media_uploads (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    media_item_id UUID NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    s3_key VARCHAR(500) NOT NULL,
+    content_type VARCHAR(255) NOT NULL,
+    size BIGINT NOT NULL,
+    multipart_upload_id VARCHAR(1024),
+    expires_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_media_uploads_user_id ON media_uploads(user_id);
+CREATE INDEX IF NOT EXISTS idx_media_uploads_media_item_id ON media_uploads(media_item_id);
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_media_uploads_updated_at BEFORE UPDATE ON media_uploads
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,22 @@
+-- Create table to track resumable (tus) uploads
+CREATE TABLE IF NOT EXISTS tus_uploads (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    upload_length BIGINT NOT NULL,
+    upload_offset BIGINT NOT NULL DEFAULT 0,
+    metadata JSONB NOT NULL DEFAULT '{}'::jsonb,
+    media_item_id UUID REFERENCES media_items(id) ON DELETE SET NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_tus_uploads_user_id ON tus_uploads(user_id);
+CREATE INDEX IF NOT EXISTS idx_tus_uploads_expires_at ON tus_uploads(expires_at)
+WHERE completed_at IS NULL;
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_tus_uploads_updated_at BEFORE UPDATE ON tus_uploads
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,32 @@
+-- Create table for transcoded HLS renditions of media items
+CREATE TABLE IF NOT EXISTS media_renditions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    media_item_id UUID NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    name VARCHAR(50) NOT NULL CHECK (name ~ '^[A-Za-z0-9_-]+$'),
+    bandwidth INTEGER NOT NULL,
+    average_bandwidth INTEGER,
+    width INTEGER,
+    height INTEGER,
+    frame_rate DOUBLE PRECISION,
+    codecs VARCHAR(255) NOT NULL,
+    target_duration INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (media_item_id, name)
+);
+
+-- Create table for the segments of each rendition
+CREATE TABLE IF NOT EXISTS media_rendition_segments (
+    rendition_id UUID NOT NULL REFERENCES media_renditions(id) ON DELETE CASCADE,
+    sequence INTEGER NOT NULL,
+    duration DOUBLE PRECISION NOT NULL,
+    s3_key VARCHAR(500) NOT NULL,
+    PRIMARY KEY (rendition_id, sequence)
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_media_renditions_media_item_id ON media_renditions(media_item_id);
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_media_renditions_updated_at BEFORE UPDATE ON media_renditions
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,20 @@
+-- Create table for per-user playback positions
+CREATE TABLE IF NOT EXISTS playback_progress (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    media_item_id UUID NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    position DOUBLE PRECISION NOT NULL CHECK (position >= 0),
+    finished BOOLEAN NOT NULL DEFAULT FALSE,
+    last_watched_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, media_item_id)
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_playback_progress_continue_watching
+ON playback_progress(user_id, last_watched_at DESC)
+WHERE NOT finished;
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_playback_progress_updated_at BEFORE UPDATE ON playback_progress
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
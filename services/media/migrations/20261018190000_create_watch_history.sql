@@ -0,0 +1,14 @@
+-- Create append-only table of viewing sessions
+CREATE TABLE IF NOT EXISTS watch_history (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    media_item_id UUID NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    start_position DOUBLE PRECISION NOT NULL CHECK (start_position >= 0),
+    end_position DOUBLE PRECISION NOT NULL CHECK (end_position >= start_position),
+    device VARCHAR(255),
+    watched_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_watch_history_user_watched_at ON watch_history(user_id, watched_at DESC);
+CREATE INDEX IF NOT EXISTS idx_watch_history_media_item_id ON watch_history(media_item_id);
@@ -0,0 +1,31 @@
+-- Create table for user-owned collections of media items
+CREATE TABLE IF NOT EXISTS collections (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    kind VARCHAR(20) NOT NULL DEFAULT 'custom'
+        CHECK (kind IN ('watch_later', 'favorites', 'custom')),
+    share_token VARCHAR(64) UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create table for the ordered items of each collection
+CREATE TABLE IF NOT EXISTS collection_items (
+    collection_id UUID NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+    media_item_id UUID NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    added_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (collection_id, media_item_id)
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_collections_user_id ON collections(user_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_collections_builtin ON collections(user_id, kind)
+WHERE kind <> 'custom';
+CREATE INDEX IF NOT EXISTS idx_collection_items_position ON collection_items(collection_id, position);
+CREATE INDEX IF NOT EXISTS idx_collection_items_media_item_id ON collection_items(media_item_id);
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_collections_updated_at BEFORE UPDATE ON collections
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,38 @@
+-- Create tables for series and their seasons
+CREATE TABLE IF NOT EXISTS series (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    title VARCHAR(500) NOT NULL,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS seasons (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    series_id UUID NOT NULL REFERENCES series(id) ON DELETE CASCADE,
+    -- Season 0 holds specials
+    number INTEGER NOT NULL CHECK (number >= 0),
+    title VARCHAR(500),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (series_id, number)
+);
+
+-- Distinguish movies from episodes and link episodes to their season
+ALTER TABLE media_items
+ADD COLUMN IF NOT EXISTS kind VARCHAR(20) CHECK (kind IN ('movie', 'episode')),
+ADD COLUMN IF NOT EXISTS season_id UUID REFERENCES seasons(id) ON DELETE SET NULL,
+ADD COLUMN IF NOT EXISTS episode_number INTEGER CHECK (episode_number >= 0);
+
+-- Create indexes for better performance
+CREATE UNIQUE INDEX IF NOT EXISTS idx_series_title ON series(LOWER(title));
+CREATE INDEX IF NOT EXISTS idx_media_items_kind ON media_items(kind);
+CREATE INDEX IF NOT EXISTS idx_media_items_episode ON media_items(season_id, episode_number)
+WHERE season_id IS NOT NULL;
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_series_updated_at BEFORE UPDATE ON series
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_seasons_updated_at BEFORE UPDATE ON seasons
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
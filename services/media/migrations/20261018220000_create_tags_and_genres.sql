@@ -0,0 +1,65 @@
+-- Create table for admin-curated genres
+CREATE TABLE IF NOT EXISTS genres (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(100) NOT NULL,
+    slug VARCHAR(100) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create table for free-form tags, shared between items by their slug
+CREATE TABLE IF NOT EXISTS tags (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(100) NOT NULL,
+    slug VARCHAR(100) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create link tables between media items and genres or tags
+CREATE TABLE IF NOT EXISTS media_item_genres (
+    media_item_id UUID NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    genre_id UUID NOT NULL REFERENCES genres(id) ON DELETE CASCADE,
+    PRIMARY KEY (media_item_id, genre_id)
+);
+
+CREATE TABLE IF NOT EXISTS media_item_tags (
+    media_item_id UUID NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    tag_id UUID NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    added_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (media_item_id, tag_id)
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_tags_slug_prefix ON tags(slug text_pattern_ops);
+CREATE INDEX IF NOT EXISTS idx_media_item_genres_genre_id ON media_item_genres(genre_id);
+CREATE INDEX IF NOT EXISTS idx_media_item_tags_tag_id ON media_item_tags(tag_id);
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_genres_updated_at BEFORE UPDATE ON genres
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Link the tags kept so far in the metadata JSONB
+CREATE TEMPORARY TABLE existing_tags ON COMMIT DROP AS
+SELECT m.id AS media_item_id,
+       regexp_replace(tag, '^\s+|\s+$', '', 'g') AS name,
+       lower(regexp_replace(regexp_replace(tag, '^\s+|\s+$', '', 'g'), '\s+', '-', 'g')) AS slug
+FROM media_items m,
+     jsonb_array_elements_text(
+         CASE WHEN jsonb_typeof(m.metadata->'tags') = 'array'
+              THEN m.metadata->'tags'
+              ELSE '[]'::jsonb
+         END
+     ) AS tag;
+
+INSERT INTO tags (name, slug)
+SELECT DISTINCT ON (slug) name, slug
+FROM existing_tags
+WHERE slug <> '' AND length(slug) <= 100
+ON CONFLICT (slug) DO NOTHING;
+
+INSERT INTO media_item_tags (media_item_id, tag_id)
+SELECT DISTINCT e.media_item_id, t.id
+FROM existing_tags e
+JOIN tags t ON t.slug = e.slug
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,45 @@
+-- Create table for per-user ratings with optional review text
+CREATE TABLE IF NOT EXISTS reviews (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    media_item_id UUID NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    rating SMALLINT NOT NULL CHECK (rating BETWEEN 1 AND 5),
+    body TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'published'
+        CHECK (status IN ('published', 'flagged', 'hidden')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, media_item_id)
+);
+
+-- Create table for abuse reports on reviews
+CREATE TABLE IF NOT EXISTS review_reports (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    review_id UUID NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason VARCHAR(20) NOT NULL CHECK (reason IN ('spam', 'abuse', 'spoiler', 'other')),
+    details TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Set when a moderator has decided on the review
+    resolved_at TIMESTAMPTZ,
+    UNIQUE (review_id, user_id)
+);
+
+-- Create table for rating aggregates, maintained when reviews are written
+CREATE TABLE IF NOT EXISTS media_rating_stats (
+    media_item_id UUID PRIMARY KEY REFERENCES media_items(id) ON DELETE CASCADE,
+    rating_count INTEGER NOT NULL DEFAULT 0,
+    rating_average DOUBLE PRECISION,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_reviews_media_item_id ON reviews(media_item_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_reviews_status ON reviews(status) WHERE status <> 'published';
+CREATE INDEX IF NOT EXISTS idx_review_reports_open ON review_reports(review_id)
+WHERE resolved_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_media_rating_stats_average ON media_rating_stats(rating_average);
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_reviews_updated_at BEFORE UPDATE ON reviews
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
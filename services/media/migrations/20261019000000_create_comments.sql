@@ -0,0 +1,42 @@
+-- Create table for threaded comments on media items
+CREATE TABLE IF NOT EXISTS comments (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    media_item_id UUID NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    parent_id UUID REFERENCES comments(id) ON DELETE CASCADE,
+    -- Playback position in seconds the comment refers to
+    timestamp_seconds DOUBLE PRECISION CHECK (timestamp_seconds >= 0),
+    body TEXT NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'published'
+        CHECK (status IN ('published', 'hidden')),
+    edited_at TIMESTAMPTZ,
+    -- Deleted comments keep their row, without the body, while they have replies
+    deleted_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create table for per-user notifications
+CREATE TABLE IF NOT EXISTS notifications (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('mention')),
+    media_item_id UUID NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    comment_id UUID NOT NULL REFERENCES comments(id) ON DELETE CASCADE,
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    read_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, comment_id, kind)
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_comments_media_item_id ON comments(media_item_id, created_at DESC, id DESC)
+WHERE parent_id IS NULL;
+CREATE INDEX IF NOT EXISTS idx_comments_parent_id ON comments(parent_id, created_at, id);
+CREATE INDEX IF NOT EXISTS idx_comments_status ON comments(status, created_at DESC) WHERE status <> 'published';
+CREATE INDEX IF NOT EXISTS idx_notifications_user_id ON notifications(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_notifications_unread ON notifications(user_id) WHERE read_at IS NULL;
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_comments_updated_at BEFORE UPDATE ON comments
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,20 @@
+-- Create table for precomputed item-item similarities, rebuilt by the API's recommendation job
+CREATE TABLE IF NOT EXISTS media_similarities (
+    media_item_id UUID NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    similar_item_id UUID NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    -- Weighted combination of the signals below, between 0 and 1
+    score DOUBLE PRECISION NOT NULL,
+    -- Jaccard similarity of the items' genres and tags
+    taxonomy_score DOUBLE PRECISION NOT NULL DEFAULT 0,
+    -- Cosine similarity of the sets of users who watched the items
+    cowatch_score DOUBLE PRECISION NOT NULL DEFAULT 0,
+    -- Agreement of type, kind and duration
+    metadata_score DOUBLE PRECISION NOT NULL DEFAULT 0,
+    computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (media_item_id, similar_item_id),
+    CHECK (media_item_id <> similar_item_id)
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_media_similarities_score ON media_similarities(media_item_id, score DESC);
+CREATE INDEX IF NOT EXISTS idx_media_similarities_similar_item_id ON media_similarities(similar_item_id);
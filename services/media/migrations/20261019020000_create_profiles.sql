@@ -0,0 +1,46 @@
+-- Create table for the viewer profiles of an account
+CREATE TABLE IF NOT EXISTS profiles (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(50) NOT NULL,
+    avatar_url TEXT,
+    maturity_level VARCHAR(20) NOT NULL DEFAULT 'adult'
+        CHECK (maturity_level IN ('all', 'kids', 'teen', 'adult')),
+    -- Preferred languages as BCP 47 tags
+    audio_language VARCHAR(35),
+    subtitle_language VARCHAR(35),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Scope viewing data to a profile; NULL is the account itself, used by
+-- tokens issued before a profile was selected
+ALTER TABLE playback_progress
+ADD COLUMN IF NOT EXISTS profile_id UUID REFERENCES profiles(id) ON DELETE CASCADE;
+
+ALTER TABLE playback_progress DROP CONSTRAINT IF EXISTS playback_progress_pkey;
+ALTER TABLE playback_progress
+ADD CONSTRAINT playback_progress_user_profile_media_key
+UNIQUE NULLS NOT DISTINCT (user_id, profile_id, media_item_id);
+
+ALTER TABLE watch_history
+ADD COLUMN IF NOT EXISTS profile_id UUID REFERENCES profiles(id) ON DELETE CASCADE;
+
+ALTER TABLE collections
+ADD COLUMN IF NOT EXISTS profile_id UUID REFERENCES profiles(id) ON DELETE CASCADE;
+
+DROP INDEX IF EXISTS idx_collections_builtin;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_collections_builtin ON collections(user_id, profile_id, kind)
+NULLS NOT DISTINCT WHERE kind <> 'custom';
+
+-- Create indexes for better performance
+CREATE UNIQUE INDEX IF NOT EXISTS idx_profiles_user_name ON profiles(user_id, LOWER(name));
+CREATE INDEX IF NOT EXISTS idx_watch_history_profile_watched_at
+ON watch_history(user_id, profile_id, watched_at DESC);
+CREATE INDEX IF NOT EXISTS idx_playback_progress_profile_continue_watching
+ON playback_progress(user_id, profile_id, last_watched_at DESC)
+WHERE NOT finished;
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_profiles_updated_at BEFORE UPDATE ON profiles
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
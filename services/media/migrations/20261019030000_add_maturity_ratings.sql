@@ -0,0 +1,22 @@
+-- Add maturity ratings to media items, on the scale of profile maturity levels
+ALTER TABLE media_items
+ADD COLUMN IF NOT EXISTS maturity_rating VARCHAR(20)
+    CHECK (maturity_rating IN ('all', 'kids', 'teen', 'adult'));
+
+-- Where the rating came from; ratings set by an admin are never overwritten
+-- by sidecar metadata
+ALTER TABLE media_items
+ADD COLUMN IF NOT EXISTS maturity_rating_source VARCHAR(20)
+    CHECK (maturity_rating_source IN ('admin', 'sidecar'));
+
+-- Add parental controls to accounts: the maximum rating for tokens without a
+-- selected profile and the hashed PIN needed to change any limit
+ALTER TABLE users
+ADD COLUMN IF NOT EXISTS max_maturity_rating VARCHAR(20)
+    CHECK (max_maturity_rating IN ('all', 'kids', 'teen', 'adult'));
+ALTER TABLE users ADD COLUMN IF NOT EXISTS parental_pin_hash TEXT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS parental_pin_failures INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS parental_pin_locked_until TIMESTAMPTZ;
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_media_items_maturity_rating ON media_items(maturity_rating);
@@ -0,0 +1,17 @@
+-- Stream resumable (tus) uploads into S3 multipart uploads instead of
+-- staging them on the local disk of one API instance
+ALTER TABLE tus_uploads ADD COLUMN IF NOT EXISTS s3_key VARCHAR(500);
+ALTER TABLE tus_uploads ADD COLUMN IF NOT EXISTS multipart_upload_id VARCHAR(1024);
+ALTER TABLE tus_uploads ADD COLUMN IF NOT EXISTS part_size BIGINT;
+
+-- Incomplete uploads staged on disk cannot be resumed; clients restart them
+DELETE FROM tus_uploads WHERE completed_at IS NULL;
+
+-- Create table for the uploaded parts of each resumable upload; every part
+-- but the last is exactly part_size bytes
+CREATE TABLE IF NOT EXISTS tus_upload_parts (
+    upload_id UUID NOT NULL REFERENCES tus_uploads(id) ON DELETE CASCADE,
+    part_number INTEGER NOT NULL,
+    etag VARCHAR(255) NOT NULL,
+    PRIMARY KEY (upload_id, part_number)
+);
@@ -1,4 +1,4 @@
-use crate::models::{MediaItem, S3ObjectInfo};
+use crate::models::{DeletedMediaItem, MediaItem, S3ObjectInfo};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use sqlx::{PgPool, Row, types::Uuid};
@@ -23,6 +23,37 @@ impl Database {
         Ok(keys)
     }
 
+    /// Keys of uploads that are still in progress and must not be processed yet
+    pub async fn get_awaiting_upload_keys(&self) -> Result<Vec<String>> {
+        let rows = sqlx::query(
+            "SELECT s3_key FROM media_items WHERE status = 'awaiting_upload'
+             UNION
+             SELECT s3_key FROM tus_uploads WHERE completed_at IS NULL AND s3_key IS NOT NULL",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let keys = rows.into_iter().map(|row| row.get("s3_key")).collect();
+
+        Ok(keys)
+    }
+
+    /// Find the media item created for an uploaded object, if any
+    pub async fn find_media_item_by_s3_key(&self, s3_key: &str) -> Result<Option<MediaItem>> {
+        let item = sqlx::query_as::<_, MediaItem>(
+            "SELECT id, type AS media_type, metadata, s3_key, status, user_id, created_at, updated_at, duration, width, height, video_codec, audio_codec, format, bitrate, sample_rate, channels, thumbnail_url
+             FROM media_items
+             WHERE s3_key = $1 AND deleted_at IS NULL
+             ORDER BY created_at DESC
+             LIMIT 1",
+        )
+        .bind(s3_key)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(item)
+    }
+
     pub async fn mark_object_as_processed(&self, s3_key: &str, etag: &str) -> Result<()> {
         sqlx::query(
             "INSERT INTO processed_s3_objects (s3_key, etag)
@@ -83,6 +114,77 @@ impl Database {
         Ok(())
     }
 
+    /// Link a media item to the season of a series as an episode
+    ///
+    /// The series and season are created when they do not exist yet. Items
+    /// that have already been classified are left alone.
+    pub async fn link_episode(
+        &self,
+        media_id: Uuid,
+        series_title: &str,
+        season: i32,
+        episode: i32,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let series_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO series (title) VALUES ($1)
+             ON CONFLICT ((LOWER(title))) DO UPDATE SET title = series.title
+             RETURNING id",
+        )
+        .bind(series_title)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let season_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO seasons (series_id, number) VALUES ($1, $2)
+             ON CONFLICT (series_id, number) DO UPDATE SET number = seasons.number
+             RETURNING id",
+        )
+        .bind(series_id)
+        .bind(season)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "UPDATE media_items SET kind = 'episode', season_id = $2, episode_number = $3
+             WHERE id = $1 AND kind IS NULL",
+        )
+        .bind(media_id)
+        .bind(season_id)
+        .bind(episode)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Mark a media item that has not been classified yet as a movie
+    pub async fn mark_as_movie(&self, media_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE media_items SET kind = 'movie' WHERE id = $1 AND kind IS NULL")
+            .bind(media_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Set a maturity rating read from sidecar metadata, unless an admin rated the item
+    pub async fn set_sidecar_maturity_rating(&self, media_id: Uuid, rating: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE media_items SET maturity_rating = $1, maturity_rating_source = 'sidecar'
+             WHERE id = $2 AND maturity_rating_source IS DISTINCT FROM 'admin'",
+        )
+        .bind(rating)
+        .bind(media_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn update_media_item_status(&self, id: Uuid, status: &str) -> Result<()> {
         sqlx::query("UPDATE media_items SET status = $1, updated_at = NOW() WHERE id = $2")
             .bind(status)
@@ -92,4 +194,52 @@ impl Database {
 
         Ok(())
     }
+
+    pub async fn get_expired_deleted_items(
+        &self,
+        retention_days: i64,
+    ) -> Result<Vec<DeletedMediaItem>> {
+        let items = sqlx::query_as::<_, DeletedMediaItem>(
+            "SELECT id, s3_key, thumbnail_url FROM media_items
+             WHERE deleted_at IS NOT NULL AND deleted_at < NOW() - make_interval(days => $1)",
+        )
+        .bind(retention_days as i32)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(items)
+    }
+
+    /// Get the S3 keys of all HLS rendition segments of a media item
+    pub async fn get_rendition_segment_keys(&self, media_id: Uuid) -> Result<Vec<String>> {
+        let keys = sqlx::query_scalar(
+            "SELECT s.s3_key FROM media_rendition_segments s
+             JOIN media_renditions r ON r.id = s.rendition_id
+             WHERE r.media_item_id = $1",
+        )
+        .bind(media_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(keys)
+    }
+
+    pub async fn purge_media_item(&self, id: Uuid, s3_key: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM media_items WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        // Forget the object so a new upload under the same key gets processed
+        sqlx::query("DELETE FROM processed_s3_objects WHERE s3_key = $1")
+            .bind(s3_key)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
 }
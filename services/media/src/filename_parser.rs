@@ -0,0 +1,211 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// `Show.Name.S01E02.Episode.Title` and `Show Name - s1e2 - Title`
+static SEASON_EPISODE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?P<series>.+?)[ ._-]+s(?P<season>\d{1,2})[ ._-]?e(?P<episode>\d{1,3})(?:[ ._-]+(?P<title>.*))?$")
+        .unwrap()
+});
+
+/// `Show Name 1x02 Episode Title`
+static CROSS_NUMBERED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?P<series>.+?)[ ._-]+(?P<season>\d{1,2})x(?P<episode>\d{1,3})(?:[ ._-]+(?P<title>.*))?$")
+        .unwrap()
+});
+
+/// Season directory such as `Season 1` or `S01`
+static SEASON_DIRECTORY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^(?:season|s)[ ._-]*(?P<season>\d{1,2})$").unwrap());
+
+/// Episode file inside a season directory, such as `03 - Title` or `E03`
+static EPISODE_FILE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?:e|ep|episode)?[ ._-]*(?P<episode>\d{1,3})(?:[ ._-]+(?P<title>.*))?$")
+        .unwrap()
+});
+
+/// `Movie.Name.2010.1080p` and `Movie Name (2010)`
+static MOVIE_YEAR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?P<title>.+?)[ ._-]*[(\[]?(?P<year>(?:19|20)\d{2})[)\]]?(?:[ ._-].*)?$").unwrap()
+});
+
+/// Release tags that end a title, such as `1080p` or `WEB-DL`
+static RELEASE_TAG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:^|[ ._-])(?:\d{3,4}p|4k|web(?:-?dl|rip)?|blu-?ray|bdrip|hdtv|dvdrip|x26[45]|h\.?26[45]|hevc)(?:[ ._-]|$)")
+        .unwrap()
+});
+
+/// What a media file's name says about its content
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParsedFilename {
+    Episode {
+        series: String,
+        season: i32,
+        episode: i32,
+        title: Option<String>,
+    },
+    Movie {
+        title: String,
+        year: Option<i32>,
+    },
+}
+
+impl ParsedFilename {
+    /// Title to give a newly discovered media item
+    pub fn display_title(&self) -> String {
+        match self {
+            ParsedFilename::Episode {
+                series,
+                season,
+                episode,
+                title,
+            } => title
+                .clone()
+                .unwrap_or_else(|| format!("{} S{:02}E{:02}", series, season, episode)),
+            ParsedFilename::Movie { title, .. } => title.clone(),
+        }
+    }
+}
+
+/// Parse the object key of a video into episode or movie information
+///
+/// Episodes are recognised by `S01E02` or `1x02` markers in the file name,
+/// or by a `Show/Season 1/02 - Title` directory layout. Everything else is
+/// treated as a movie, with the release year split off when present.
+pub fn parse_filename(key: &str) -> ParsedFilename {
+    let mut segments = key.rsplit('/');
+    let file_name = segments.next().unwrap_or(key);
+    let stem = file_name
+        .rsplit_once('.')
+        .map_or(file_name, |(stem, _)| stem);
+
+    for pattern in [&*SEASON_EPISODE, &*CROSS_NUMBERED] {
+        if let Some(captures) = pattern.captures(stem) {
+            let series = clean(&captures["series"]);
+            if !series.is_empty() {
+                return ParsedFilename::Episode {
+                    series,
+                    season: captures["season"].parse().unwrap_or(0),
+                    episode: captures["episode"].parse().unwrap_or(0),
+                    title: captures.name("title").and_then(|t| clean_title(t.as_str())),
+                };
+            }
+        }
+    }
+
+    if let (Some(season_dir), Some(series_dir)) = (segments.next(), segments.next())
+        && let Some(season) = SEASON_DIRECTORY.captures(season_dir)
+        && let Some(episode) = EPISODE_FILE.captures(stem)
+    {
+        let series = clean(series_dir);
+        if !series.is_empty() {
+            return ParsedFilename::Episode {
+                series,
+                season: season["season"].parse().unwrap_or(0),
+                episode: episode["episode"].parse().unwrap_or(0),
+                title: episode.name("title").and_then(|t| clean_title(t.as_str())),
+            };
+        }
+    }
+
+    if let Some(captures) = MOVIE_YEAR.captures(stem) {
+        let title = clean(&captures["title"]);
+        if !title.is_empty() {
+            return ParsedFilename::Movie {
+                title,
+                year: captures["year"].parse().ok(),
+            };
+        }
+    }
+
+    ParsedFilename::Movie {
+        title: clean_title(stem).unwrap_or_else(|| stem.to_string()),
+        year: None,
+    }
+}
+
+/// Turn dot and underscore separated words into a readable name
+fn clean(name: &str) -> String {
+    name.replace(['.', '_'], " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| c == '-' || c.is_whitespace())
+        .to_string()
+}
+
+/// Clean a title, cutting it off at the first release tag
+fn clean_title(title: &str) -> Option<String> {
+    let title = match RELEASE_TAG.find(title) {
+        Some(tag) => &title[..tag.start()],
+        None => title,
+    };
+    let title = clean(title);
+    (!title.is_empty()).then_some(title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn episode(series: &str, season: i32, episode: i32, title: Option<&str>) -> ParsedFilename {
+        ParsedFilename::Episode {
+            series: series.to_string(),
+            season,
+            episode,
+            title: title.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_parse_episodes() {
+        assert_eq!(
+            parse_filename("tv/The.Expanse.S02E05.Home.1080p.WEB-DL.mkv"),
+            episode("The Expanse", 2, 5, Some("Home"))
+        );
+        assert_eq!(
+            parse_filename("Doctor Who - s1e13 - The Parting of the Ways.mp4"),
+            episode("Doctor Who", 1, 13, Some("The Parting of the Ways"))
+        );
+        assert_eq!(
+            parse_filename("Firefly 1x03.avi"),
+            episode("Firefly", 1, 3, None)
+        );
+        assert_eq!(
+            parse_filename("shows/Twin Peaks/Season 2/07 - Lonely Souls.mkv"),
+            episode("Twin Peaks", 2, 7, Some("Lonely Souls"))
+        );
+        assert_eq!(
+            parse_filename("Twin Peaks/S00/E01.mkv"),
+            episode("Twin Peaks", 0, 1, None)
+        );
+    }
+
+    #[test]
+    fn test_parse_movies() {
+        assert_eq!(
+            parse_filename("movies/Blade.Runner.1982.1080p.BluRay.x264.mkv"),
+            ParsedFilename::Movie {
+                title: "Blade Runner".to_string(),
+                year: Some(1982)
+            }
+        );
+        assert_eq!(
+            parse_filename("Arrival (2016).mp4"),
+            ParsedFilename::Movie {
+                title: "Arrival".to_string(),
+                year: Some(2016)
+            }
+        );
+        assert_eq!(
+            parse_filename("home_video.mov"),
+            ParsedFilename::Movie {
+                title: "home video".to_string(),
+                year: None
+            }
+        );
+        assert_eq!(
+            parse_filename("Firefly 1x03.avi").display_title(),
+            "Firefly S01E03"
+        );
+    }
+}
@@ -25,6 +25,13 @@ pub struct MediaItem {
     pub thumbnail_url: Option<String>,
 }
 
+#[derive(Debug, Clone, FromRow)]
+pub struct DeletedMediaItem {
+    pub id: Uuid,
+    pub s3_key: String,
+    pub thumbnail_url: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct S3ObjectInfo {
     pub key: String,
@@ -1,6 +1,8 @@
 use crate::database::Database;
+use crate::filename_parser::{ParsedFilename, parse_filename};
 use crate::metadata_extractor::MetadataExtractor;
 use crate::models::{MediaItem, MediaMetadata, S3ObjectInfo};
+use crate::sidecar::{is_sidecar, maturity_rating, sidecar_key};
 use crate::thumbnail_generator::ThumbnailGenerator;
 use anyhow::Result;
 use aws_sdk_s3::{Client, types::Object};
@@ -9,7 +11,11 @@ use sqlx::types::Uuid;
 use std::time::Duration;
 use tokio::time::sleep;
 use tokio_cron_scheduler::{Job, JobScheduler};
-use tracing::{error, info};
+use tracing::{error, info, warn};
+
+/// Prefix of the objects the API keeps resumable upload bytes short of a
+/// full part in
+const TUS_PENDING_PREFIX: &str = "tus-pending/";
 
 pub struct S3Poller {
     s3_client: Client,
@@ -44,9 +50,12 @@ impl S3Poller {
 
         // Get list of already processed files from database
         let processed_files = self.database.get_processed_files().await?;
-        let processed_files_set: std::collections::HashSet<String> =
+        let mut processed_files_set: std::collections::HashSet<String> =
             processed_files.into_iter().collect();
 
+        // Objects of uploads that have not been completed yet are skipped too
+        processed_files_set.extend(self.database.get_awaiting_upload_keys().await?);
+
         loop {
             let mut request = self.s3_client.list_objects_v2().bucket(&self.bucket_name);
 
@@ -64,6 +73,16 @@ impl S3Poller {
                             continue;
                         }
 
+                        // Sidecar metadata is read along with its media object
+                        if is_sidecar(key) {
+                            continue;
+                        }
+
+                        // Bytes of resumable uploads waiting for a full part
+                        if key.starts_with(TUS_PENDING_PREFIX) {
+                            continue;
+                        }
+
                         // Convert to our S3ObjectInfo struct
                         let object_info = S3ObjectInfo {
                             key: key.clone(),
@@ -153,18 +172,31 @@ impl S3Poller {
             .generate_thumbnail(&object.key, &temp_file_path)
             .await?;
 
+        // Uploads made through the API already have a media item owned by the uploader
+        let existing = self.database.find_media_item_by_s3_key(&object.key).await?;
+        let parsed = parse_filename(&object.key);
+
         // Create MediaItem with metadata and thumbnail URL
         let media_item = MediaItem {
-            id: Uuid::new_v4(),
-            media_type: "video".to_string(), // TODO: Determine media type from file extension or metadata
-            metadata: serde_json::json!({
-                "title": object.key.split('/').last().unwrap_or(&object.key), // Simple title from filename
-                // Add other metadata fields as needed
-            }),
+            id: existing.as_ref().map_or_else(Uuid::new_v4, |item| item.id),
+            media_type: existing
+                .as_ref()
+                .map_or_else(|| "video".to_string(), |item| item.media_type.clone()), // TODO: Determine media type from file extension or metadata
+            metadata: match &existing {
+                Some(item) => item.metadata.clone(),
+                None => serde_json::json!({
+                    "title": parsed.display_title(),
+                    // Add other metadata fields as needed
+                }),
+            },
             s3_key: object.key.clone(),
             status: "processed".to_string(),
-            user_id: Uuid::new_v4(), // TODO: Associate with actual user
-            created_at: Utc::now(),
+            user_id: existing
+                .as_ref()
+                .map_or_else(Uuid::new_v4, |item| item.user_id), // TODO: Associate objects dropped into the bucket with a user
+            created_at: existing
+                .as_ref()
+                .map_or_else(Utc::now, |item| item.created_at),
             updated_at: Utc::now(),
             duration: metadata.duration,
             width: metadata.width,
@@ -184,6 +216,29 @@ impl S3Poller {
         // Save to database
         self.database.save_media_item(&media_item).await?;
 
+        if let Some(rating) = self.sidecar_maturity_rating(&object.key).await? {
+            self.database
+                .set_sidecar_maturity_rating(media_item.id, rating)
+                .await?;
+        }
+
+        // Place videos in the series hierarchy based on their file name
+        if media_item.media_type == "video" {
+            match &parsed {
+                ParsedFilename::Episode {
+                    series,
+                    season,
+                    episode,
+                    ..
+                } => {
+                    self.database
+                        .link_episode(media_item.id, series, *season, *episode)
+                        .await?
+                }
+                ParsedFilename::Movie { .. } => self.database.mark_as_movie(media_item.id).await?,
+            }
+        }
+
         // Mark object as processed
         self.database
             .mark_object_as_processed(&object.key, &object.etag)
@@ -192,6 +247,34 @@ impl S3Poller {
         Ok(())
     }
 
+    /// Read the maturity rating from the sidecar metadata of an object, if any
+    async fn sidecar_maturity_rating(&self, key: &str) -> Result<Option<&'static str>> {
+        let key = sidecar_key(key);
+        let response = match self
+            .s3_client
+            .get_object()
+            .bucket(&self.bucket_name)
+            .key(&key)
+            .send()
+            .await
+        {
+            Ok(response) => response,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => {
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let body = response.body.collect().await?.into_bytes();
+        match serde_json::from_slice(&body) {
+            Ok(sidecar) => Ok(maturity_rating(&sidecar)),
+            Err(e) => {
+                warn!("Ignoring invalid sidecar metadata {}: {}", key, e);
+                Ok(None)
+            }
+        }
+    }
+
     pub async fn start_polling(&self, schedule: &str) -> Result<()> {
         // Clone self for use in the async closure
         let poller = self.clone();
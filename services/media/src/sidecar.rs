@@ -0,0 +1,99 @@
+//! Sidecar metadata stored next to media objects
+//!
+//! A media object such as `movies/Alien.mkv` may come with a JSON file of
+//! the same name, `movies/Alien.json`, describing it. Only the content
+//! rating is used so far.
+
+/// Extension of sidecar metadata files
+const SIDECAR_EXTENSION: &str = "json";
+
+/// Fields of sidecar metadata that may hold a content rating, by priority
+const RATING_FIELDS: [&str; 4] = ["maturity_rating", "content_rating", "certification", "mpaa"];
+
+/// Check whether an object is a sidecar metadata file
+pub fn is_sidecar(key: &str) -> bool {
+    extension(key).is_some_and(|extension| extension.eq_ignore_ascii_case(SIDECAR_EXTENSION))
+}
+
+/// Key of the sidecar metadata file of a media object
+pub fn sidecar_key(key: &str) -> String {
+    let stem = match extension(key) {
+        Some(extension) => &key[..key.len() - extension.len() - 1],
+        None => key,
+    };
+    format!("{}.{}", stem, SIDECAR_EXTENSION)
+}
+
+/// Extension of the file name of a key, if any
+fn extension(key: &str) -> Option<&str> {
+    let name = key.rsplit('/').next().unwrap_or(key);
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => Some(extension),
+        _ => None,
+    }
+}
+
+/// Get the maturity rating described by sidecar metadata
+pub fn maturity_rating(sidecar: &serde_json::Value) -> Option<&'static str> {
+    RATING_FIELDS
+        .iter()
+        .filter_map(|field| sidecar.get(field)?.as_str())
+        .find_map(parse_maturity_rating)
+}
+
+/// Map a content rating to a maturity rating (`all`, `kids`, `teen` or `adult`)
+///
+/// Understands the maturity ratings themselves, MPAA and US TV ratings and
+/// minimum ages such as `12` or `16+`.
+pub fn parse_maturity_rating(rating: &str) -> Option<&'static str> {
+    let rating = rating.trim().to_ascii_uppercase().replace([' ', '_'], "-");
+
+    let level = match rating.as_str() {
+        "ALL" | "G" | "TV-Y" | "TV-G" | "U" => "all",
+        "KIDS" | "PG" | "TV-Y7" | "TV-Y7-FV" | "TV-PG" => "kids",
+        "TEEN" | "PG-13" | "TV-14" => "teen",
+        "ADULT" | "R" | "NC-17" | "TV-MA" | "X" => "adult",
+        _ => {
+            let age: u32 = rating.trim_end_matches('+').parse().ok()?;
+            match age {
+                0..=6 => "all",
+                7..=12 => "kids",
+                13..=17 => "teen",
+                _ => "adult",
+            }
+        }
+    };
+
+    Some(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sidecar_key() {
+        assert_eq!(sidecar_key("movies/Alien.mkv"), "movies/Alien.json");
+        assert_eq!(sidecar_key("Show.S01E02.mp4"), "Show.S01E02.json");
+        assert_eq!(sidecar_key("dir.v2/clip"), "dir.v2/clip.json");
+        assert!(is_sidecar("movies/Alien.JSON"));
+        assert!(!is_sidecar("movies/Alien.mkv"));
+        assert!(!is_sidecar("movies/.json"));
+    }
+
+    #[test]
+    fn test_parse_maturity_rating() {
+        assert_eq!(parse_maturity_rating("G"), Some("all"));
+        assert_eq!(parse_maturity_rating("tv-y7"), Some("kids"));
+        assert_eq!(parse_maturity_rating("PG 13"), Some("teen"));
+        assert_eq!(parse_maturity_rating("TV-MA"), Some("adult"));
+        assert_eq!(parse_maturity_rating("12"), Some("kids"));
+        assert_eq!(parse_maturity_rating("16+"), Some("teen"));
+        assert_eq!(parse_maturity_rating("teen"), Some("teen"));
+        assert_eq!(parse_maturity_rating("unrated"), None);
+
+        let sidecar = serde_json::json!({"certification": "R", "content_rating": "bogus"});
+        assert_eq!(maturity_rating(&sidecar), Some("adult"));
+        assert_eq!(maturity_rating(&serde_json::json!({"rating": 4.5})), None);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::database::Database;
+use crate::models::DeletedMediaItem;
+use anyhow::Result;
+use aws_sdk_s3::{
+    Client,
+    types::{Delete, ObjectIdentifier},
+};
+use tokio_cron_scheduler::{Job, JobScheduler};
+use tracing::{error, info};
+
+/// Maximum number of keys S3 deletes in one request
+const DELETE_BATCH_SIZE: usize = 1000;
+
+/// Permanently removes media items that stayed in the trash past the retention period
+#[derive(Clone)]
+pub struct TrashPurger {
+    s3_client: Client,
+    bucket_name: String,
+    database: Database,
+    retention_days: i64,
+}
+
+impl TrashPurger {
+    pub fn new(
+        s3_client: Client,
+        bucket_name: String,
+        database: Database,
+        retention_days: i64,
+    ) -> Self {
+        Self {
+            s3_client,
+            bucket_name,
+            database,
+            retention_days,
+        }
+    }
+
+    /// Purge all expired items, returning how many were removed
+    pub async fn purge_expired(&self) -> Result<usize> {
+        let items = self
+            .database
+            .get_expired_deleted_items(self.retention_days)
+            .await?;
+
+        let mut purged = 0;
+        for item in items {
+            match self.purge_item(&item).await {
+                Ok(()) => purged += 1,
+                Err(e) => error!("Failed to purge media item {}: {}", item.id, e),
+            }
+        }
+
+        Ok(purged)
+    }
+
+    async fn purge_item(&self, item: &DeletedMediaItem) -> Result<()> {
+        info!("Purging media item {} ({})", item.id, item.s3_key);
+
+        // Storage is removed first so a failure leaves the row to retry later
+        self.s3_client
+            .delete_object()
+            .bucket(&self.bucket_name)
+            .key(&item.s3_key)
+            .send()
+            .await?;
+
+        if let Some((bucket, key)) = item.thumbnail_url.as_deref().and_then(parse_s3_url) {
+            self.s3_client
+                .delete_object()
+                .bucket(bucket)
+                .key(key)
+                .send()
+                .await?;
+        }
+
+        // Segment rows cascade with the item, so their objects go first
+        let segment_keys = self.database.get_rendition_segment_keys(item.id).await?;
+        for keys in segment_keys.chunks(DELETE_BATCH_SIZE) {
+            self.delete_objects(keys).await?;
+        }
+
+        self.database
+            .purge_media_item(item.id, &item.s3_key)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete up to `DELETE_BATCH_SIZE` objects from the media bucket
+    async fn delete_objects(&self, keys: &[String]) -> Result<()> {
+        let objects = keys
+            .iter()
+            .map(|key| ObjectIdentifier::builder().key(key).build())
+            .collect::<Result<Vec<_>, _>>()?;
+        let delete = Delete::builder()
+            .set_objects(Some(objects))
+            .quiet(true)
+            .build()?;
+
+        let output = self
+            .s3_client
+            .delete_objects()
+            .bucket(&self.bucket_name)
+            .delete(delete)
+            .send()
+            .await?;
+
+        if let Some(failed) = output.errors().first() {
+            anyhow::bail!(
+                "Failed to delete {} of {} segments, e.g. {}: {}",
+                output.errors().len(),
+                keys.len(),
+                failed.key().unwrap_or_default(),
+                failed.message().unwrap_or_default()
+            );
+        }
+
+        Ok(())
+    }
+
+    pub async fn start_purging(&self, schedule: &str) -> Result<()> {
+        let purger = self.clone();
+
+        let scheduler = JobScheduler::new().await?;
+
+        let job = Job::new_async(schedule, move |_, _| {
+            let purger = purger.clone();
+            Box::pin(async move {
+                info!("Trash purge job executed");
+                match purger.purge_expired().await {
+                    Ok(purged) => info!("Purged {} media items from the trash", purged),
+                    Err(e) => error!("Failed to purge trash: {}", e),
+                }
+            })
+        })?;
+
+        scheduler.add(job).await?;
+        scheduler.start().await?;
+
+        info!(
+            "Started trash purge scheduler with schedule: {} (retention: {} days)",
+            schedule, self.retention_days
+        );
+        Ok(())
+    }
+}
+
+/// Split an `s3://bucket/key` URL into bucket and key
+fn parse_s3_url(url: &str) -> Option<(&str, &str)> {
+    let (bucket, key) = url.strip_prefix("s3://")?.split_once('/')?;
+    if bucket.is_empty() || key.is_empty() {
+        return None;
+    }
+    Some((bucket, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_s3_url() {
+        assert_eq!(
+            parse_s3_url("s3://thumbnail-bucket/videos/movie.jpg"),
+            Some(("thumbnail-bucket", "videos/movie.jpg"))
+        );
+        assert_eq!(parse_s3_url("https://example.com/movie.jpg"), None);
+        assert_eq!(parse_s3_url("s3://thumbnail-bucket/"), None);
+    }
+}